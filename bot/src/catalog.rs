@@ -0,0 +1,81 @@
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+use std::sync::RwLock;
+
+use crate::db::models::{GameClass, GameSp};
+use crate::db::repo;
+
+/* Game classes and their SPs. Loaded on ready and after every /class_catalog change; menus and
+   embeds are built synchronously, so they read this copy instead of the database. */
+struct Catalog {
+    classes: Vec<GameClass>,
+    sps: Vec<GameSp>,
+}
+
+static CATALOG: Lazy<RwLock<Catalog>> = Lazy::new(|| RwLock::new(Catalog { classes: Vec::new(), sps: Vec::new() }));
+
+pub async fn load(pool: &PgPool) -> anyhow::Result<()> {
+    let classes = repo::list_game_classes(pool).await?;
+    let sps = repo::list_game_sps(pool).await?;
+    let mut c = CATALOG.write().unwrap_or_else(|e| e.into_inner());
+    c.classes = classes;
+    c.sps = sps;
+    Ok(())
+}
+
+fn read<T>(f: impl FnOnce(&Catalog) -> T) -> T {
+    f(&CATALOG.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Classes offered in the join menu, in order.
+pub fn classes() -> Vec<GameClass> {
+    read(|c| c.classes.iter().filter(|k| k.is_active).cloned().collect())
+}
+
+/// Class by code, name or alias (case-insensitive), removed ones included so old signups still resolve.
+pub fn find_class(text: &str) -> Option<GameClass> {
+    let text = text.trim();
+    read(|c| {
+        c.classes
+            .iter()
+            .find(|k| {
+                k.code.eq_ignore_ascii_case(text)
+                    || k.name.eq_ignore_ascii_case(text)
+                    || k.aliases.iter().any(|a| a.eq_ignore_ascii_case(text))
+            })
+            .cloned()
+    })
+}
+
+/// SPs a class can pick, in order.
+pub fn sps_for(class: &str) -> Vec<GameSp> {
+    let Some(class) = find_class(class).filter(|k| k.is_active) else { return Vec::new(); };
+    read(|c| c.sps.iter().filter(|s| s.is_active && s.class_code == class.code).cloned().collect())
+}
+
+pub fn sp_allowed(class: &str, sp: &str) -> bool {
+    sps_for(class).iter().any(|s| s.code.eq_ignore_ascii_case(sp.trim()))
+}
+
+/// Guild emoji name for a class/SP pair, e.g. "MSW_SP1".
+pub fn emoji_name(class: &str, sp: &str) -> String {
+    let code = find_class(class).map(|k| k.code).unwrap_or_else(|| class.trim().to_ascii_uppercase());
+    let sp = sp.trim().to_ascii_uppercase();
+    let custom = read(|c| {
+        c.sps
+            .iter()
+            .find(|s| s.class_code == code && s.code == sp)
+            .and_then(|s| s.emoji_name.clone())
+    });
+    custom.unwrap_or_else(|| format!("{}_{}", code, sp))
+}
+
+/// All SPs of a class, removed ones included (for /class_catalog).
+pub fn all_sps_for(class_code: &str) -> Vec<GameSp> {
+    read(|c| c.sps.iter().filter(|s| s.class_code == class_code).cloned().collect())
+}
+
+/// All classes, removed ones included (for /class_catalog).
+pub fn all_classes() -> Vec<GameClass> {
+    read(|c| c.classes.clone())
+}
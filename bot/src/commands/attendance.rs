@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::all::*;
+use serenity::builder::{CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::models::Raid;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::guild_tz;
+use crate::utils::{from_user_id, mention_user, user_name_best};
+
+/* What the owner marks for each main once the raid is over */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Present,
+    Late,
+    NoShow,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Present, Status::Late, Status::NoShow];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Status::Present => "present",
+            Status::Late => "late",
+            Status::NoShow => "no_show",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Status> {
+        Status::ALL.into_iter().find(|s| s.key() == key)
+    }
+
+    /// Short form used in component ids
+    fn code(self) -> &'static str {
+        match self {
+            Status::Present => "p",
+            Status::Late => "l",
+            Status::NoShow => "n",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Present => "Present",
+            Status::Late => "Late",
+            Status::NoShow => "No-show",
+        }
+    }
+
+    fn emoji(self) -> &'static str {
+        match self {
+            Status::Present => "✅",
+            Status::Late => "⏰",
+            Status::NoShow => "❌",
+        }
+    }
+}
+
+// Panel state: players picked in the select, key = (owner_user_id, raid_id)
+static PANEL_PICK: Lazy<DashMap<(u64, Uuid), Vec<i64>>> = Lazy::new(DashMap::new);
+// Display names of the raid's mains, fetched once when the panel goes out
+static PANEL_NAMES: Lazy<DashMap<Uuid, HashMap<i64, String>>> = Lazy::new(DashMap::new);
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("attendance")
+            .description("Who showed up to past raids")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "user", "A player's attendance on this server")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "Player (default: you)"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "raid", "Attendance of one raid")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "raid_id", "Raid UUID").required(true))
+            )
+    ).await?;
+    Ok(())
+}
+
+/* === End-of-raid panel === */
+
+/// Sends the attendance panel to the raid owner (DM, or the raid channel if DMs are closed).
+/// Runs once per raid; cancelled raids and raids without mains get none.
+pub async fn send_panel(http: &Http, pool: &PgPool, raid_id: Uuid) -> anyhow::Result<()> {
+    let raid = repo::get_raid(pool, raid_id).await?;
+    if !raid.is_active {
+        return Ok(());
+    }
+    let mains = main_user_ids(pool, raid_id).await?;
+    if mains.is_empty() || !repo::claim_attendance_request(pool, raid_id).await? {
+        return Ok(());
+    }
+
+    let gid = GuildId::new(raid.guild_id as u64);
+    let mut names = HashMap::new();
+    for uid in &mains {
+        let name = match gid.member(http, UserId::new(*uid as u64)).await {
+            Ok(m) => m.display_name().to_string(),
+            Err(_) => format!("User {}", uid),
+        };
+        names.insert(*uid, name);
+    }
+    PANEL_NAMES.insert(raid_id, names);
+
+    let (content, rows) = render_panel(&raid, &mains, &HashMap::new(), &[]);
+    let msg = CreateMessage::new().content(content.clone()).components(rows);
+    let sent_dm = match UserId::new(raid.owner_id as u64).create_dm_channel(http).await {
+        Ok(dm) => dm.send_message(http, msg.clone()).await.is_ok(),
+        Err(_) => false,
+    };
+    if !sent_dm {
+        ChannelId::new(raid.chat_channel_id())
+            .send_message(http, msg.content(format!("{}\n{}", mention_user(raid.owner_id), content)))
+            .await?;
+    }
+    Ok(())
+}
+
+/* Distinct users with a main slot, in roster order */
+async fn main_user_ids(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<Vec<i64>> {
+    let mut ids: Vec<i64> = Vec::new();
+    for p in repo::list_participants(pool, raid_id).await?.into_iter().filter(|p| p.is_main) {
+        if !ids.contains(&p.user_id) {
+            ids.push(p.user_id);
+        }
+    }
+    Ok(ids)
+}
+
+fn name_of(raid_id: Uuid, user_id: i64) -> String {
+    PANEL_NAMES
+        .get(&raid_id)
+        .and_then(|n| n.get(&user_id).cloned())
+        .unwrap_or_else(|| format!("User {}", user_id))
+}
+
+fn render_panel(raid: &Raid, mains: &[i64], marks: &HashMap<i64, Status>, picked: &[i64]) -> (String, Vec<CreateActionRow>) {
+    let when = raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
+    let lines = mains
+        .iter()
+        .map(|uid| match marks.get(uid) {
+            Some(s) => format!("{} {} {}", s.emoji(), mention_user(*uid), s.label()),
+            None => format!("▫️ {} not marked", mention_user(*uid)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let unmarked = mains.iter().filter(|u| !marks.contains_key(u)).count();
+    let content = format!(
+        "📋 **Attendance for {}** ({})\nPick players, then mark them. {} not marked yet.\n{}",
+        raid.raid_name, when, unmarked, lines
+    );
+
+    // Unmarked players first so large rosters stay workable within the 25-option limit
+    let mut order: Vec<i64> = mains.to_vec();
+    order.sort_by_key(|u| marks.contains_key(u));
+    let options: Vec<CreateSelectMenuOption> = order
+        .iter()
+        .take(25)
+        .map(|uid| {
+            let mark = marks.get(uid).map(|s| format!(" ({})", s.label())).unwrap_or_default();
+            CreateSelectMenuOption::new(format!("{}{}", name_of(raid.id, *uid), mark), uid.to_string())
+                .default_selection(picked.contains(uid))
+        })
+        .collect();
+    let max = options.len() as u8;
+    let select = CreateSelectMenu::new(format!("r:atu:{}", raid.id), CreateSelectMenuKind::String { options })
+        .placeholder("Players to mark")
+        .min_values(1)
+        .max_values(max);
+
+    let mut buttons: Vec<CreateButton> = Status::ALL
+        .into_iter()
+        .map(|s| {
+            CreateButton::new(format!("r:atm:{}:{}", s.code(), raid.id))
+                .label(format!("{} {}", s.emoji(), s.label()))
+                .style(if s == Status::NoShow { ButtonStyle::Danger } else { ButtonStyle::Secondary })
+                .disabled(picked.is_empty())
+        })
+        .collect();
+    buttons.push(
+        CreateButton::new(format!("r:atr:{}", raid.id))
+            .label("Rest present")
+            .style(ButtonStyle::Success)
+            .disabled(unmarked == 0),
+    );
+    // The raid channel may be gone soon; drops can still be logged from here
+    buttons.push(
+        CreateButton::new(format!("r:lt:{}", raid.id))
+            .label("🎁 Log loot")
+            .style(ButtonStyle::Secondary),
+    );
+    (content, vec![CreateActionRow::SelectMenu(select), CreateActionRow::Buttons(buttons)])
+}
+
+async fn current_marks(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<HashMap<i64, Status>> {
+    Ok(repo::list_raid_attendance(pool, raid_id)
+        .await?
+        .into_iter()
+        .filter_map(|a| Status::from_key(&a.status).map(|s| (a.user_id, s)))
+        .collect())
+}
+
+/* Only the raid owner may mark */
+async fn owner_raid(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<Option<Raid>> {
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if raid.owner_id != from_user_id(it.user.id) {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("Only the raid owner can mark attendance.").ephemeral(true)
+        )).await?;
+        return Ok(None);
+    }
+    Ok(Some(raid))
+}
+
+async fn update_panel(ctx: &Context, it: &ComponentInteraction, raid: &Raid, picked: &[i64]) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let mains = main_user_ids(&pool, raid.id).await?;
+    let marks = current_marks(&pool, raid.id).await?;
+    // Names are kept in memory only; after a restart look them up again
+    if !PANEL_NAMES.contains_key(&raid.id) {
+        let mut names = HashMap::new();
+        for uid in &mains {
+            names.insert(*uid, user_name_best(ctx, Some(raid.guild_id as u64), *uid).await);
+        }
+        PANEL_NAMES.insert(raid.id, names);
+    }
+    let (content, rows) = render_panel(raid, &mains, &marks, picked);
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new().content(content).components(rows)
+    )).await?;
+    Ok(())
+}
+
+/* Select: remember who is picked */
+pub async fn pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let Some(raid) = owner_raid(ctx, it, raid_id).await? else { return Ok(()); };
+    let ComponentInteractionDataKind::StringSelect { values } = &it.data.kind else { return Ok(()); };
+    let picked: Vec<i64> = values.iter().filter_map(|v| v.parse().ok()).collect();
+    PANEL_PICK.insert((it.user.id.get(), raid_id), picked.clone());
+    update_panel(ctx, it, &raid, &picked).await
+}
+
+/* Buttons: mark the picked players (`code` = p/l/n), or everyone unmarked as present */
+pub async fn mark(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid, code: &str) -> anyhow::Result<()> {
+    let Some(raid) = owner_raid(ctx, it, raid_id).await? else { return Ok(()); };
+    let pool = pool_from_ctx(ctx).await?;
+
+    let (users, status) = match Status::ALL.into_iter().find(|s| s.code() == code) {
+        Some(s) => (PANEL_PICK.remove(&(it.user.id.get(), raid_id)).map(|(_, v)| v).unwrap_or_default(), s),
+        None => {
+            let marks = current_marks(&pool, raid_id).await?;
+            let rest = main_user_ids(&pool, raid_id).await?.into_iter().filter(|u| !marks.contains_key(u)).collect();
+            (rest, Status::Present)
+        }
+    };
+    if !users.is_empty() {
+        repo::upsert_attendance(&pool, raid_id, raid.guild_id, &users, status.key(), from_user_id(it.user.id)).await?;
+        super::points::award_attendance(&pool, &raid, &users, status, from_user_id(it.user.id)).await?;
+    }
+    update_panel(ctx, it, &raid, &[]).await
+}
+
+/* === /attendance === */
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let mut user = cmd.user.id;
+    let mut raid_id_s = String::new();
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("user", CommandDataOptionValue::User(u)) => user = *u,
+            ("raid_id", CommandDataOptionValue::String(v)) => raid_id_s = v.trim().to_string(),
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let reply = match sub.name.as_str() {
+        "user" => describe_user(&pool, gid, user).await?,
+        "raid" => {
+            let Ok(raid_id) = Uuid::parse_str(&raid_id_s) else {
+                cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Invalid raid_id")).await?; return Ok(());
+            };
+            describe_raid(&pool, gid, raid_id).await?
+        }
+        _ => return Ok(()),
+    };
+    let reply = if reply.chars().count() > 1900 { reply.chars().take(1890).collect::<String>() + "\n…" } else { reply };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+async fn describe_user(pool: &PgPool, gid: GuildId, user: UserId) -> anyhow::Result<String> {
+    let rows = repo::list_user_attendance(pool, gid.get() as i64, from_user_id(user)).await?;
+    if rows.is_empty() {
+        return Ok(format!("No attendance recorded for {} yet.", user.mention()));
+    }
+    let count = |s: Status| rows.iter().filter(|r| r.status == s.key()).count();
+    let tz = guild_tz(gid.get());
+    let recent = rows
+        .iter()
+        .take(15)
+        .map(|r| {
+            let s = Status::from_key(&r.status);
+            format!(
+                "{} {} — {} `{}`",
+                s.map(|s| s.emoji()).unwrap_or("?"),
+                r.scheduled_for.with_timezone(&tz).format("%Y-%m-%d"),
+                r.raid_name,
+                r.raid_id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(format!(
+        "**Attendance of {}** ({} raids)\n✅ present {} · ⏰ late {} · ❌ no-show {}\n\n{}",
+        user.mention(), rows.len(), count(Status::Present), count(Status::Late), count(Status::NoShow), recent
+    ))
+}
+
+async fn describe_raid(pool: &PgPool, gid: GuildId, raid_id: Uuid) -> anyhow::Result<String> {
+    let raid = match repo::get_raid(pool, raid_id).await {
+        Ok(r) if r.guild_id == gid.get() as i64 => r,
+        _ => return Ok("Raid not found on this server.".to_string()),
+    };
+    let rows = repo::list_raid_attendance(pool, raid_id).await?;
+    let when = raid.scheduled_for.with_timezone(&guild_tz(gid.get())).format("%Y-%m-%d %H:%M %Z");
+    if rows.is_empty() {
+        return Ok(format!("No attendance recorded for **{}** ({}).", raid.raid_name, when));
+    }
+    let mut out = format!("**Attendance for {}** ({})", raid.raid_name, when);
+    for s in Status::ALL {
+        let users: Vec<String> = rows.iter().filter(|r| r.status == s.key()).map(|r| mention_user(r.user_id)).collect();
+        if !users.is_empty() {
+            out.push_str(&format!("\n{} **{}** ({}): {}", s.emoji(), s.label(), users.len(), users.join(", ")));
+        }
+    }
+    out.push_str(&format!("\n_Marked by {}_", mention_user(rows[0].marked_by)));
+    Ok(out)
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use serenity::all::*;
+use serenity::builder::{CreateAttachment, EditInteractionResponse};
+use uuid::Uuid;
+
+use crate::db::repo::{self, GuildRaidListRow};
+use crate::handlers::pool_from_ctx;
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_calendar")
+            .description("Download upcoming raids as a calendar (.ics) file")
+            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "mine", "Only raids you're signed up for, with your status"))
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+    let mut mine = false;
+    for o in &cmd.data.options {
+        if o.name == "mine" {
+            if let CommandDataOptionValue::Boolean(b) = &o.value { mine = *b; }
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let mut rows = repo::list_active_raids_by_guild(&pool, gid.get() as i64).await?;
+    let signups = if mine {
+        let signups = repo::list_user_signups(&pool, gid.get() as i64, cmd.user.id.get() as i64).await?;
+        rows.retain(|r| signups.contains_key(&r.id));
+        Some(signups)
+    } else {
+        None
+    };
+
+    if rows.is_empty() {
+        let msg = if mine { "You're not signed up for any upcoming raid." } else { "No upcoming raids." };
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(msg)).await?;
+        return Ok(());
+    }
+
+    let guild_name = gid.name(&ctx.cache).unwrap_or_else(|| "Discord".to_string());
+    let ics = render_ics(&format!("{} raids", guild_name), &rows, signups.as_ref());
+    let file_name = if mine { "my-raids.ics" } else { "raids.ics" };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+        .content(format!("{} raid(s). Open the file or import it into your calendar app.", rows.len()))
+        .new_attachment(CreateAttachment::bytes(ics.into_bytes(), file_name))
+    ).await?;
+    Ok(())
+}
+
+/// iCalendar (RFC 5545) text for the given raids. With `signups`, each summary carries the
+/// user's MAIN/RESERVE status.
+pub fn render_ics(calendar_name: &str, rows: &[GuildRaidListRow], signups: Option<&HashMap<Uuid, bool>>) -> String {
+    let stamp = ics_time(chrono::Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Rust_bot_DC//Raids//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", ics_escape(calendar_name)),
+    ];
+
+    for r in rows {
+        let url = format!("https://discord.com/channels/{}/{}", r.guild_id, r.thread_id.unwrap_or(r.channel_id));
+        let summary = match signups.and_then(|s| s.get(&r.id)) {
+            Some(true) => format!("{} (MAIN)", r.raid_name),
+            Some(false) => format!("{} (RESERVE)", r.raid_name),
+            None => r.raid_name.clone(),
+        };
+        let description = if r.description.trim().is_empty() { url.clone() } else { format!("{}\n\n{}", r.description.trim(), url) };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", r.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", ics_time(r.scheduled_for)));
+        lines.push(format!("DURATION:PT{}M", r.duration_minutes));
+        lines.push(format!("SUMMARY:{}", ics_escape(&summary)));
+        lines.push(format!("DESCRIPTION:{}", ics_escape(&description)));
+        lines.push(format!("URL:{}", url));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&ics_fold(&line));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn ics_time(t: chrono::DateTime<chrono::Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/* TEXT values: backslash, comma, semicolon and newlines must be escaped */
+fn ics_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/* Lines longer than 75 octets continue on the next line after a space (never inside a UTF-8 char) */
+fn ics_fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out
+}
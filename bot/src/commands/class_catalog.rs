@@ -0,0 +1,184 @@
+use serenity::all::*;
+use serenity::builder::{CreateAutocompleteResponse, EditInteractionResponse};
+use sqlx::PgPool;
+
+use crate::catalog;
+use crate::db::models::{GameClass, GameSp};
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::utils::{is_bot_owner, parse_list_unique};
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("class_catalog")
+            .description("Game classes and SPs offered when joining (changes: bot owner only)")
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show classes and their SPs"))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "class", "Add a class or change one")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "code", "Short code, e.g. MSW").required(true).max_length(10))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Shown in the menu (default: code)").max_length(50))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "aliases", "Other spellings, comma separated"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "position", "Menu order").min_int_value(0))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "sp", "Add an SP to a class or change one")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class code").required(true).set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "code", "e.g. SP12").required(true).max_length(10))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Shown in the menu (default: code)").max_length(50))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "emoji", "Server emoji name (default: CLASS_SP)"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "position", "Menu order (default: last)").min_int_value(0))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove_sp", "Stop offering an SP")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class code").required(true).set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "code", "e.g. SP12").required(true))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove_class", "Stop offering a class")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class code").required(true).set_autocomplete(true))
+            )
+    ).await?;
+    Ok(())
+}
+
+pub async fn autocomplete_classes(ctx: &Context, ac: &CommandInteraction, typed: &str) -> anyhow::Result<()> {
+    let typed = typed.trim().to_lowercase();
+    let mut resp = CreateAutocompleteResponse::new();
+    for c in catalog::classes().into_iter().filter(|c| c.code.to_lowercase().contains(&typed) || c.name.to_lowercase().contains(&typed)).take(25) {
+        resp = resp.add_string_choice(format!("{} ({})", c.name, c.code), c.code);
+    }
+    ac.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp)).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    if cmd.guild_id.is_none() {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    }
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    // The catalog is shared by every server, so only the bot's owner may change it
+    if sub.name != "list" && !is_bot_owner(&ctx.http, cmd.user.id).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content("Only the bot's owner can change the class catalog: it is shared by every server.")
+        ).await?;
+        return Ok(());
+    }
+
+    let mut code = String::new();
+    let mut class = String::new();
+    let mut name: Option<String> = None;
+    let mut aliases: Option<String> = None;
+    let mut emoji: Option<String> = None;
+    let mut position: Option<i32> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("code", CommandDataOptionValue::String(v)) => code = v.trim().to_ascii_uppercase(),
+            ("class", CommandDataOptionValue::String(v)) => class = v.trim().to_string(),
+            ("name", CommandDataOptionValue::String(v)) => name = Some(v.trim().to_string()).filter(|v| !v.is_empty()),
+            ("aliases", CommandDataOptionValue::String(v)) => aliases = Some(v.clone()),
+            ("emoji", CommandDataOptionValue::String(v)) => emoji = Some(v.trim().trim_matches(':').to_string()).filter(|v| !v.is_empty()),
+            ("position", CommandDataOptionValue::Integer(v)) => position = Some(*v as i32),
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let reply = match sub.name.as_str() {
+        "list" => describe_all(),
+        "class" => {
+            let existing = catalog::find_class(&code).filter(|c| c.code == code);
+            let c = GameClass {
+                name: name.or(existing.as_ref().map(|c| c.name.clone())).unwrap_or_else(|| code.clone()),
+                aliases: aliases
+                    .map(|a| parse_list_unique(&a).into_iter().map(|s| s.to_lowercase()).collect())
+                    .or(existing.as_ref().map(|c| c.aliases.clone()))
+                    .unwrap_or_default(),
+                position: position
+                    .or(existing.as_ref().map(|c| c.position))
+                    .unwrap_or_else(|| catalog::all_classes().iter().map(|c| c.position + 1).max().unwrap_or(0)),
+                code,
+                is_active: true,
+            };
+            repo::upsert_game_class(&pool, &c).await?;
+            reload(&pool, format!("Class **{}** (`{}`) saved.", c.name, c.code)).await?
+        }
+        "sp" => {
+            let Some(class) = catalog::find_class(&class) else {
+                return edit(ctx, cmd, format!("Unknown class `{}`.", class)).await;
+            };
+            let all = catalog::all_sps_for(&class.code);
+            let existing = all.iter().find(|s| s.code == code);
+            let sp = GameSp {
+                class_code: class.code.clone(),
+                name: name.or(existing.map(|s| s.name.clone())).unwrap_or_else(|| code.clone()),
+                emoji_name: emoji.or(existing.and_then(|s| s.emoji_name.clone())),
+                position: position
+                    .or(existing.map(|s| s.position))
+                    .unwrap_or_else(|| all.iter().map(|s| s.position + 1).max().unwrap_or(0)),
+                code,
+                is_active: true,
+            };
+            repo::upsert_game_sp(&pool, &sp).await?;
+            reload(&pool, format!("{} **{}** (`{}`) saved.", class.code, sp.name, sp.code)).await?
+        }
+        "remove_sp" => {
+            let Some(class) = catalog::find_class(&class) else {
+                return edit(ctx, cmd, format!("Unknown class `{}`.", class)).await;
+            };
+            // Soft delete: signups that already use it keep showing it
+            if repo::deactivate_game_sp(&pool, &class.code, &code).await? {
+                reload(&pool, format!("{} {} is no longer offered.", class.code, code)).await?
+            } else {
+                format!("{} has no SP `{}`.", class.code, code)
+            }
+        }
+        "remove_class" => {
+            let Some(class) = catalog::find_class(&class) else {
+                return edit(ctx, cmd, format!("Unknown class `{}`.", class)).await;
+            };
+            if repo::deactivate_game_class(&pool, &class.code).await? {
+                reload(&pool, format!("Class {} is no longer offered.", class.code)).await?
+            } else {
+                format!("Class {} is already removed.", class.code)
+            }
+        }
+        _ => return Ok(()),
+    };
+    edit(ctx, cmd, reply).await
+}
+
+async fn edit(ctx: &Context, cmd: &CommandInteraction, content: String) -> anyhow::Result<()> {
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+async fn reload(pool: &PgPool, msg: String) -> anyhow::Result<String> {
+    catalog::load(pool).await?;
+    Ok(format!("{}\n\n{}", msg, describe_all()))
+}
+
+fn describe_all() -> String {
+    let mut out = String::from("**Classes and SPs**");
+    for c in catalog::classes() {
+        let sps = catalog::sps_for(&c.code)
+            .iter()
+            .map(|s| if s.name == s.code { s.code.clone() } else { format!("{} ({})", s.name, s.code) })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let aliases = if c.aliases.is_empty() { String::new() } else { format!(" — also: {}", c.aliases.join(", ")) };
+        out.push_str(&format!("\n**{}** `{}`{}\n{}", c.name, c.code, aliases, if sps.is_empty() { "no SPs".to_string() } else { sps }));
+    }
+    if out.chars().count() > 1900 {
+        out = out.chars().take(1890).collect::<String>() + "\n…";
+    }
+    out
+}
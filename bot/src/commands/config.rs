@@ -0,0 +1,287 @@
+use chrono_tz::{Tz, TZ_VARIANTS};
+use serenity::all::*;
+use serenity::builder::{CreateAutocompleteResponse, EditInteractionResponse};
+
+use crate::handlers::pool_from_ctx;
+use crate::settings;
+use crate::settings::{GuildRole, PromotionPolicy};
+use crate::utils::is_bot_admin;
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("config")
+            .description("Server settings for the raid bot (bot admins only)")
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "show", "Show current settings"))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "timezone", "Timezone used for raid dates and channel names")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "timezone", "IANA name, e.g. Europe/Warsaw").required(true).set_autocomplete(true))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "weekday_category", "Category for raids on a weekday")
+                    .add_sub_option(weekday_option())
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Channel, "category", "Leave empty to go back to matching by category name")
+                        .channel_types(vec![ChannelType::Category]))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "fallback_category", "Category used when the weekday one is missing or full")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Channel, "category", "Leave empty for no category")
+                        .channel_types(vec![ChannelType::Category]))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "max_channels", "Max raid channels per category")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "count", "0 = no limit")
+                        .required(true).min_int_value(0).max_int_value(50))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "channel_name", "Raid channel name template")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "template",
+                        "{emoji} {slug} {name} {date} {time} {weekday}; empty = default").max_length(100))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "thread_mode", "Post raids in one channel with a thread each")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Channel, "channel", "Raids channel; leave empty for one channel per raid")
+                        .channel_types(vec![ChannelType::Text]))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "role", "Which server role the bot treats as organiser, reserve, etc.")
+                    .add_sub_option(role_kind_option())
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Role, "role", "Leave empty to unset"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "promotion", "Order in which reserves move up when a slot frees")
+                    .add_sub_option(promotion_policy_option())
+            )
+    ).await?;
+    Ok(())
+}
+
+fn weekday_option() -> CreateCommandOption {
+    let mut opt = CreateCommandOption::new(CommandOptionType::String, "weekday", "Day of the raid (server time)").required(true);
+    for day in WEEKDAYS {
+        opt = opt.add_string_choice(day, day);
+    }
+    opt
+}
+
+fn role_kind_option() -> CreateCommandOption {
+    let mut opt = CreateCommandOption::new(CommandOptionType::String, "which", "Bot role to set").required(true);
+    for role in GuildRole::ALL {
+        opt = opt.add_string_choice(role.label(), role.key());
+    }
+    opt
+}
+
+fn promotion_policy_option() -> CreateCommandOption {
+    let mut opt = CreateCommandOption::new(CommandOptionType::String, "policy", "Reliability ranks recent no-shows lower and long bench streaks higher").required(true);
+    for policy in PromotionPolicy::ALL {
+        opt = opt.add_string_choice(policy.label(), policy.key());
+    }
+    opt
+}
+
+const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+pub async fn autocomplete_timezones(ctx: &Context, ac: &CommandInteraction, typed: &str) -> anyhow::Result<()> {
+    let typed = typed.trim().to_lowercase();
+    let mut resp = CreateAutocompleteResponse::new();
+    for tz in TZ_VARIANTS.iter().filter(|tz| tz.name().to_lowercase().contains(&typed)).take(25) {
+        resp = resp.add_string_choice(tz.name(), tz.name());
+    }
+    ac.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp)).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+
+    if !is_bot_admin(&ctx.http, cmd).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("Only {} or members with Manage Server can change server settings.", settings::role_mention(gid.get(), GuildRole::Admin)))
+        ).await?;
+        return Ok(());
+    }
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let reply = match sub.name.as_str() {
+        "show" => describe(gid),
+        "timezone" => handle_timezone(ctx, gid, opts).await?,
+        "weekday_category" | "fallback_category" | "max_channels" | "channel_name" | "thread_mode" => {
+            handle_channel_setting(ctx, gid, &sub.name, opts).await?
+        }
+        "role" => handle_role(ctx, gid, opts).await?,
+        "promotion" => handle_promotion(ctx, gid, opts).await?,
+        _ => return Ok(()),
+    };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+fn describe(gid: GuildId) -> String {
+    let s = settings::get(gid.get());
+    let mut out = format!(
+        "**Server settings**\nTimezone: `{}`\nReserve promotion: {}",
+        s.timezone,
+        PromotionPolicy::from_key(&s.promotion_policy).label()
+    );
+
+    out.push_str("\n\n**Raid channels**");
+    if let Some(id) = s.raid_thread_channel_id {
+        out.push_str(&format!("\nThread mode: raids are posted in <#{}> (placement below is unused)", id));
+    }
+    for day in WEEKDAYS {
+        let cat = match s.weekday_categories.get(day) {
+            Some(id) => format!("<#{}>", id),
+            None => "category named after the day".to_string(),
+        };
+        out.push_str(&format!("\n{}: {}", day, cat));
+    }
+    let fallback = s.fallback_category_id.map(|id| format!("<#{}>", id)).unwrap_or_else(|| "none".to_string());
+    let max = s.max_channels_per_category.filter(|m| *m > 0).map(|m| m.to_string()).unwrap_or_else(|| "no limit".to_string());
+    let template = s.channel_name_template.as_deref().unwrap_or(settings::DEFAULT_CHANNEL_NAME_TEMPLATE);
+    out.push_str(&format!("\nFallback: {}\nMax raid channels per category: {}\nName template: `{}`", fallback, max, template));
+
+    out.push_str("\n\n**Roles**");
+    for role in GuildRole::ALL {
+        let id = role.get(&s).map(|id| format!("<@&{}>", id)).unwrap_or_else(|| "not set".to_string());
+        out.push_str(&format!("\n{}: {}", role.label(), id));
+    }
+    out
+}
+
+async fn handle_role(ctx: &Context, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut which = None;
+    let mut role_id: Option<RoleId> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("which", CommandDataOptionValue::String(v)) => which = GuildRole::from_key(v),
+            ("role", CommandDataOptionValue::Role(r)) => role_id = Some(*r),
+            _ => {}
+        }
+    }
+    let Some(which) = which else { return Ok("Unknown bot role.".to_string()); };
+
+    let mut s = settings::get(gid.get());
+    which.set(&mut s, role_id.map(|r| r.get() as i64));
+    let pool = pool_from_ctx(ctx).await?;
+    settings::save(&pool, &s).await?;
+    Ok(match role_id {
+        Some(r) => format!("{} role set to <@&{}>.", which.label(), r),
+        None => format!("{} role unset.", which.label()),
+    })
+}
+
+async fn handle_promotion(ctx: &Context, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut policy = PromotionPolicy::JoinOrder;
+    for o in opts {
+        if let ("policy", CommandDataOptionValue::String(v)) = (o.name.as_str(), &o.value) {
+            policy = PromotionPolicy::from_key(v);
+        }
+    }
+
+    let mut s = settings::get(gid.get());
+    s.promotion_policy = policy.key().to_string();
+    let pool = pool_from_ctx(ctx).await?;
+    settings::save(&pool, &s).await?;
+    Ok(match policy {
+        PromotionPolicy::JoinOrder => "Reserves now move up in the order they joined.".to_string(),
+        PromotionPolicy::Reliability => "Reserves now move up by reliability: recent no-shows rank lower, long bench streaks higher, join time breaks ties. Raids can override this with `/raid_edit`.".to_string(),
+    })
+}
+
+async fn handle_channel_setting(ctx: &Context, gid: GuildId, sub: &str, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut weekday = String::new();
+    let mut category: Option<ChannelId> = None;
+    let mut count: i64 = 0;
+    let mut template: Option<String> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("weekday", CommandDataOptionValue::String(v)) => weekday = v.clone(),
+            ("category" | "channel", CommandDataOptionValue::Channel(c)) => category = Some(*c),
+            ("count", CommandDataOptionValue::Integer(v)) => count = *v,
+            ("template", CommandDataOptionValue::String(v)) => template = Some(v.trim().to_string()).filter(|t| !t.is_empty()),
+            _ => {}
+        }
+    }
+
+    let mut s = settings::get(gid.get());
+    let reply = match sub {
+        "weekday_category" => {
+            if !WEEKDAYS.contains(&weekday.as_str()) {
+                return Ok(format!("Unknown weekday `{}`.", weekday));
+            }
+            match category {
+                Some(c) => {
+                    s.weekday_categories.insert(weekday.clone(), c.get() as i64);
+                    format!("Raids on {} now go to <#{}>.", weekday, c)
+                }
+                None => {
+                    s.weekday_categories.remove(&weekday);
+                    format!("Raids on {} go to the category named after the day again.", weekday)
+                }
+            }
+        }
+        "fallback_category" => {
+            s.fallback_category_id = category.map(|c| c.get() as i64);
+            match category {
+                Some(c) => format!("Fallback category set to <#{}>.", c),
+                None => "Fallback category cleared.".to_string(),
+            }
+        }
+        "thread_mode" => {
+            s.raid_thread_channel_id = category.map(|c| c.get() as i64);
+            match category {
+                Some(c) => format!("New raids are posted in <#{}> with a thread each.", c),
+                None => "New raids get their own channel again.".to_string(),
+            }
+        }
+        "max_channels" => {
+            s.max_channels_per_category = Some(count as i32).filter(|m| *m > 0);
+            if count > 0 { format!("At most {} raid channels per category.", count) } else { "No limit on raid channels per category.".to_string() }
+        }
+        _ => {
+            s.channel_name_template = template;
+            let t = s.channel_name_template.as_deref().unwrap_or(settings::DEFAULT_CHANNEL_NAME_TEMPLATE);
+            let example = crate::commands::raid::render_channel_name(
+                t, "🦾", "armav2", "ArmaV2", chrono::Utc::now(), settings::guild_tz(gid.get()),
+            );
+            if example.trim().is_empty() {
+                return Ok("That template gives an empty channel name.".to_string());
+            }
+            format!("Channel name template set to `{}`.\nExample: `{}`", t, example)
+        }
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    settings::save(&pool, &s).await?;
+    // Only new raids (and edited times) are placed with the new settings
+    Ok(reply)
+}
+
+async fn handle_timezone(ctx: &Context, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut tz_name = String::new();
+    for o in opts {
+        if o.name == "timezone" {
+            if let CommandDataOptionValue::String(s) = &o.value { tz_name = s.trim().to_string(); }
+        }
+    }
+    let Ok(tz) = tz_name.parse::<Tz>() else {
+        return Ok(format!("Unknown timezone `{}`. Pick one from the list, e.g. `Europe/Warsaw`.", tz_name));
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    settings::set_timezone(&pool, gid.get(), tz).await?;
+
+    // Existing raid channels keep their names; lists and embeds pick the new zone on next refresh.
+    crate::commands::raid::trigger_refresh(ctx, gid.get()).await;
+    Ok(format!("Timezone set to `{}`. New raids and the raid list now use it.", tz.name()))
+}
@@ -0,0 +1,212 @@
+use serenity::all::*;
+use serenity::builder::EditInteractionResponse;
+use sqlx::PgPool;
+
+use crate::db::models::JoinRule;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::join_rules::{self, Access};
+use crate::settings::{role_mention, GuildRole};
+use crate::utils::is_bot_admin;
+
+fn raid_type_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "raid_type", "Only raids of this type (default: the whole server)")
+        .set_autocomplete(true)
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    let mut access = CreateCommandOption::new(CommandOptionType::String, "access", "What the role allows").required(true);
+    for a in Access::ALL {
+        access = access.add_string_choice(a.label(), a.key());
+    }
+
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("join_rule")
+            .description("Which roles may join raids (bot admins only)")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a rule, or change tag/position of an existing one")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Role, "role", "Members with this role").required(true))
+                    .add_sub_option(access)
+                    .add_sub_option(raid_type_option())
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "tag", "Shown after the name, e.g. [-c90]").max_length(20))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "position", "Lower is checked first (default: last)").min_int_value(0))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a rule")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "id", "Rule id from /join_rule list").required(true))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show the rules")
+                    .add_sub_option(raid_type_option())
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "reset", "Drop the rules of the server (back to defaults) or of a raid type")
+                    .add_sub_option(raid_type_option())
+            )
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    if sub.name != "list" && !is_bot_admin(&ctx.http, cmd).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("Only {} can change join rules.", role_mention(gid.get(), GuildRole::Admin)))
+        ).await?;
+        return Ok(());
+    }
+
+    let mut role: Option<RoleId> = None;
+    let mut access: Option<Access> = None;
+    let mut raid_type = String::new();
+    let mut tag: Option<String> = None;
+    let mut position: Option<i32> = None;
+    let mut id: i64 = 0;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("role", CommandDataOptionValue::Role(r)) => role = Some(*r),
+            ("access", CommandDataOptionValue::String(v)) => access = Access::from_key(v),
+            ("raid_type", CommandDataOptionValue::String(v)) => raid_type = v.trim().to_string(),
+            ("tag", CommandDataOptionValue::String(v)) => tag = Some(v.trim().to_string()).filter(|t| !t.is_empty()),
+            ("position", CommandDataOptionValue::Integer(v)) => position = Some(*v as i32),
+            ("id", CommandDataOptionValue::Integer(v)) => id = *v,
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    // Rules are stored under the catalog's spelling of the type
+    if !raid_type.is_empty() {
+        match repo::get_raid_type(&pool, gid.get() as i64, &raid_type).await? {
+            Some(t) => raid_type = t.name,
+            None => {
+                cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+                    .content(format!("Raid type `{}` not found.", raid_type))
+                ).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let reply = match sub.name.as_str() {
+        "add" => {
+            let (Some(role), Some(access)) = (role, access) else { return Ok(()); };
+            handle_add(&pool, gid, &raid_type, role, access, tag, position).await?
+        }
+        "remove" => {
+            if repo::delete_join_rule(&pool, gid.get() as i64, id).await? {
+                format!("Rule #{} removed.\n\n{}", id, describe_all(&pool, gid).await?)
+            } else {
+                format!("Rule #{} not found.", id)
+            }
+        }
+        "list" => {
+            if raid_type.is_empty() {
+                describe_all(&pool, gid).await?
+            } else {
+                let set = join_rules::rules_for(&pool, gid.get(), &raid_type).await?;
+                format!("**Join rules for {}**\n{}", raid_type, describe(&set.rules, set.is_default))
+            }
+        }
+        "reset" => {
+            let n = repo::delete_join_rules_scope(&pool, gid.get() as i64, &raid_type).await?;
+            let scope = if raid_type.is_empty() { "the server".to_string() } else { raid_type.clone() };
+            format!("Removed {} rule(s) of {}.\n\n{}", n, scope, describe_all(&pool, gid).await?)
+        }
+        _ => return Ok(()),
+    };
+
+    let reply = if reply.chars().count() > 1900 { reply.chars().take(1890).collect::<String>() + "\n…" } else { reply };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/* The first rule of a scope starts it off as a copy of what applied there so far */
+async fn handle_add(
+    pool: &PgPool,
+    gid: GuildId,
+    raid_type: &str,
+    role: RoleId,
+    access: Access,
+    tag: Option<String>,
+    position: Option<i32>,
+) -> anyhow::Result<String> {
+    let all = repo::list_join_rules(pool, gid.get() as i64).await?;
+    let mut scope: Vec<JoinRule> = all.iter().filter(|r| r.raid_type.eq_ignore_ascii_case(raid_type)).cloned().collect();
+    if scope.is_empty() {
+        let inherited = if raid_type.is_empty() {
+            join_rules::default_rules(gid.get())
+        } else {
+            join_rules::pick_scope(gid.get(), all, "").rules
+        };
+        for mut r in inherited {
+            r.raid_type = raid_type.to_string();
+            scope.push(repo::upsert_join_rule(pool, &r).await?);
+        }
+    }
+
+    let existing = scope.iter().find(|r| r.role_id == role.get() as i64 && r.access == access.key());
+    let next = scope.iter().map(|r| r.position + 1).max().unwrap_or(0);
+    let rule = JoinRule {
+        id: 0,
+        guild_id: gid.get() as i64,
+        raid_type: raid_type.to_string(),
+        role_id: role.get() as i64,
+        access: access.key().to_string(),
+        // Tags only mean something on main/reserve rules
+        tag_suffix: tag.filter(|_| matches!(access, Access::Main | Access::Reserve)).map(|t| format!(" {}", t)),
+        position: position.or(existing.map(|r| r.position)).unwrap_or(next),
+    };
+    let saved = repo::upsert_join_rule(pool, &rule).await?;
+    let scope_name = if raid_type.is_empty() { "the server" } else { raid_type };
+    Ok(format!("Rule #{} for {}: {}\n\n{}", saved.id, scope_name, describe_rule(&saved), describe_all(pool, gid).await?))
+}
+
+fn describe_rule(r: &JoinRule) -> String {
+    let access = Access::from_key(&r.access).map(|a| a.label()).unwrap_or("?");
+    let tag = r.tag_suffix.as_deref().map(|t| format!(", tag `{}`", t.trim())).unwrap_or_default();
+    format!("<@&{}> may {}{}", r.role_id, access, tag)
+}
+
+fn describe(rules: &[JoinRule], is_default: bool) -> String {
+    if rules.is_empty() {
+        return "No rules: everyone may join as main and sign up alts.".to_string();
+    }
+    let lines = rules
+        .iter()
+        .map(|r| if is_default { format!("• {}", describe_rule(r)) } else { format!("`#{}` {}", r.id, describe_rule(r)) })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if is_default {
+        format!("{}\n_(defaults from `/config role`; `/join_rule add` to customise)_", lines)
+    } else {
+        lines
+    }
+}
+
+async fn describe_all(pool: &PgPool, gid: GuildId) -> anyhow::Result<String> {
+    let all = repo::list_join_rules(pool, gid.get() as i64).await?;
+    let server = join_rules::pick_scope(gid.get(), all.clone(), "");
+    let mut out = format!("**Join rules (server)**\n{}", describe(&server.rules, server.is_default));
+
+    let mut types: Vec<&str> = all.iter().map(|r| r.raid_type.as_str()).filter(|t| !t.is_empty()).collect();
+    types.dedup();
+    for t in types {
+        let rules: Vec<JoinRule> = all.iter().filter(|r| r.raid_type == t).cloned().collect();
+        out.push_str(&format!("\n\n**{}** (instead of the server rules)\n{}", t, describe(&rules, false)));
+    }
+    out.push_str("\n\nNo main/reserve rule = anyone may join; holding a reserve or stay-on-reserve rule's role keeps you on reserve; the first main/reserve rule you match gives the tag.");
+    Ok(out)
+}
@@ -0,0 +1,175 @@
+use serenity::all::*;
+use serenity::builder::{CreateInputText, CreateModal, EditInteractionResponse};
+use uuid::Uuid;
+
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::guild_tz;
+use crate::ui::menus;
+use crate::utils::{from_user_id, mention_user, user_name_best};
+
+const ITEM_MAX: usize = 100;
+const HISTORY_LEN: i64 = 20;
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("loot")
+            .description("Drops handed out in raids")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "history", "Latest drops on this server")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "Only drops of this player"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "item", "Only items containing this text"))
+            )
+    ).await?;
+    Ok(())
+}
+
+/* === "Log loot" button (Manage panel, attendance panel) === */
+
+/// Button: the owner picks who got the drop, then names the item in a modal.
+pub async fn start(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if raid.owner_id != from_user_id(it.user.id) {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("Only the raid owner can log loot.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+
+    // Mains first, one entry per player (a select holds at most 25)
+    let mut parts = repo::list_participants(&pool, raid_id).await?;
+    parts.sort_by_key(|p| !p.is_main);
+    let mut user_ids: Vec<i64> = Vec::new();
+    for p in parts {
+        if !user_ids.contains(&p.user_id) {
+            user_ids.push(p.user_id);
+        }
+    }
+    if user_ids.is_empty() {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("Nobody signed up for this raid.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+
+    it.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await?;
+    let mut options: Vec<(String, String)> = Vec::new();
+    for uid in user_ids.into_iter().take(25) {
+        options.push((user_name_best(ctx, Some(raid.guild_id as u64), uid).await, uid.to_string()));
+    }
+    it.edit_response(&ctx.http, EditInteractionResponse::new()
+        .content(format!("🎁 **Log loot for {}**\nPick who received the drop.", raid.raid_name))
+        .components(vec![menus::user_select_row(format!("r:ltu:{raid_id}"), "Recipient", options)])
+    ).await?;
+    Ok(())
+}
+
+/// Select: ask for the item; the recipient travels in the modal id.
+pub async fn pick_recipient(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let ComponentInteractionDataKind::StringSelect { values } = &it.data.kind else { return Ok(()); };
+    let Some(user_id) = values.first().and_then(|v| v.parse::<i64>().ok()) else { return Ok(()); };
+    let modal = CreateModal::new(format!("r:ltm:{user_id}:{raid_id}"), "Log loot").components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Item", "item")
+                .placeholder("e.g. Fernon's Armour")
+                .max_length(ITEM_MAX as u16)
+        ),
+    ]);
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await?;
+    Ok(())
+}
+
+/// Modal: store the drop. The select message stays up for the next one.
+pub async fn save(ctx: &Context, m: &ModalInteraction, raid_id: Uuid, user_id: &str) -> anyhow::Result<()> {
+    let _ = m.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let item: String = m.data.components.iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|c| match c {
+            ActionRowComponent::InputText(t) if t.custom_id == "item" => t.value.clone(),
+            _ => None,
+        })
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(ITEM_MAX)
+        .collect();
+    let Ok(user_id) = user_id.parse::<i64>() else { return Ok(()); };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    let reply = if raid.owner_id != from_user_id(m.user.id) {
+        "Only the raid owner can log loot.".to_string()
+    } else if item.is_empty() {
+        "Give the item a name.".to_string()
+    } else {
+        repo::insert_raid_loot(&pool, raid_id, raid.guild_id, user_id, &item, from_user_id(m.user.id)).await?;
+        format!("🎁 Logged **{}** → {} for **{}**.", item, mention_user(user_id), raid.raid_name)
+    };
+    m.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/* === /loot === */
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let mut user: Option<UserId> = None;
+    let mut item: Option<String> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("user", CommandDataOptionValue::User(u)) => user = Some(*u),
+            ("item", CommandDataOptionValue::String(v)) => item = Some(v.trim().to_string()).filter(|v| !v.is_empty()),
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let rows = repo::list_loot_history(&pool, gid.get() as i64, user.map(from_user_id), item.as_deref(), HISTORY_LEN).await?;
+    let mut filters: Vec<String> = Vec::new();
+    if let Some(u) = user {
+        filters.push(u.mention().to_string());
+    }
+    if let Some(i) = &item {
+        filters.push(format!("“{}”", i));
+    }
+    let title = if filters.is_empty() { "**Latest loot**".to_string() } else { format!("**Loot: {}**", filters.join(", ")) };
+
+    let reply = if rows.is_empty() {
+        format!("{}\nNothing logged yet.", title)
+    } else {
+        let tz = guild_tz(gid.get());
+        let lines = rows
+            .iter()
+            .map(|r| format!(
+                "{} **{}** → {} — {} `{}`",
+                r.scheduled_for.with_timezone(&tz).format("%Y-%m-%d"),
+                r.item,
+                mention_user(r.user_id),
+                r.raid_name,
+                r.raid_id
+            ))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("{}\n{}", title, lines)
+    };
+    let reply = if reply.chars().count() > 1900 { reply.chars().take(1890).collect::<String>() + "\n…" } else { reply };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
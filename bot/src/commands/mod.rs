@@ -1,4 +1,19 @@
+pub mod attendance;
+pub mod calendar;
+pub mod class_catalog;
+pub mod config;
+pub mod join_rule;
+pub mod loot;
+pub mod points;
+pub mod profile;
 pub mod raid;
+pub mod raid_clone;
+pub mod raid_composition;
+pub mod raid_edit;
+pub mod raid_history;
+pub mod raid_wizard;
+pub mod raid_type;
+pub mod template;
 
 use serenity::prelude::Context;
 
@@ -9,5 +24,20 @@ pub async fn register_commands(ctx: &Context) -> anyhow::Result<()> {
     raid::register_role_add(ctx).await?;
     raid::register_all_raid_list(ctx).await?;
     raid::register_move_raid_list(ctx).await?;
+    template::register(ctx).await?;
+    raid_type::register(ctx).await?;
+    config::register(ctx).await?;
+    raid_edit::register(ctx).await?;
+    raid_wizard::register(ctx).await?;
+    raid_clone::register(ctx).await?;
+    calendar::register(ctx).await?;
+    join_rule::register(ctx).await?;
+    class_catalog::register(ctx).await?;
+    raid_composition::register(ctx).await?;
+    profile::register(ctx).await?;
+    attendance::register(ctx).await?;
+    points::register(ctx).await?;
+    loot::register(ctx).await?;
+    raid_history::register(ctx).await?;
     Ok(())
 }
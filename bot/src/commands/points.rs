@@ -0,0 +1,215 @@
+use serenity::all::*;
+use serenity::builder::EditInteractionResponse;
+use sqlx::PgPool;
+
+use crate::commands::attendance::Status;
+use crate::db::models::{AttendancePoints, Raid};
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::{guild_tz, role_mention, GuildRole};
+use crate::utils::{from_user_id, is_bot_admin, member_has_guild_role, mention_user};
+
+const HISTORY_LEN: i64 = 15;
+const LEADERBOARD_LEN: i64 = 20;
+
+fn user_option(description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::User, "user", description)
+}
+
+fn change_subcommand(name: &str, description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::SubCommand, name, description)
+        .add_sub_option(user_option("Player").required(true))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "amount", "Points").required(true).min_int_value(1).max_int_value(100_000))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Shown in the history").max_length(100))
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("points")
+            .description("Guild points (DKP)")
+            .add_option(change_subcommand("give", "Give points to a player (organisers)"))
+            .add_option(change_subcommand("take", "Take points from a player (organisers)"))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "balance", "A player's points")
+                    .add_sub_option(user_option("Player (default: you)"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "history", "A player's latest point changes")
+                    .add_sub_option(user_option("Player (default: you)"))
+            )
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "leaderboard", "Most points on this server"))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "auto_award", "Points given when attendance is marked (bot admins); no amounts = show")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "raid_type", "Only raids of this type (default: the whole server)").set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "present", "Points for present"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "late", "Points for late"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "no_show", "Points for a no-show (negative to take)"))
+            )
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        return edit(ctx, cmd, "Use this in a server.".to_string()).await;
+    };
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let mut user = cmd.user.id;
+    let mut amount: i32 = 0;
+    let mut reason = String::new();
+    let mut raid_type = String::new();
+    let mut present: Option<i32> = None;
+    let mut late: Option<i32> = None;
+    let mut no_show: Option<i32> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("user", CommandDataOptionValue::User(u)) => user = *u,
+            ("amount", CommandDataOptionValue::Integer(v)) => amount = *v as i32,
+            ("reason", CommandDataOptionValue::String(v)) => reason = v.trim().to_string(),
+            ("raid_type", CommandDataOptionValue::String(v)) => raid_type = v.trim().to_string(),
+            ("present", CommandDataOptionValue::Integer(v)) => present = Some(*v as i32),
+            ("late", CommandDataOptionValue::Integer(v)) => late = Some(*v as i32),
+            ("no_show", CommandDataOptionValue::Integer(v)) => no_show = Some(*v as i32),
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let guild_id = gid.get() as i64;
+    let reply = match sub.name.as_str() {
+        "give" | "take" => {
+            if !is_bot_admin(&ctx.http, cmd).await? && !member_has_guild_role(&ctx.http, gid, cmd.user.id, GuildRole::Organiser).await? {
+                return edit(ctx, cmd, format!(
+                    "Only {} or {} can give or take points.",
+                    role_mention(gid.get(), GuildRole::Organiser), role_mention(gid.get(), GuildRole::Admin)
+                )).await;
+            }
+            let signed = if sub.name == "take" { -amount } else { amount };
+            repo::add_points(&pool, guild_id, from_user_id(user), signed, &reason, from_user_id(cmd.user.id)).await?;
+            let balance = repo::points_balance(&pool, guild_id, from_user_id(user)).await?;
+            format!("{} {} point(s) {} {}. Balance: **{}**.",
+                    if signed > 0 { "Gave" } else { "Took" }, amount, if signed > 0 { "to" } else { "from" }, user.mention(), balance)
+        }
+        "balance" => {
+            let balance = repo::points_balance(&pool, guild_id, from_user_id(user)).await?;
+            format!("{} has **{}** point(s).", user.mention(), balance)
+        }
+        "history" => describe_history(&pool, gid, user).await?,
+        "leaderboard" => {
+            let rows = repo::points_leaderboard(&pool, guild_id, LEADERBOARD_LEN).await?;
+            if rows.is_empty() {
+                "Nobody has points yet.".to_string()
+            } else {
+                let lines = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (uid, balance))| format!("{}. {} — **{}**", i + 1, mention_user(*uid), balance))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("**Points leaderboard**\n{}", lines)
+            }
+        }
+        "auto_award" => {
+            if present.is_none() && late.is_none() && no_show.is_none() {
+                describe_awards(&pool, gid).await?
+            } else {
+                if !is_bot_admin(&ctx.http, cmd).await? {
+                    return edit(ctx, cmd, format!("Only {} can change automatic points.", role_mention(gid.get(), GuildRole::Admin))).await;
+                }
+                // Awards are stored under the catalog's spelling of the type
+                if !raid_type.is_empty() {
+                    match repo::get_raid_type(&pool, guild_id, &raid_type).await? {
+                        Some(t) => raid_type = t.name,
+                        None => return edit(ctx, cmd, format!("Raid type `{}` not found.", raid_type)).await,
+                    }
+                }
+                let rows = repo::list_attendance_points(&pool, guild_id).await?;
+                let current = rows.iter().find(|r| r.raid_type.eq_ignore_ascii_case(&raid_type));
+                let award = AttendancePoints {
+                    guild_id,
+                    raid_type: raid_type.clone(),
+                    present: present.or(current.map(|c| c.present)).unwrap_or(0),
+                    late: late.or(current.map(|c| c.late)).unwrap_or(0),
+                    no_show: no_show.or(current.map(|c| c.no_show)).unwrap_or(0),
+                };
+                // All zeros = no automatic points for this scope
+                if award.present == 0 && award.late == 0 && award.no_show == 0 {
+                    repo::delete_attendance_points(&pool, guild_id, &raid_type).await?;
+                } else {
+                    repo::upsert_attendance_points(&pool, &award).await?;
+                }
+                format!("Saved.\n\n{}", describe_awards(&pool, gid).await?)
+            }
+        }
+        _ => return Ok(()),
+    };
+    edit(ctx, cmd, reply).await
+}
+
+async fn edit(ctx: &Context, cmd: &CommandInteraction, content: String) -> anyhow::Result<()> {
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+async fn describe_history(pool: &PgPool, gid: GuildId, user: UserId) -> anyhow::Result<String> {
+    let guild_id = gid.get() as i64;
+    let rows = repo::list_points_history(pool, guild_id, from_user_id(user), HISTORY_LEN).await?;
+    if rows.is_empty() {
+        return Ok(format!("{} has no point history yet.", user.mention()));
+    }
+    let tz = guild_tz(gid.get());
+    let lines = rows
+        .iter()
+        .map(|r| {
+            let reason = if r.reason.is_empty() { String::new() } else { format!(" — {}", r.reason) };
+            format!("`{:+}` {}{} (by {})", r.amount, r.created_at.with_timezone(&tz).format("%Y-%m-%d"), reason, mention_user(r.given_by))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let balance = repo::points_balance(pool, guild_id, from_user_id(user)).await?;
+    Ok(format!("**Points of {}**: {}\n{}", user.mention(), balance, lines))
+}
+
+async fn describe_awards(pool: &PgPool, gid: GuildId) -> anyhow::Result<String> {
+    let rows = repo::list_attendance_points(pool, gid.get() as i64).await?;
+    if rows.is_empty() {
+        return Ok("No automatic points: marking attendance awards nothing. Set some with `/points auto_award present:<n>`.".to_string());
+    }
+    let lines = rows
+        .iter()
+        .map(|r| {
+            let scope = if r.raid_type.is_empty() { "Every raid" } else { r.raid_type.as_str() };
+            format!("• {}: present {:+}, late {:+}, no-show {:+}", scope, r.present, r.late, r.no_show)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(format!("**Automatic points on attendance**\n{}", lines))
+}
+
+/// Awards the points configured for the raid's type (or the whole guild) to `user_ids` for
+/// `status`. Marking the same player again replaces the earlier award.
+pub async fn award_attendance(pool: &PgPool, raid: &Raid, user_ids: &[i64], status: Status, marked_by: i64) -> anyhow::Result<()> {
+    let rows = repo::list_attendance_points(pool, raid.guild_id).await?;
+    let Some(award) = rows
+        .iter()
+        .find(|r| !r.raid_type.is_empty() && r.raid_type.eq_ignore_ascii_case(&raid.raid_name))
+        .or_else(|| rows.iter().find(|r| r.raid_type.is_empty()))
+    else {
+        return Ok(());
+    };
+    let amount = match status {
+        Status::Present => award.present,
+        Status::Late => award.late,
+        Status::NoShow => award.no_show,
+    };
+    let reason = format!("{}: {}", raid.raid_name, status.label());
+    repo::set_attendance_award(pool, raid.id, raid.guild_id, user_ids, amount, &reason, marked_by).await
+}
@@ -0,0 +1,162 @@
+use chrono::Utc;
+use serenity::all::*;
+use serenity::builder::{CreateAutocompleteResponse, EditInteractionResponse};
+
+use crate::catalog;
+use crate::db::models::PlayerProfile;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::utils::{from_user_id, parse_list_unique};
+
+fn name_option(description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "name", description).required(true).set_autocomplete(true)
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("profile")
+            .description("Your saved characters for the Quick join button")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Save a character or change a saved one")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Character name").required(true).max_length(32))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class").required(true).set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "sp", "SP you join with, e.g. SP3").required(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "extra_sps", "Other SPs you bring, comma separated"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "default", "Quick join signs this one as main"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "default", "Pick the character Quick join signs as main")
+                    .add_sub_option(name_option("Character name"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Delete a saved character")
+                    .add_sub_option(name_option("Character name"))
+            )
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show your saved characters"))
+    ).await?;
+    Ok(())
+}
+
+pub async fn autocomplete_names(ctx: &Context, ac: &CommandInteraction, typed: &str) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let typed = typed.trim().to_lowercase();
+    let mut resp = CreateAutocompleteResponse::new();
+    for p in repo::list_player_profiles(&pool, from_user_id(ac.user.id)).await?
+        .into_iter()
+        .filter(|p| p.name.to_lowercase().contains(&typed))
+        .take(25)
+    {
+        resp = resp.add_string_choice(format!("{} ({})", p.name, profile_label(&p)), p.name);
+    }
+    ac.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp)).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let mut name = String::new();
+    let mut class = String::new();
+    let mut sp = String::new();
+    let mut extra_sps: Vec<String> = Vec::new();
+    let mut make_default = false;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("name", CommandDataOptionValue::String(v)) => name = v.trim().to_string(),
+            ("class", CommandDataOptionValue::String(v)) => class = v.trim().to_string(),
+            ("sp", CommandDataOptionValue::String(v)) => sp = normalize_sp(v),
+            ("extra_sps", CommandDataOptionValue::String(v)) => extra_sps = parse_list_unique(v).iter().map(|s| normalize_sp(s)).collect(),
+            ("default", CommandDataOptionValue::Boolean(v)) => make_default = *v,
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let user_id = from_user_id(cmd.user.id);
+    let reply = match sub.name.as_str() {
+        "list" => describe_all(&repo::list_player_profiles(&pool, user_id).await?),
+        "add" => {
+            if name.is_empty() {
+                return edit(ctx, cmd, "Give the character a name.".to_string()).await;
+            }
+            let Some(class) = catalog::find_class(&class) else {
+                return edit(ctx, cmd, format!("Unknown class `{}`.", class)).await;
+            };
+            if let Some(bad) = std::iter::once(&sp).chain(extra_sps.iter()).find(|s| !catalog::sp_allowed(&class.code, s)) {
+                return edit(ctx, cmd, format!("{} is not an SP of class {}.", bad, class.code)).await;
+            }
+            extra_sps.retain(|s| *s != sp);
+
+            let existing = repo::list_player_profiles(&pool, user_id).await?;
+            let saved = repo::upsert_player_profile(&pool, &PlayerProfile {
+                id: 0,
+                user_id,
+                name: name.clone(),
+                class: class.code,
+                active_sp: sp,
+                extra_sps,
+                // The first character is the default until another one is picked
+                is_default: existing.is_empty(),
+                created_at: Utc::now(),
+            }).await?;
+            if make_default && !saved.is_default {
+                repo::set_default_player_profile(&pool, user_id, &saved.name).await?;
+            }
+            format!("Saved **{}**.\n\n{}", saved.name, describe_all(&repo::list_player_profiles(&pool, user_id).await?))
+        }
+        "default" => {
+            if repo::set_default_player_profile(&pool, user_id, &name).await? {
+                format!("Quick join now signs **{}** as main.\n\n{}", name, describe_all(&repo::list_player_profiles(&pool, user_id).await?))
+            } else {
+                format!("No saved character `{}`.", name)
+            }
+        }
+        "remove" => {
+            if repo::delete_player_profile(&pool, user_id, &name).await? {
+                format!("Removed **{}**.\n\n{}", name, describe_all(&repo::list_player_profiles(&pool, user_id).await?))
+            } else {
+                format!("No saved character `{}`.", name)
+            }
+        }
+        _ => return Ok(()),
+    };
+    edit(ctx, cmd, reply).await
+}
+
+async fn edit(ctx: &Context, cmd: &CommandInteraction, content: String) -> anyhow::Result<()> {
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+/// "sp 3" → "SP3"
+fn normalize_sp(s: &str) -> String {
+    s.replace(' ', "").to_ascii_uppercase()
+}
+
+/// "MSW / SP1 (+SP2, SP4)"
+pub fn profile_label(p: &PlayerProfile) -> String {
+    if p.extra_sps.is_empty() {
+        format!("{} / {}", p.class, p.active_sp)
+    } else {
+        format!("{} / {} (+{})", p.class, p.active_sp, p.extra_sps.join(", "))
+    }
+}
+
+fn describe_all(profiles: &[PlayerProfile]) -> String {
+    if profiles.is_empty() {
+        return "No saved characters. Save one with `/profile add`; the Quick join button then signs you up in one click.".to_string();
+    }
+    let lines = profiles
+        .iter()
+        .map(|p| format!("{} **{}** — {}", if p.is_default { "⭐" } else { "•" }, p.name, profile_label(p)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("**Your characters**\n{}\n_⭐ joins as main with Quick join; the others can be quick-joined as alts._", lines)
+}
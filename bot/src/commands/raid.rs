@@ -2,17 +2,18 @@ use serenity::all::*;
 use serenity::builder::{CreateChannel, CreateMessage, EditMessage, EditInteractionResponse};
 use uuid::Uuid;
 
+use crate::db::models::NewRaidRow;
 use crate::db::repo;
 use crate::handlers::pool_from_ctx;
 use crate::ui::{embeds, menus};
-use crate::utils::{parse_raid_datetime, weekday_key,parse_list_unique, mention_user, ORGANISER_ROLE_NAME, PERMISSIONS_ROLE_NAME};
+use crate::settings::{role_mention, GuildRole};
+use crate::utils::{parse_raid_datetime, weekday_key, raid_slug, parse_list_unique, mention_user, member_has_guild_role, is_bot_admin};
 use crate::tasks;
-use crate::utils::extract_duration_hours;
-use chrono_tz::Europe::Warsaw;
+use crate::settings::guild_tz;
 use chrono::Datelike;
 use once_cell::sync::Lazy;
 use dashmap::DashMap;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
 // Legacy in-memory registry (kept for compatibility but not authoritative).
 // Authoritative registry lives in Redis + DB; we keep a tiny in-process debounce state below.
@@ -21,71 +22,42 @@ pub static ALL_RAID_LIST_MSG: Lazy<DashMap<u64, (u64, Vec<u64>)>> = Lazy::new(Da
 // Debounce flags per guild to coalesce frequent refreshes
 static PENDING_REFRESH: Lazy<DashMap<u64, ()>> = Lazy::new(DashMap::new);
 
+// `/raid` previews waiting for "Create" / "Cancel"
+struct PendingRaid {
+    new: NewRaid,
+    created: Instant,
+}
+static PENDING_RAIDS: Lazy<DashMap<Uuid, PendingRaid>> = Lazy::new(DashMap::new);
+const PENDING_RAID_TTL: Duration = Duration::from_secs(15 * 60);
+
 pub async fn register(ctx: &Context) -> anyhow::Result<()> {
     Command::create_global_command(
         &ctx.http,
         CreateCommand::new("raid")
             .description("Create a raid")
             // Required first
-            //raid_name IN ('ArmaV2','Pollutus','Arma','Azgobas','Valehir','Alzanor','Hc_Azgobas','Hc_Valehir','Hc_Alzanor','Hc_A8-A6','Hc_A1-A5')
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "raid_name", "One of: arma_v2, pollu, arma")
-                    .required(true)
-                    .add_string_choice("ArmaV2", "ArmaV2")
-                    .add_string_choice("Pollutus", "Pollutus")
-                    .add_string_choice("Sky_Tower", "Sky_Tower")
-                    .add_string_choice("Arma", "Arma")
-                    .add_string_choice("Azgobas", "Azgobas")
-                    .add_string_choice("Valehir", "Valehir")
-                    .add_string_choice("Alzanor", "Alzanor")
-                    .add_string_choice("Hc_Azgobas", "Hc_Azgobas")
-                    .add_string_choice("Hc_Valehir", "Hc_Valehir")
-                    .add_string_choice("Hc_Alzanor", "Hc_Alzanor")
-                    .add_string_choice("Hc_A8-A6", "Hc_A8-A6")
-                    .add_string_choice("Hc_A1-A5", "Hc_A1-A5")
-                    .add_string_choice("Hc_A1-8", "Hc_A1-8")
-                    .add_string_choice("Nezarun", "Nezarun")
-                    .add_string_choice("Nezarun_v2", "Nezarun_v2")
-                    .add_string_choice("Fernon", "Fernon")
-            )
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "raid_date", "Format: HH:MM YYYY-MM-DD").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_players", "Main slots").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "allow_alts", "Allow alts").required(true))
-            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_alts", "Alt slots").required(true))
+            .add_option(raid_name_option())
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "raid_date", "e.g. fri 19:00, tomorrow 21:30, 24.12 20:00, 20:00 2025-06-01").required(true))
             .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "priority", "Enable priority role window").required(true))
             .add_option(CreateCommandOption::new(CommandOptionType::String, "description", "Short description").required(true))
-            // Optional after
-            .add_option(CreateCommandOption::new(CommandOptionType::String, "prioritylist", "Role name for priority (e.g., Maraton)"))
+            // Optional after (limits default to the raid type's settings)
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "duration_minutes", "Raid length in minutes").min_int_value(1).max_int_value(24 * 60))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_players", "Main slots"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "allow_alts", "Allow alts"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_alts", "Alt slots"))
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "prioritylist", "Role names for priority (default: the server's priority role)"))
             .add_option(CreateCommandOption::new(CommandOptionType::Integer, "priority_hours", "How long priority lasts (hours)"))
     ).await?;
     Ok(())
 }
-fn emoji_and_slug(raid_choice: &str) -> (&'static str, String) {
-    // Normalize name: lowercase and replace separators with hyphens
-    let slug = raid_choice
-        .to_lowercase()
-        .replace(' ', "-")
-        .replace('_', "-");
-
-    // Pick emoji
-    let emoji = match slug.as_str() {
-        "armav2" => "🦾",
-        "arma" => "🤖",
-        "pollutus" => "🦠",
-        "azgobas" => "🐉",
-        "valehir" => "💀",
-        "alzanor" => "🥶",
-        "sky-tower" => "🗼",
-        "nezarun" => "🔨",
-        "nezarun-v2" => "🐙",
-        s if s.starts_with("hc-") => "🔥", // Hc_* variants
-        _ => "🏷️", // fallback
-    };
 
-    (emoji, slug)
+/// `raid_name` option autocompleted from the `raid_types` catalog (shared by `/raid` and `/raid_template`).
+pub fn raid_name_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "raid_name", "Raid type")
+        .required(true)
+        .set_autocomplete(true)
 }
 
-
 pub async fn register_kick(ctx: &Context) -> anyhow::Result<()> {
     Command::create_global_command(
         &ctx.http,
@@ -112,7 +84,7 @@ pub async fn register_role_add(ctx: &Context) -> anyhow::Result<()> {
     Command::create_global_command(
         &ctx.http,
         CreateCommand::new("role_add")
-            .description("Add or remove a predefined role to a user (raid_organiser only)")
+            .description("Add or remove a predefined role to a user (organisers only)")
             .add_option(CreateCommandOption::new(CommandOptionType::User, "user", "Target user").required(true))
             .add_option(
                 CreateCommandOption::new(CommandOptionType::String, "action", "add or remove")
@@ -123,11 +95,11 @@ pub async fn register_role_add(ctx: &Context) -> anyhow::Result<()> {
             .add_option(
                 CreateCommandOption::new(CommandOptionType::String, "role", "Role to set")
                     .required(true)
-                    .add_string_choice("Maraton", "Maraton")
-                    .add_string_choice("c90", "c90")
-                    .add_string_choice("c1-89", "c1-89")
-                    .add_string_choice("Alt_allow", "Alt_allow")
-                    .add_string_choice("reserve", "reserve")
+                    .add_string_choice(GuildRole::DefaultPriority.label(), GuildRole::DefaultPriority.key())
+                    .add_string_choice(GuildRole::C90.label(), GuildRole::C90.key())
+                    .add_string_choice(GuildRole::C1to89.label(), GuildRole::C1to89.key())
+                    .add_string_choice(GuildRole::AltAllow.label(), GuildRole::AltAllow.key())
+                    .add_string_choice(GuildRole::Reserve.label(), GuildRole::Reserve.key())
             )
     ).await?;
     Ok(())
@@ -147,7 +119,7 @@ pub async fn register_move_raid_list(ctx: &Context) -> anyhow::Result<()> {
     Command::create_global_command(
         &ctx.http,
         CreateCommand::new("move_raid_list_here")
-            .description("Move the guild's consolidated raids list to this channel (raid_organiser only)")
+            .description("Move the guild's consolidated raids list to this channel (bot admins only)")
     ).await?;
     Ok(())
 }
@@ -160,10 +132,38 @@ pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<(
         "role_add" => handle_role_add(ctx, cmd).await,
         "all_raid_list" => handle_all_raid_list(ctx, cmd).await,
         "move_raid_list_here" => handle_move_raid_list(ctx, cmd).await,
+        "raid_template" => super::template::handle(ctx, cmd).await,
+        "raid_type" => super::raid_type::handle(ctx, cmd).await,
+        "config" => super::config::handle(ctx, cmd).await,
+        "join_rule" => super::join_rule::handle(ctx, cmd).await,
+        "class_catalog" => super::class_catalog::handle(ctx, cmd).await,
+        "raid_edit" => super::raid_edit::handle(ctx, cmd).await,
+        "raid_wizard" => super::raid_wizard::handle(ctx, cmd).await,
+        "raid_clone" => super::raid_clone::handle(ctx, cmd).await,
+        "raid_calendar" => super::calendar::handle(ctx, cmd).await,
+        "raid_composition" => super::raid_composition::handle(ctx, cmd).await,
+        "profile" => super::profile::handle(ctx, cmd).await,
+        "attendance" => super::attendance::handle(ctx, cmd).await,
+        "points" => super::points::handle(ctx, cmd).await,
+        "loot" => super::loot::handle(ctx, cmd).await,
+        "raid_history" => super::raid_history::handle(ctx, cmd).await,
         _ => Ok(())
     }
 }
 
+pub async fn handle_autocomplete(ctx: &Context, ac: &CommandInteraction) -> anyhow::Result<()> {
+    let Some(focused) = ac.data.autocomplete() else { return Ok(()); };
+    match (ac.data.name.as_str(), focused.name) {
+        ("raid" | "raid_template", "raid_name") | ("raid_type", "name") | ("join_rule" | "points", "raid_type") => {
+            super::raid_type::autocomplete_raid_types(ctx, ac, focused.value).await
+        }
+        ("config", "timezone") => super::config::autocomplete_timezones(ctx, ac, focused.value).await,
+        ("class_catalog" | "raid_composition" | "profile", "class") => super::class_catalog::autocomplete_classes(ctx, ac, focused.value).await,
+        ("profile", "name") => super::profile::autocomplete_names(ctx, ac, focused.value).await,
+        _ => Ok(()),
+    }
+}
+
 async fn handle_create(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
     // Quick ephemeral ACK to avoid 10s latency errors
     let _ = cmd
@@ -177,11 +177,12 @@ async fn handle_create(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Resul
         )
         .await;
 
-    let mut raid_name = "arma_v2".to_string();
+    let mut raid_name = String::new();
     let mut raid_date_str = String::new();
-    let mut max_players: i64 = 12;
-    let mut allow_alts = true;
-    let mut max_alts: i64 = 1;
+    let mut duration_minutes: Option<i64> = None;
+    let mut max_players: Option<i64> = None;
+    let mut allow_alts: Option<bool> = None;
+    let mut max_alts: Option<i64> = None;
     let mut priority = false;
     let mut priority_role_name: Vec<String> = Vec::new();
     let mut priority_hours: Option<i64> = None;
@@ -191,9 +192,10 @@ async fn handle_create(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Resul
         match opt.name.as_str() {
             "raid_name" => if let CommandDataOptionValue::String(s) = &opt.value { raid_name = s.clone(); },
             "raid_date" => if let CommandDataOptionValue::String(s) = &opt.value { raid_date_str = s.clone(); },
-            "max_players" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_players = *n; },
-            "allow_alts" => if let CommandDataOptionValue::Boolean(b) = &opt.value { allow_alts = *b; },
-            "max_alts" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_alts = *n; },
+            "duration_minutes" => if let CommandDataOptionValue::Integer(n) = &opt.value { duration_minutes = Some(*n); },
+            "max_players" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_players = Some(*n); },
+            "allow_alts" => if let CommandDataOptionValue::Boolean(b) = &opt.value { allow_alts = Some(*b); },
+            "max_alts" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_alts = Some(*n); },
             "priority" => if let CommandDataOptionValue::Boolean(b) = &opt.value { priority = *b; },
             "priority_hours" => if let CommandDataOptionValue::Integer(n) = &opt.value { priority_hours = Some(*n); },
             "prioritylist" => if let CommandDataOptionValue::String(s) = &opt.value { priority_role_name = parse_list_unique(s); },
@@ -202,72 +204,42 @@ async fn handle_create(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Resul
         }
     }
 
-    let Some(scheduled_for) = parse_raid_datetime(&raid_date_str) else {
-        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
-            .content("Invalid date format. Use `HH:MM YYYY-MM-DD`.")
-        ).await?;
-        return Ok(());
+    // Autocomplete still allows free text, so resolve against the catalog
+    let guild_id = cmd.guild_id.map(|g| g.get() as i64).unwrap_or(0);
+    let raid_type = match repo::get_raid_type(&pool_from_ctx(ctx).await?, guild_id, &raid_name).await? {
+        Some(t) if t.is_active => t,
+        _ => {
+            cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+                .content(format!("Unknown raid type `{}`. Pick one from the list.", raid_name))
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    let tz = cmd.guild_id.map(|g| guild_tz(g.get())).unwrap_or(crate::settings::DEFAULT_TIMEZONE);
+    let scheduled_for = match parse_raid_datetime(&raid_date_str, tz) {
+        Ok(dt) => dt,
+        Err(msg) => {
+            cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+                .content(format!("❌ {}\nTimes are in server time ({}).", msg, tz.name()))
+            ).await?;
+            return Ok(());
+        }
     };
 
     let mut priority_role_id: Option<Vec<i64>> = None;
     let mut priority_until: Option<chrono::DateTime<chrono::Utc>> = None;
 
     if priority {
-        // default if user didn’t provide any list
-        if priority_role_name.is_empty() {
-            priority_role_name = vec!["Maraton".to_string()];
-        }
-
         if let Some(gid) = cmd.guild_id {
-            let roles_map = gid.roles(&ctx.http).await?;
-
-            // Resolve provided names -> RoleId (case-insensitive)
-            let mut found_role_ids: Vec<RoleId> = Vec::new();
-            for name in &priority_role_name {
-                if let Some((rid, _)) = roles_map
-                    .iter()
-                    .find(|(_, r)| r.name.eq_ignore_ascii_case(name))
-                {
-                    found_role_ids.push(*rid);
+            match resolve_priority_role_ids(ctx, gid, cmd.user.id, &priority_role_name).await? {
+                Ok(ids) => priority_role_id = Some(ids),
+                Err(msg) => {
+                    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(msg)).await?;
+                    return Ok(());
                 }
             }
 
-            if found_role_ids.is_empty() {
-                let listed = priority_role_name.join(", ");
-                cmd.edit_response(&ctx.http, EditInteractionResponse::new()
-                    .content(format!(
-                        "None of the roles from `prioritylist` were found in this server: {}.",
-                        listed
-                    ))
-                ).await?;
-                return Ok(());
-            }
-
-            // Author must have at least one of the matched roles
-            let member = gid.member(&ctx.http, cmd.user.id).await?;
-            let has_any = member.roles.iter().any(|rid| found_role_ids.contains(rid));
-            if !has_any {
-                let listed = priority_role_name.join(", ");
-                cmd.edit_response(&ctx.http, EditInteractionResponse::new()
-                    .content(format!(
-                        "You don't have any of the required roles for priority: {}.",
-                        listed
-                    ))
-                ).await?;
-                return Ok(());
-            }
-
-            // === Store as CSV string ===
-            let _csv = found_role_ids
-                .iter()
-                .map(|rid| rid.get().to_string())
-                .collect::<Vec<_>>()
-                .join(",");
-
-            // Keep first role id for backward compat (if you still have the old column)
-            let ids_vec_i64: Vec<i64> = found_role_ids.iter().map(|rid| rid.get() as i64).collect();
-            priority_role_id = Some(ids_vec_i64);
-
             if let Some(h) = priority_hours {
                 priority_until = Some(scheduled_for - chrono::Duration::hours(h));
             }
@@ -285,95 +257,329 @@ async fn handle_create(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Resul
         }
     };
 
-    let weekday = weekday_key(scheduled_for);
-    let guild = gid.to_partial_guild(&ctx.http).await?;
-    let channels = guild.channels(&ctx.http).await?;
-    let category_id = channels.values().find_map(|c| {
-        if c.kind == ChannelType::Category && c.name.to_lowercase().contains(weekday) { Some(c.id) } else { None }
-    });
-    let when_local = scheduled_for.with_timezone(&Warsaw);
-    let (emoji, name_slug) = emoji_and_slug(&raid_name);
-    let date = when_local.format("%d-%m");
-    let time = when_local.format("%H_%M");
+    let new = NewRaid {
+        guild_id: gid,
+        created_by: cmd.user.id,
+        raid_name: raid_type.name,
+        scheduled_for,
+        duration_minutes: duration_minutes.map(|m| m as i32).unwrap_or(raid_type.default_duration_minutes),
+        max_players: max_players.unwrap_or(raid_type.default_max_players as i64),
+        allow_alts: allow_alts.unwrap_or(raid_type.default_allow_alts),
+        max_alts: max_alts.unwrap_or(raid_type.default_max_alts as i64),
+        priority,
+        priority_role_id,
+        priority_until,
+        description,
+    };
 
-    let chan_name = format!("{emoji}-{name_slug}-{date} at {time}",
+    // Show what the input resolved to; the raid is posted only after "Create"
+    let content = preview_content(&raid_date_str, scheduled_for, tz);
+    let embed = embeds::render_new_raid_embed(&new.raid_name, &new.description, scheduled_for, new.duration_minutes, &new.max_players, tz);
+    let pending_id = Uuid::new_v4();
+    PENDING_RAIDS.retain(|_, p| p.created.elapsed() < PENDING_RAID_TTL);
+    PENDING_RAIDS.insert(pending_id, PendingRaid { new, created: Instant::now() });
 
-    );
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+        .content(content)
+        .embed(embed)
+        .components(vec![menus::new_raid_confirm_row(pending_id)])
+    ).await?;
+    Ok(())
+}
 
+/* Preview header shared by `/raid` and `/raid_wizard` */
+pub fn preview_content(input: &str, scheduled_for: chrono::DateTime<chrono::Utc>, tz: chrono_tz::Tz) -> String {
+    let ts = scheduled_for.timestamp();
+    format!(
+        "`{}` → **{}** (<t:{}:F>, <t:{}:R>)\nCreate this raid?",
+        input.trim(),
+        scheduled_for.with_timezone(&tz).format("%A %Y-%m-%d %H:%M %Z"),
+        ts, ts
+    )
+}
+
+/* "Create" on the /raid preview */
+pub async fn confirm_new_raid(ctx: &Context, it: &ComponentInteraction, pending_id: Uuid) -> anyhow::Result<()> {
+    let pending = PENDING_RAIDS
+        .remove_if(&pending_id, |_, p| p.new.created_by == it.user.id)
+        .map(|(_, p)| p)
+        .filter(|p| p.created.elapsed() < PENDING_RAID_TTL);
+
+    let Some(pending) = pending else {
+        it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content("This preview has expired. Run `/raid` again.")
+                .embeds(vec![])
+                .components(vec![])
+        )).await?;
+        return Ok(());
+    };
+
+    post_new_raid(ctx, it, pending.new).await
+}
+
+/// Final step of both creation flows: swaps the preview for a progress note, posts the raid, links it.
+pub async fn post_new_raid(ctx: &Context, it: &ComponentInteraction, new: NewRaid) -> anyhow::Result<()> {
+    // Drop the buttons right away so a double click can't post twice
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content("⏳ Creating raid…")
+            .components(vec![])
+    )).await?;
+
+    let raid = create_raid(ctx, new).await?;
+    it.edit_response(&ctx.http, EditInteractionResponse::new()
+        .content(format!("Raid created! <#{}>", raid.chat_channel_id()))
+    ).await?;
+    Ok(())
+}
+
+/* "Cancel" on the /raid preview */
+pub async fn cancel_new_raid(ctx: &Context, it: &ComponentInteraction, pending_id: Uuid) -> anyhow::Result<()> {
+    PENDING_RAIDS.remove(&pending_id);
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content("Raid not created.")
+            .embeds(vec![])
+            .components(vec![])
+    )).await?;
+    Ok(())
+}
+
+/// Resolves `prioritylist` role names (case-insensitive) to role IDs; the invoker must hold at least one.
+/// An empty list means the guild's default priority role. The inner `Err` is the message to show the user.
+pub async fn resolve_priority_role_ids(
+    ctx: &Context,
+    gid: GuildId,
+    invoker: UserId,
+    names: &[String],
+) -> anyhow::Result<Result<Vec<i64>, String>> {
+    let roles_map = gid.roles(&ctx.http).await?;
 
-    let text_channel = match category_id {
-        Some(cat) => {
-            gid.create_channel(&ctx.http, CreateChannel::new(&chan_name).kind(ChannelType::Text).category(cat)).await?
+    // Resolve provided names -> RoleId (case-insensitive)
+    let mut found_role_ids: Vec<RoleId> = Vec::new();
+    for name in names {
+        if let Some((rid, _)) = roles_map
+            .iter()
+            .find(|(_, r)| r.name.eq_ignore_ascii_case(name))
+        {
+            found_role_ids.push(*rid);
         }
-        None => {
-            gid.create_channel(&ctx.http, CreateChannel::new(&chan_name).kind(ChannelType::Text)).await?
+    }
+    let mut names = names.to_vec();
+    if names.is_empty() {
+        let Some(rid) = crate::settings::role_id(gid.get(), GuildRole::DefaultPriority) else {
+            return Ok(Err(format!(
+                "Give a `prioritylist`: no {} role is set up.",
+                role_mention(gid.get(), GuildRole::DefaultPriority)
+            )));
+        };
+        found_role_ids.push(rid);
+        names.push(roles_map.get(&rid).map(|r| r.name.clone()).unwrap_or_else(|| rid.to_string()));
+    }
+
+    if found_role_ids.is_empty() {
+        return Ok(Err(format!(
+            "None of the roles from `prioritylist` were found in this server: {}.",
+            names.join(", ")
+        )));
+    }
+
+    // Author must have at least one of the matched roles
+    let member = gid.member(&ctx.http, invoker).await?;
+    let has_any = member.roles.iter().any(|rid| found_role_ids.contains(rid));
+    if !has_any {
+        return Ok(Err(format!(
+            "You don't have any of the required roles for priority: {}.",
+            names.join(", ")
+        )));
+    }
+
+    Ok(Ok(found_role_ids.iter().map(|rid| rid.get() as i64).collect()))
+}
+
+/// Everything needed to post a raid. Built by `/raid` and by the template scheduler in `tasks`.
+pub struct NewRaid {
+    pub guild_id: GuildId,
+    pub created_by: UserId,
+    pub raid_name: String,
+    pub scheduled_for: chrono::DateTime<chrono::Utc>,
+    pub duration_minutes: i32,
+    pub max_players: i64,
+    pub allow_alts: bool,
+    pub max_alts: i64,
+    pub priority: bool,
+    pub priority_role_id: Option<Vec<i64>>,
+    pub priority_until: Option<chrono::DateTime<chrono::Utc>>,
+    pub description: String,
+}
+
+/* Category for a raid channel: the weekday's configured category (or, if none is set, one whose name
+   contains the weekday), then the guild's fallback category. A category that already holds
+   `max_channels_per_category` active raids is skipped. `current` is the raid's own channel when moving it. */
+pub async fn raid_category(
+    ctx: &Context,
+    gid: GuildId,
+    scheduled_for: chrono::DateTime<chrono::Utc>,
+    tz: chrono_tz::Tz,
+    current: Option<ChannelId>,
+) -> anyhow::Result<Option<ChannelId>> {
+    let settings = crate::settings::get(gid.get());
+    let weekday = weekday_key(scheduled_for, tz);
+    let channels = gid.channels(&ctx.http).await?;
+
+    let mut candidates: Vec<ChannelId> = Vec::new();
+    match settings.weekday_categories.get(weekday) {
+        Some(id) => candidates.push(ChannelId::new(*id as u64)),
+        None => candidates.extend(channels.values().find_map(|c| {
+            if c.kind == ChannelType::Category && c.name.to_lowercase().contains(weekday) { Some(c.id) } else { None }
+        })),
+    }
+    candidates.extend(settings.fallback_category_id.map(|id| ChannelId::new(id as u64)));
+
+    let pool = pool_from_ctx(ctx).await?;
+    for cat in candidates {
+        // Deleted categories stay in the settings until an admin clears them
+        if channels.get(&cat).is_none_or(|c| c.kind != ChannelType::Category) { continue; }
+        let Some(max) = settings.max_channels_per_category.filter(|m| *m > 0) else { return Ok(Some(cat)); };
+        // Already there: staying doesn't add a channel
+        if current.is_some_and(|ch| channels.get(&ch).and_then(|c| c.parent_id) == Some(cat)) { return Ok(Some(cat)); }
+        let in_category: Vec<i64> = channels.values()
+            .filter(|c| c.parent_id == Some(cat))
+            .map(|c| c.id.get() as i64)
+            .collect();
+        if repo::count_active_raids_in_channels(&pool, &in_category).await? < max as i64 {
+            return Ok(Some(cat));
         }
+    }
+    Ok(None)
+}
+
+/* Raid channel name from the guild's template (see `/config channel_name`), local to the guild */
+pub async fn raid_channel_name(
+    pool: &sqlx::PgPool,
+    gid: GuildId,
+    raid_name: &str,
+    scheduled_for: chrono::DateTime<chrono::Utc>,
+    tz: chrono_tz::Tz,
+) -> anyhow::Result<String> {
+    let (emoji, name_slug) = match repo::get_raid_type(pool, gid.get() as i64, raid_name).await? {
+        Some(t) => (t.emoji, t.slug),
+        None => ("🏷️".to_string(), raid_slug(raid_name)),
     };
+    let template = crate::settings::get(gid.get())
+        .channel_name_template
+        .unwrap_or_else(|| crate::settings::DEFAULT_CHANNEL_NAME_TEMPLATE.to_string());
+    Ok(render_channel_name(&template, &emoji, &name_slug, raid_name, scheduled_for, tz))
+}
 
-    let raid_id = Uuid::new_v4();
-    let embed = embeds::render_new_raid_embed(&raid_name, &description, scheduled_for, &max_players);
-    let (_desc_clean, dur_h) = extract_duration_hours(&description);
-    let msg = text_channel.id.send_message(
-        &ctx.http,
-        CreateMessage::new()
-            .embed(embed)
-            .components(vec![menus::main_buttons_row(raid_id)])
-    ).await?;
+/* Placeholders: {emoji} {slug} {name} {date} (dd-mm) {time} (HH_MM) {weekday} */
+pub fn render_channel_name(
+    template: &str,
+    emoji: &str,
+    slug: &str,
+    raid_name: &str,
+    scheduled_for: chrono::DateTime<chrono::Utc>,
+    tz: chrono_tz::Tz,
+) -> String {
+    let when_local = scheduled_for.with_timezone(&tz);
+    template
+        .replace("{emoji}", emoji)
+        .replace("{slug}", slug)
+        .replace("{name}", raid_name)
+        .replace("{date}", &when_local.format("%d-%m").to_string())
+        .replace("{time}", &when_local.format("%H_%M").to_string())
+        .replace("{weekday}", weekday_key(scheduled_for, tz))
+        .chars()
+        .take(100) // Discord's channel name limit
+        .collect()
+}
 
-    repo::create_raid_with_id(
-        &pool_from_ctx(ctx).await?,
-        raid_id,
-        gid.get() as i64,
-        text_channel.id.get() as i64,
-        msg.id.get() as i64,
+/// Creates the channel, posts the embed, stores the raid row and arms all timers.
+pub async fn create_raid(ctx: &Context, new: NewRaid) -> anyhow::Result<crate::db::models::Raid> {
+    let NewRaid {
+        guild_id: gid,
+        created_by,
+        raid_name,
         scheduled_for,
-        cmd.user.id.get() as i64,
-        cmd.user.id.get() as i64,
-        description,
-        vec![],
+        duration_minutes,
+        max_players,
+        allow_alts,
+        max_alts,
         priority,
+        priority_role_id,
+        priority_until,
+        description,
+    } = new;
+
+    let tz = guild_tz(gid.get());
+    let chan_name = raid_channel_name(&pool_from_ctx(ctx).await?, gid, &raid_name, scheduled_for, tz).await?;
+    let raid_id = Uuid::new_v4();
+    let embed = embeds::render_new_raid_embed(&raid_name, &description, scheduled_for, duration_minutes, &max_players, tz);
+    let message = CreateMessage::new()
+        .embed(embed)
+        .components(vec![menus::main_buttons_row(raid_id)]);
+
+    // Thread mode: embed goes to the shared raids channel, talk happens in a thread under it
+    let (channel_id, msg, thread_id) = match crate::settings::get(gid.get()).raid_thread_channel_id {
+        Some(raids_channel) => {
+            let raids_channel = ChannelId::new(raids_channel as u64);
+            let msg = raids_channel.send_message(&ctx.http, message).await?;
+            let thread = raids_channel.create_thread_from_message(
+                &ctx.http,
+                msg.id,
+                CreateThread::new(&chan_name).auto_archive_duration(AutoArchiveDuration::OneWeek),
+            ).await?;
+            (raids_channel, msg, Some(thread.id.get() as i64))
+        }
+        None => {
+            let category_id = raid_category(ctx, gid, scheduled_for, tz, None).await?;
+            let text_channel = match category_id {
+                Some(cat) => {
+                    gid.create_channel(&ctx.http, CreateChannel::new(&chan_name).kind(ChannelType::Text).category(cat)).await?
+                }
+                None => {
+                    gid.create_channel(&ctx.http, CreateChannel::new(&chan_name).kind(ChannelType::Text)).await?
+                }
+            };
+            let msg = text_channel.id.send_message(&ctx.http, message).await?;
+            (text_channel.id, msg, None)
+        }
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let mut raid = repo::create_raid_with_id(&pool, NewRaidRow {
+        id: raid_id,
+        guild_id: gid.get() as i64,
+        channel_id: channel_id.get() as i64,
+        message_id: msg.id.get() as i64,
+        thread_id,
+        scheduled_for,
+        duration_minutes,
+        created_by: created_by.get() as i64,
+        owner_id: created_by.get() as i64,
         raid_name,
-        max_players as i32,
+        description,
+        max_players: max_players as i32,
         allow_alts,
-        max_alts as i32,
+        max_alts: max_alts as i32,
+        is_priority: priority,
+        priority_list: vec![],
         priority_role_id,
         priority_until,
-    ).await?;
-
-    if let Some(until) = priority_until {
-        tasks::schedule_priority_promotion(
-            ctx.http.clone(),
-            pool_from_ctx(ctx).await?,
-            raid_id,
-            gid.get() as i64,
-            text_channel.id.get() as i64,
-            msg.id.get() as i64,
-            until,
-        );
-    }
+    }).await?;
+    raid.event_id = crate::scheduled_events::sync_raid_event(&ctx.http, &pool, &raid).await;
 
     // Try refresh consolidated list (if exists in this guild)
     let _ = refresh_guild_raid_list_if_any(ctx, gid.get()).await;
-    let duration_for_schedule:i64 = dur_h.ceil() as i64;
-    tasks::schedule_auto_delete(
+    tasks::arm_raid_timers(
         ctx.http.clone(),
-        pool_from_ctx(ctx).await?,   // <—
-        raid_id,
-        text_channel.id.get() as i64,
-        scheduled_for + chrono::Duration::hours(duration_for_schedule) + chrono::Duration::minutes(20),
-    );
-    tasks::schedule_raid_15m_reminder(
-        ctx.http.clone(),
-        pool_from_ctx(ctx).await?,
+        pool,
         crate::handlers::redis_from_ctx(ctx).await?,
-        raid_id,
-        scheduled_for - chrono::Duration::minutes(15),
+        &raid,
     );
 
-    cmd.edit_response(&ctx.http, EditInteractionResponse::new()
-        .content("Raid created!")
-    ).await?;
-    Ok(())
+    Ok(raid)
 }
 
 async fn handle_kick(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
@@ -448,6 +654,8 @@ async fn handle_transfer(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Res
     let Some(new_owner) = new_owner else { return Ok(()); };
     sqlx::query!("UPDATE raids SET owner_id = $1 WHERE id = $2", new_owner.get() as i64, raid_id)
         .execute(&pool).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    crate::scheduled_events::sync_raid_event(&ctx.http, &pool, &raid).await;
     cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Ownership transferred.")).await?;
     Ok(())
 }
@@ -477,26 +685,18 @@ async fn handle_role_add(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Res
         cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Missing user.")).await?; return Ok(());
     };
 
-    // Permission: must have raid_organiser role
-    let roles_map = gid.roles(&ctx.http).await?;
-    let invoker = gid.member(&ctx.http, cmd.user.id).await?;
-    let is_organiser = invoker.roles.iter().any(|rid| {
-        roles_map.get(rid).map_or(false, |r| r.name.eq_ignore_ascii_case(ORGANISER_ROLE_NAME))
-    });
-    if !is_organiser {
-        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Only raid_organiser can use this.")).await?; return Ok(());
+    // Permission: must have the organiser role
+    if !member_has_guild_role(&ctx.http, gid, cmd.user.id, GuildRole::Organiser).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("Only {} can use this.", role_mention(gid.get(), GuildRole::Organiser)))
+        ).await?; return Ok(());
     }
 
-    // Resolve actual role name (reserve can be overridden by env)
-    let wanted_name = if role_choice.eq_ignore_ascii_case("reserve") {
-        std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string())
-    } else {
-        role_choice.clone()
+    let Some(role) = GuildRole::from_key(&role_choice) else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Unknown role.")).await?; return Ok(());
     };
-
-    let role_id = roles_map.iter().find_map(|(rid, r)| if r.name.eq_ignore_ascii_case(&wanted_name) { Some(*rid) } else { None });
-    let Some(role_id) = role_id else {
-        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Role '{}' not found on this server.", wanted_name))).await?; return Ok(());
+    let Some(role_id) = crate::settings::role_id(gid.get(), role) else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Role {} is not set up on this server.", role_mention(gid.get(), role)))).await?; return Ok(());
     };
 
     let member = gid.member(&ctx.http, user_id).await?;
@@ -512,9 +712,9 @@ async fn handle_role_add(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Res
         Ok(_) => {
             cmd.edit_response(&ctx.http, EditInteractionResponse::new()
                 .content(format!(
-                    "{} role '{}' for {}.",
+                    "{} role <@&{}> for {}.",
                     if action.eq_ignore_ascii_case("add") { "Added" } else { "Removed" },
-                    wanted_name,
+                    role_id,
                     mention_user(user_id.get() as i64)
                 ))
             ).await?;
@@ -624,20 +824,13 @@ async fn handle_move_raid_list(ctx: &Context, cmd: &CommandInteraction) -> anyho
         )
         .await;
 
-    // Permission: must have raid_organiser role
-    let roles_map = gid.roles(&ctx.http).await?;
-    let invoker = gid.member(&ctx.http, cmd.user.id).await?;
-    let is_organiser = invoker.roles.iter().any(|rid| {
-        roles_map
-            .get(rid)
-            .map_or(false, |r| r.name.eq_ignore_ascii_case(PERMISSIONS_ROLE_NAME))
-    });
-    if !is_organiser {
+    // Permission: bot admins only
+    if !is_bot_admin(&ctx.http, cmd).await? {
         cmd
             .edit_response(
                 &ctx.http,
                 EditInteractionResponse::new()
-                    .content("Only raid_organiser can use this."),
+                    .content(format!("Only {} can use this.", role_mention(gid.get(), GuildRole::Admin))),
             )
             .await?;
         return Ok(());
@@ -715,10 +908,11 @@ async fn render_all_raids_list(_ctx: &Context, pool: &sqlx::PgPool, guild_id: u6
         return Ok(vec!["Brak aktywnych rajdów.".to_string()]);
     }
 
-    // Group raids by weekday in Warsaw timezone
+    // Group raids by weekday in the guild's timezone
+    let tz = guild_tz(guild_id);
     let mut by_day: [Vec<String>; 7] = Default::default();
     for r in rows {
-        let when_local = r.scheduled_for.with_timezone(&Warsaw);
+        let when_local = r.scheduled_for.with_timezone(&tz);
         let weekday = when_local.weekday();
         let idx = match weekday {
             chrono::Weekday::Mon => 0,
@@ -730,7 +924,7 @@ async fn render_all_raids_list(_ctx: &Context, pool: &sqlx::PgPool, guild_id: u6
             chrono::Weekday::Sun => 6,
         };
         let filled = repo::count_mains(pool, r.id).await.unwrap_or(0);
-        let chan_tag = format!("<#{}>", r.channel_id as u64);
+        let chan_tag = format!("<#{}>", r.thread_id.unwrap_or(r.channel_id) as u64);
         // No owner, no time — just name, count and channel
         by_day[idx].push(format!("• {} — {}/{} — {}", r.raid_name, filled, r.max_players, chan_tag));
     }
@@ -0,0 +1,275 @@
+use std::collections::{HashMap, HashSet};
+
+use serenity::all::*;
+use serenity::builder::{CreateInputText, CreateModal, EditInteractionResponse, EditMessage};
+use uuid::Uuid;
+
+use crate::db::models::Raid;
+use crate::db::repo;
+use crate::handlers::{pool_from_ctx, redis_from_ctx};
+use crate::join_rules::{self, JoinAccess};
+use crate::queue;
+use crate::ui::{embeds, menus};
+use crate::settings::{guild_tz, role_mention, GuildRole};
+use crate::utils::{dm_user, member_has_guild_role, shift_local_days};
+
+use super::raid::NewRaid;
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_clone")
+            .description("Run a raid again N days later with the same setup")
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "raid_id", "Raid UUID (active or finished)").required(true))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "days", "Shift by this many days (default 7)").min_int_value(1).max_int_value(365))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "previous_mains", "What to do with the source raid's mains (default: nothing)")
+                    .add_string_choice("nothing", "none")
+                    .add_string_choice("invite by DM", "invite")
+                    .add_string_choice("sign up as reserve", "reserve")
+            )
+    ).await?;
+    Ok(())
+}
+
+/// What happens to the source raid's mains on the clone.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CarryMains {
+    None,
+    Invite,
+    Reserve,
+}
+
+impl CarryMains {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "none" | "no" => Some(Self::None),
+            "invite" => Some(Self::Invite),
+            "reserve" => Some(Self::Reserve),
+            _ => None,
+        }
+    }
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let mut raid_id_s = String::new();
+    let mut days: i64 = 7;
+    let mut carry = CarryMains::None;
+    for o in &cmd.data.options {
+        match o.name.as_str() {
+            "raid_id" => if let CommandDataOptionValue::String(s) = &o.value { raid_id_s = s.trim().to_string(); },
+            "days" => if let CommandDataOptionValue::Integer(n) = &o.value { days = *n; },
+            "previous_mains" => if let CommandDataOptionValue::String(s) = &o.value { carry = CarryMains::parse(s).unwrap_or(CarryMains::None); },
+            _ => {}
+        }
+    }
+
+    let Ok(raid_id) = Uuid::parse_str(&raid_id_s) else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Invalid raid_id")).await?; return Ok(());
+    };
+    let pool = pool_from_ctx(ctx).await?;
+    let source = repo::get_raid(&pool, raid_id).await?;
+
+    let reply = match clone_raid(ctx, &source, cmd.guild_id, cmd.user.id, days, carry).await? {
+        Ok(msg) | Err(msg) => msg,
+    };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/* "Clone" button in the owner panel */
+pub async fn open_clone_modal(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let modal = CreateModal::new(format!("r:clnm:{raid_id}"), "Clone raid").components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Days later", "days").value("7").required(true)
+        ),
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Previous mains: none, invite or reserve", "previous_mains")
+                .value("none")
+                .required(false)
+        ),
+    ]);
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await?;
+    Ok(())
+}
+
+/* Submit of the "Clone raid" modal */
+pub async fn handle_clone_modal(ctx: &Context, m: &ModalInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let _ = m.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let values: HashMap<&str, &str> = m.data.components.iter()
+        .flat_map(|row| row.components.iter())
+        .filter_map(|c| match c {
+            ActionRowComponent::InputText(t) => Some((t.custom_id.as_str(), t.value.as_deref().unwrap_or("").trim())),
+            _ => None,
+        })
+        .collect();
+
+    let days = values.get("days").and_then(|d| d.parse::<i64>().ok()).filter(|d| (1..=365).contains(d));
+    let carry = CarryMains::parse(values.get("previous_mains").copied().unwrap_or(""));
+    let (Some(days), Some(carry)) = (days, carry) else {
+        m.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content("Days must be 1–365 and previous mains one of `none`, `invite`, `reserve`.")
+        ).await?;
+        return Ok(());
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let source = repo::get_raid(&pool, raid_id).await?;
+    let reply = match clone_raid(ctx, &source, m.guild_id, m.user.id, days, carry).await? {
+        Ok(msg) | Err(msg) => msg,
+    };
+    m.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/// Posts a copy of `source` shifted by `days`, owned by `invoker`, who asked from `in_guild`.
+/// The inner `Err` is shown to the user.
+pub async fn clone_raid(
+    ctx: &Context,
+    source: &Raid,
+    in_guild: Option<GuildId>,
+    invoker: UserId,
+    days: i64,
+    carry: CarryMains,
+) -> anyhow::Result<Result<String, String>> {
+    let gid = GuildId::new(source.guild_id as u64);
+    if in_guild != Some(gid) {
+        return Ok(Err("Raid not found on this server.".to_string()));
+    }
+    let is_owner = source.owner_id == invoker.get() as i64;
+    if !is_owner {
+        let is_organiser = match member_has_guild_role(&ctx.http, gid, invoker, GuildRole::Organiser).await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("clone: role check for {} in guild {} failed: {e:#}", invoker, gid);
+                return Ok(Err("Couldn't check your roles on this server. Try again later.".to_string()));
+            }
+        };
+        if !is_organiser {
+            return Ok(Err(format!("Only the raid owner or {} can clone this raid.", role_mention(gid.get(), GuildRole::Organiser))));
+        }
+    }
+
+    let scheduled_for = match shift_local_days(source.scheduled_for, days, guild_tz(gid.get())) {
+        Ok(at) => at,
+        Err(msg) => return Ok(Err(msg)),
+    };
+    if scheduled_for <= chrono::Utc::now() {
+        return Ok(Err(format!("{} days later is still in the past; pick more days.", days)));
+    }
+
+    let new = NewRaid {
+        guild_id: gid,
+        created_by: invoker,
+        raid_name: source.raid_name.clone(),
+        scheduled_for,
+        duration_minutes: source.duration_minutes,
+        max_players: source.max_players as i64,
+        allow_alts: source.allow_alts,
+        max_alts: source.max_alts as i64,
+        priority: source.is_priority,
+        priority_role_id: source.priority_role_id.clone(),
+        // Same window length before the start
+        priority_until: source.priority_until.map(|until| scheduled_for - (source.scheduled_for - until)),
+        description: source.description.clone(),
+    };
+    let mut raid = super::raid::create_raid(ctx, new).await?;
+
+    let pool = pool_from_ctx(ctx).await?;
+    if !source.composition.is_empty() {
+        repo::set_raid_composition(&pool, raid.id, &source.composition).await?;
+        raid = repo::get_raid(&pool, raid.id).await?;
+    }
+    if source.promotion_policy.is_some() || source.min_points > 0 {
+        raid.promotion_policy = source.promotion_policy.clone();
+        raid.min_points = source.min_points;
+        raid = repo::update_raid_settings(&pool, &raid).await?;
+    }
+    let old_parts = repo::list_participants(&pool, source.id).await?;
+    let when_local = raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
+    let mut carried = 0;
+    let mut skipped = 0;
+    match carry {
+        CarryMains::None => {}
+        CarryMains::Invite => {
+            let users: HashSet<i64> = old_parts.iter().filter(|p| p.is_main).map(|p| p.user_id).collect();
+            for uid in users {
+                dm_user(&ctx.http, uid as u64, format!(
+                    "📨 **{}** is on again on {} and you were a main last time.\nSign up: <#{}>",
+                    raid.raid_name, when_local, raid.chat_channel_id()
+                )).await;
+                carried += 1;
+            }
+        }
+        CarryMains::Reserve => {
+            // Same path as a signup from the join menu: the join rules decide who may still come, the
+            // queue signs them up as reserve and its promotion run fills the empty main slots.
+            // One row per character; alts stay off so the alt limits need no checking.
+            let rules = join_rules::rules_for(&pool, gid.get(), &raid.raid_name).await?;
+            let redis = redis_from_ctx(ctx).await?;
+            let mut users: Vec<i64> = Vec::new();
+            for p in old_parts.iter().filter(|p| p.is_main && !p.is_alt) {
+                let Ok(member) = gid.member(&ctx.http, UserId::new(p.user_id as u64)).await else {
+                    skipped += 1;
+                    continue;
+                };
+                let (access, tag_suffix) = rules.join_access(&member.roles);
+                if access == JoinAccess::Denied {
+                    skipped += 1;
+                    continue;
+                }
+                let ev = queue::RaidEvent::Join(queue::JoinEvent {
+                    raid_id: raid.id,
+                    guild_id: raid.guild_id,
+                    user_id: p.user_id,
+                    class: p.class.clone(),
+                    active_sp: p.active_sp.clone(),
+                    main_now: false,
+                    tag_suffix,
+                    is_alt: false,
+                    extra_sps: Some(p.extra_sps.clone()),
+                });
+                let corr = queue::publish(&redis, &ev).await?;
+                let _ack = queue::wait_for_ack(&redis, &corr, 900).await?;
+                users.push(p.user_id);
+            }
+            let parts = repo::list_participants(&pool, raid.id).await?;
+            for uid in users {
+                let Some(row) = parts.iter().find(|p| p.user_id == uid && !p.is_alt) else { continue; };
+                dm_user(&ctx.http, uid as u64, format!(
+                    "📝 You were signed up as **{}** as {} for **{}** on {}, since you were a main last time.\nChannel: <#{}>",
+                    if row.is_main { "MAIN" } else { "RESERVE" }, row.joined_as(), raid.raid_name, when_local, raid.chat_channel_id()
+                )).await;
+                carried += 1;
+            }
+        }
+    }
+    // The message was posted before the reserves, composition and points minimum were added
+    if (carried > 0 && matches!(carry, CarryMains::Reserve)) || !raid.composition.is_empty() || raid.min_points > 0 {
+        let parts = repo::list_participants(&pool, raid.id).await?;
+        let embed = embeds::render_raid_embed(ctx, gid.get(), &raid, &parts);
+        ChannelId::new(raid.channel_id as u64)
+            .edit_message(&ctx.http, raid.message_id as u64,
+                          EditMessage::new().embed(embed).components(vec![menus::main_buttons_row(raid.id), menus::sp_buttons_row(raid.id)]))
+            .await?;
+    }
+
+    let note = match carry {
+        CarryMains::None => String::new(),
+        CarryMains::Invite => format!(" Invited {} previous main(s).", carried),
+        CarryMains::Reserve if skipped > 0 => format!(
+            " Signed up {} previous main(s); {} no longer may join and were left out.", carried, skipped
+        ),
+        CarryMains::Reserve => format!(" Signed up {} previous main(s).", carried),
+    };
+    let _ = super::raid::force_refresh_guild_raid_list(ctx, gid.get()).await;
+    Ok(Ok(format!("Raid cloned for {}: <#{}>.{}", when_local, raid.chat_channel_id(), note)))
+}
@@ -0,0 +1,141 @@
+use serenity::all::*;
+use serenity::builder::{EditInteractionResponse, EditMessage};
+use uuid::Uuid;
+
+use crate::catalog;
+use crate::composition;
+use crate::db::models::CompositionRule;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::tasks;
+use crate::ui::{embeds, menus};
+
+fn raid_id_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "raid_id", "Raid UUID").required(true)
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_composition")
+            .description("Required or limited classes/SPs of a raid (owner only)")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Add a class/SP limit or change one")
+                    .add_sub_option(raid_id_option())
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class code").required(true).set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "sp", "Only this SP, e.g. SP3 (default: any SP)"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "min", "Mains needed (default 0)").min_int_value(0))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "max", "Most mains allowed (default: no limit)").min_int_value(0))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "clear", "Remove limits (all of them without a class)")
+                    .add_sub_option(raid_id_option())
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "class", "Class code").set_autocomplete(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "sp", "Only the limit of this SP"))
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "show", "Show the limits and how they are met")
+                    .add_sub_option(raid_id_option())
+            )
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let mut raid_id_s = String::new();
+    let mut class = String::new();
+    let mut sp: Option<String> = None;
+    let mut min: i32 = 0;
+    let mut max: Option<i32> = None;
+    for o in opts {
+        match (o.name.as_str(), &o.value) {
+            ("raid_id", CommandDataOptionValue::String(v)) => raid_id_s = v.trim().to_string(),
+            ("class", CommandDataOptionValue::String(v)) => class = v.trim().to_string(),
+            ("sp", CommandDataOptionValue::String(v)) => sp = Some(v.replace(' ', "").to_ascii_uppercase()).filter(|v| !v.is_empty()),
+            ("min", CommandDataOptionValue::Integer(v)) => min = *v as i32,
+            ("max", CommandDataOptionValue::Integer(v)) => max = Some(*v as i32),
+            _ => {}
+        }
+    }
+
+    let Ok(raid_id) = Uuid::parse_str(&raid_id_s) else {
+        return edit(ctx, cmd, "Invalid raid_id".to_string()).await;
+    };
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if sub.name != "show" {
+        if let Some(msg) = super::raid_edit::deny_edit(&raid, cmd.user.id) {
+            return edit(ctx, cmd, msg.to_string()).await;
+        }
+    }
+
+    // Rules are stored under the catalog code of the class
+    if !class.is_empty() {
+        match catalog::find_class(&class) {
+            Some(c) => class = c.code,
+            None => return edit(ctx, cmd, format!("Unknown class `{}`.", class)).await,
+        }
+    }
+
+    let mut rules = raid.composition.0.clone();
+    let same = |r: &CompositionRule, sp: &Option<String>| r.class == class && r.sp == *sp;
+    match sub.name.as_str() {
+        "show" => {}
+        "set" => {
+            if let Some(sp) = sp.as_deref().filter(|sp| !catalog::sp_allowed(&class, sp)) {
+                return edit(ctx, cmd, format!("{} is not an SP of class {}.", sp, class)).await;
+            }
+            if max.is_some_and(|max| max < min) {
+                return edit(ctx, cmd, "`max` can't be lower than `min`.".to_string()).await;
+            }
+            let rule = CompositionRule { class: class.clone(), sp: sp.clone(), min, max };
+            match rules.iter_mut().find(|r| same(r, &sp)) {
+                Some(r) => *r = rule,
+                None => rules.push(rule),
+            }
+        }
+        "clear" => {
+            let before = rules.len();
+            rules.retain(|r| !class.is_empty() && !(r.class == class && (sp.is_none() || r.sp == sp)));
+            if rules.len() == before {
+                return edit(ctx, cmd, "Nothing to remove.".to_string()).await;
+            }
+        }
+        _ => return Ok(()),
+    }
+
+    if sub.name != "show" {
+        repo::set_raid_composition(&pool, raid_id, &rules).await?;
+        let raid = repo::get_raid(&pool, raid_id).await?;
+        // Reserves that now fit (or fill a minimum) move up; mains over a new maximum stay
+        tasks::rebalance_roster(&ctx.http, &pool, &raid).await?;
+        let parts = repo::list_participants(&pool, raid_id).await?;
+        let embed = embeds::render_raid_embed(ctx, raid.guild_id as u64, &raid, &parts);
+        ChannelId::new(raid.channel_id as u64)
+            .edit_message(&ctx.http, raid.message_id as u64,
+                          EditMessage::new().embed(embed).components(vec![menus::main_buttons_row(raid.id), menus::sp_buttons_row(raid.id)]))
+            .await?;
+        crate::commands::raid::trigger_refresh(ctx, raid.guild_id as u64).await;
+    }
+
+    let parts = repo::list_participants(&pool, raid_id).await?;
+    let reply = if rules.is_empty() {
+        format!("**{}** has no composition limits.", raid.raid_name)
+    } else {
+        format!("**Composition of {}**\n{}", raid.raid_name, composition::summary(&rules, &parts).join("\n"))
+    };
+    edit(ctx, cmd, reply).await
+}
+
+async fn edit(ctx: &Context, cmd: &CommandInteraction, content: String) -> anyhow::Result<()> {
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
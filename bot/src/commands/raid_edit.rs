@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+
+use serenity::all::*;
+use serenity::builder::{CreateInputText, CreateModal, EditChannel, EditInteractionResponse, EditMessage};
+use uuid::Uuid;
+
+use crate::db::models::Raid;
+use crate::db::repo;
+use crate::handlers::{pool_from_ctx, redis_from_ctx};
+use crate::settings::{guild_tz, PromotionPolicy};
+use crate::tasks;
+use crate::ui::{embeds, menus};
+use crate::utils::{dm_user, parse_list_unique, parse_raid_datetime};
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_edit")
+            .description("Change time, slots or description of a raid (owner only)")
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "raid_id", "Raid UUID").required(true))
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "raid_date", "New time, e.g. fri 19:00 or 20:00 2025-06-01"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "duration_minutes", "Raid length in minutes").min_int_value(1).max_int_value(24 * 60))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_players", "Main slots").min_int_value(1))
+            .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "allow_alts", "Allow alts"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "max_alts", "Alt slots").min_int_value(0))
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "description", "Short description"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "priority_hours", "Priority window before start in hours (0 = off)").min_int_value(0))
+            .add_option(CreateCommandOption::new(CommandOptionType::String, "prioritylist", "Role names for priority (e.g., Maraton)"))
+            .add_option(promotion_option())
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "min_points", "Points needed for a main slot (0 = none)").min_int_value(0))
+    ).await?;
+    Ok(())
+}
+
+fn promotion_option() -> CreateCommandOption {
+    let mut opt = CreateCommandOption::new(CommandOptionType::String, "promotion", "Order in which reserves move up")
+        .add_string_choice("Server default", "default");
+    for policy in PromotionPolicy::ALL {
+        opt = opt.add_string_choice(policy.label(), policy.key());
+    }
+    opt
+}
+
+/// Changes requested by `/raid_edit` or the "Edit raid" modal; `None` keeps the current value.
+#[derive(Default)]
+pub struct RaidEdit {
+    pub scheduled_for: Option<chrono::DateTime<chrono::Utc>>,
+    pub duration_minutes: Option<i32>,
+    pub max_players: Option<i32>,
+    pub allow_alts: Option<bool>,
+    pub max_alts: Option<i32>,
+    pub description: Option<String>,
+    pub priority_hours: Option<i64>, // Some(0) turns the window off
+    pub priority_role_ids: Option<Vec<i64>>,
+    pub promotion_policy: Option<Option<String>>, // Some(None) goes back to the server's policy
+    pub min_points: Option<i32>,
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let mut raid_id_s = String::new();
+    let mut raid_date_str: Option<String> = None;
+    let mut priority_role_name: Vec<String> = Vec::new();
+    let mut edit = RaidEdit::default();
+    for o in &cmd.data.options {
+        match o.name.as_str() {
+            "raid_id" => if let CommandDataOptionValue::String(s) = &o.value { raid_id_s = s.trim().to_string(); },
+            "raid_date" => if let CommandDataOptionValue::String(s) = &o.value { raid_date_str = Some(s.clone()); },
+            "duration_minutes" => if let CommandDataOptionValue::Integer(n) = &o.value { edit.duration_minutes = Some(*n as i32); },
+            "max_players" => if let CommandDataOptionValue::Integer(n) = &o.value { edit.max_players = Some(*n as i32); },
+            "allow_alts" => if let CommandDataOptionValue::Boolean(b) = &o.value { edit.allow_alts = Some(*b); },
+            "max_alts" => if let CommandDataOptionValue::Integer(n) = &o.value { edit.max_alts = Some(*n as i32); },
+            "description" => if let CommandDataOptionValue::String(s) = &o.value { edit.description = Some(s.clone()); },
+            "priority_hours" => if let CommandDataOptionValue::Integer(n) = &o.value { edit.priority_hours = Some(*n); },
+            "prioritylist" => if let CommandDataOptionValue::String(s) = &o.value { priority_role_name = parse_list_unique(s); },
+            "min_points" => if let CommandDataOptionValue::Integer(n) = &o.value { edit.min_points = Some(*n as i32); },
+            "promotion" => if let CommandDataOptionValue::String(s) = &o.value {
+                edit.promotion_policy = Some(Some(s.clone()).filter(|s| s != "default"));
+            },
+            _ => {}
+        }
+    }
+
+    let Ok(raid_id) = Uuid::parse_str(&raid_id_s) else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Invalid raid_id")).await?; return Ok(());
+    };
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if let Some(msg) = deny_edit(&raid, cmd.user.id) {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(msg)).await?; return Ok(());
+    }
+
+    let tz = guild_tz(raid.guild_id as u64);
+    if let Some(s) = raid_date_str {
+        match parse_raid_datetime(&s, tz) {
+            Ok(dt) => edit.scheduled_for = Some(dt),
+            Err(msg) => {
+                cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+                    .content(format!("❌ {}\nTimes are in server time ({}).", msg, tz.name()))
+                ).await?;
+                return Ok(());
+            }
+        }
+    }
+    if !priority_role_name.is_empty() {
+        let gid = GuildId::new(raid.guild_id as u64);
+        match crate::commands::raid::resolve_priority_role_ids(ctx, gid, cmd.user.id, &priority_role_name).await? {
+            Ok(ids) => edit.priority_role_ids = Some(ids),
+            Err(msg) => {
+                cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(msg)).await?; return Ok(());
+            }
+        }
+    }
+
+    let reply = match apply_raid_edit(ctx, raid, edit).await? { Ok(s) | Err(s) => s };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/* Owner-only, and only while the raid is live */
+pub fn deny_edit(raid: &Raid, user: UserId) -> Option<&'static str> {
+    if raid.owner_id != user.get() as i64 {
+        Some("Only the raid owner can edit.")
+    } else if !raid.is_active {
+        Some("This raid has been cancelled. Editing is no longer available.")
+    } else {
+        None
+    }
+}
+
+/* Values the modal is prefilled with; unchanged fields are left alone on submit */
+fn modal_when(raid: &Raid) -> String {
+    raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%H:%M %Y-%m-%d").to_string()
+}
+
+fn modal_priority_hours(raid: &Raid) -> String {
+    raid.priority_until
+        .map(|until| (raid.scheduled_for - until).num_hours().to_string())
+        .unwrap_or_else(|| "0".to_string())
+}
+
+fn modal_alts(raid: &Raid) -> String {
+    if raid.allow_alts { raid.max_alts.to_string() } else { "0".to_string() }
+}
+
+/* "Edit raid" button in the Manage panel */
+pub async fn open_edit_modal(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if let Some(msg) = deny_edit(&raid, it.user.id) {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(msg).ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+
+    let tz = guild_tz(raid.guild_id as u64);
+    let input = |style, label: &str, id: &str, value: String| {
+        CreateActionRow::InputText(CreateInputText::new(style, label, id).value(value).required(true))
+    };
+    let modal = CreateModal::new(format!("r:edm:{raid_id}"), "Edit raid").components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Date & time", "when")
+                .value(modal_when(&raid))
+                .placeholder(format!("e.g. fri 19:00 ({})", tz.name()))
+                .required(true)
+        ),
+        input(InputTextStyle::Short, "Main slots", "max_players", raid.max_players.to_string()),
+        input(InputTextStyle::Short, "Alt slots (0 = no alts)", "max_alts", modal_alts(&raid)),
+        input(InputTextStyle::Short, "Priority hours before start (0 = off)", "priority_hours", modal_priority_hours(&raid)),
+        input(InputTextStyle::Paragraph, "Description", "description", raid.description.clone()),
+    ]);
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await?;
+    Ok(())
+}
+
+/* Submit of the "Edit raid" modal */
+pub async fn handle_edit_modal(ctx: &Context, m: &ModalInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let _ = m.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if let Some(msg) = deny_edit(&raid, m.user.id) {
+        m.edit_response(&ctx.http, EditInteractionResponse::new().content(msg)).await?; return Ok(());
+    }
+
+    let values: HashMap<&str, &str> = m.data.components.iter()
+        .flat_map(|row| row.components.iter())
+        .filter_map(|c| match c {
+            ActionRowComponent::InputText(t) => Some((t.custom_id.as_str(), t.value.as_deref().unwrap_or("").trim())),
+            _ => None,
+        })
+        .collect();
+    let field = |id: &str| values.get(id).copied().unwrap_or("");
+
+    let mut edit = RaidEdit::default();
+    let tz = guild_tz(raid.guild_id as u64);
+    let reply = 'parse: {
+        if field("when") != modal_when(&raid) {
+            match parse_raid_datetime(field("when"), tz) {
+                Ok(dt) => edit.scheduled_for = Some(dt),
+                Err(msg) => break 'parse format!("❌ {}\nTimes are in server time ({}).", msg, tz.name()),
+            }
+        }
+        match field("max_players").parse::<i32>() {
+            Ok(n) if n != raid.max_players => edit.max_players = Some(n),
+            Ok(_) => {}
+            Err(_) => break 'parse "Main slots must be a number.".to_string(),
+        }
+        if field("max_alts") != modal_alts(&raid) {
+            match field("max_alts").parse::<i32>() {
+                Ok(0) => edit.allow_alts = Some(false),
+                Ok(n) => { edit.allow_alts = Some(true); edit.max_alts = Some(n); }
+                Err(_) => break 'parse "Alt slots must be a number.".to_string(),
+            }
+        }
+        if field("priority_hours") != modal_priority_hours(&raid) {
+            match field("priority_hours").parse::<i64>() {
+                Ok(h) => edit.priority_hours = Some(h),
+                Err(_) => break 'parse "Priority window must be a number of hours.".to_string(),
+            }
+        }
+        if field("description") != raid.description.trim() {
+            edit.description = Some(field("description").to_string());
+        }
+        match apply_raid_edit(ctx, raid, edit).await? { Ok(s) | Err(s) => s }
+    };
+    m.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+/// Validates and stores the edit, then brings everything derived from the raid row up to date:
+/// channel name and weekday category, the raid embed, the consolidated list, timers, and DMs on a time change.
+/// The inner `Err` is the message to show the user.
+pub async fn apply_raid_edit(ctx: &Context, raid: Raid, edit: RaidEdit) -> anyhow::Result<Result<String, String>> {
+    let old = raid.clone();
+    let mut new = raid;
+
+    if let Some(t) = edit.scheduled_for { new.scheduled_for = t; }
+    if let Some(d) = edit.duration_minutes { new.duration_minutes = d; }
+    if let Some(n) = edit.max_players {
+        if n < 1 { return Ok(Err("Main slots must be at least 1.".to_string())); }
+        new.max_players = n;
+    }
+    if let Some(b) = edit.allow_alts { new.allow_alts = b; }
+    if let Some(n) = edit.max_alts {
+        if n < 0 { return Ok(Err("Alt slots can't be negative.".to_string())); }
+        new.max_alts = n;
+    }
+    if let Some(d) = edit.description {
+        if d.trim().is_empty() { return Ok(Err("Description can't be empty.".to_string())); }
+        new.description = d;
+    }
+    if let Some(p) = edit.promotion_policy { new.promotion_policy = p; }
+    if let Some(n) = edit.min_points {
+        if n < 0 { return Ok(Err("Minimum points can't be negative.".to_string())); }
+        new.min_points = n;
+    }
+    if let Some(ids) = edit.priority_role_ids {
+        new.priority_role_id = Some(ids);
+        new.is_priority = true;
+    }
+    match edit.priority_hours {
+        Some(0) => {
+            new.is_priority = false;
+            new.priority_until = None;
+        }
+        Some(h) if h < 0 => return Ok(Err("Priority window can't be negative.".to_string())),
+        Some(h) => {
+            if new.priority_role_id.as_ref().is_none_or(|ids| ids.is_empty()) {
+                return Ok(Err("This raid has no priority roles. Set `prioritylist` as well.".to_string()));
+            }
+            new.is_priority = true;
+            new.priority_until = Some(new.scheduled_for - chrono::Duration::hours(h));
+        }
+        // Window keeps its length when the raid moves
+        None => new.priority_until = old.priority_until.map(|until| until + (new.scheduled_for - old.scheduled_for)),
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::update_raid_settings(&pool, &new).await?;
+    let time_changed = raid.scheduled_for != old.scheduled_for;
+    let gid = GuildId::new(raid.guild_id as u64);
+    let tz = guild_tz(gid.get());
+
+    if time_changed {
+        let name = crate::commands::raid::raid_channel_name(&pool, gid, &raid.raid_name, raid.scheduled_for, tz).await?;
+        let mut channel_edit = EditChannel::new().name(name);
+        // Thread mode renames the thread; the raids channel is shared and stays put
+        let channel_id = match raid.thread_id {
+            Some(thread_id) => ChannelId::new(thread_id as u64),
+            None => {
+                if let Some(cat) = crate::commands::raid::raid_category(ctx, gid, raid.scheduled_for, tz, Some(ChannelId::new(raid.channel_id as u64))).await? {
+                    channel_edit = channel_edit.category(cat);
+                }
+                ChannelId::new(raid.channel_id as u64)
+            }
+        };
+        // Renames are rate limited hard (2 per 10 min), so don't hold the reply on it
+        let http = ctx.http.clone();
+        tokio::spawn(async move {
+            if let Err(e) = channel_id.edit(&http, channel_edit).await {
+                eprintln!("raid_edit: channel update failed: {e}");
+            }
+        });
+    }
+
+    let slots_changed = raid.max_players != old.max_players
+        || raid.max_alts != old.max_alts
+        || raid.is_priority != old.is_priority
+        || raid.min_points < old.min_points;
+    if slots_changed {
+        tasks::rebalance_roster(&ctx.http, &pool, &raid).await?;
+    }
+
+    let parts = repo::list_participants(&pool, raid.id).await?;
+    let embed = embeds::render_raid_embed(ctx, gid.get(), &raid, &parts);
+    ChannelId::new(raid.channel_id as u64)
+        .edit_message(&ctx.http, raid.message_id as u64,
+                      EditMessage::new().embed(embed).components(vec![menus::main_buttons_row(raid.id), menus::sp_buttons_row(raid.id)]))
+        .await?;
+
+    tasks::arm_raid_timers(ctx.http.clone(), pool.clone(), redis_from_ctx(ctx).await?, &raid);
+    crate::scheduled_events::sync_raid_event(&ctx.http, &pool, &raid).await;
+    crate::commands::raid::trigger_refresh(ctx, gid.get()).await;
+
+    if !time_changed {
+        return Ok(Ok("Raid updated.".to_string()));
+    }
+
+    let old_local = old.scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z");
+    let new_local = raid.scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z");
+    let users: HashSet<i64> = parts.iter().map(|p| p.user_id).collect();
+    for uid in &users {
+        let msg = format!(
+            "🕒 **{}** has been moved from **{}** to **{}**.\nChannel: <#{}>",
+            raid.raid_name, old_local, new_local, raid.chat_channel_id()
+        );
+        dm_user(&ctx.http, *uid as u64, msg).await;
+    }
+    Ok(Ok(format!("Raid updated and moved to **{}**. {} participant(s) notified.", new_local, users.len())))
+}
@@ -0,0 +1,177 @@
+use serenity::all::*;
+use serenity::builder::{CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditInteractionResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::guild_tz;
+use crate::ui::embeds;
+use crate::utils::{from_user_id, mention_user};
+
+const PAGE_SIZE: i64 = 10;
+
+/* One `/raid_history` listing. It lives entirely in the custom_ids of its components
+   ("<page>:<user id or 0>"), so buttons keep working after a restart. */
+struct HistoryView {
+    guild_id: GuildId,
+    user: Option<UserId>,
+    page: i64,
+}
+
+impl HistoryView {
+    fn key(&self, page: i64) -> String {
+        format!("{}:{}", page, self.user.map(|u| u.get()).unwrap_or(0))
+    }
+
+    fn from_key(guild_id: GuildId, key: &str) -> Option<HistoryView> {
+        let (page, user) = key.split_once(':')?;
+        Some(HistoryView {
+            guild_id,
+            user: Some(user.parse::<u64>().ok()?).filter(|u| *u != 0).map(UserId::new),
+            page: page.parse().ok()?,
+        })
+    }
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_history")
+            .description("Past raids of this server with their final roster")
+            .add_option(CreateCommandOption::new(CommandOptionType::User, "user", "Only raids this player signed up for"))
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+    let mut user: Option<UserId> = None;
+    for o in &cmd.data.options {
+        if let ("user", CommandDataOptionValue::User(u)) = (o.name.as_str(), &o.value) {
+            user = Some(*u);
+        }
+    }
+
+    let view = HistoryView { guild_id: gid, user, page: 0 };
+    let pool = pool_from_ctx(ctx).await?;
+    let (content, rows) = render_list(&pool, &view).await?;
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(content).components(rows)).await?;
+    Ok(())
+}
+
+async fn render_list(pool: &PgPool, view: &HistoryView) -> anyhow::Result<(String, Vec<CreateActionRow>)> {
+    let guild_id = view.guild_id.get() as i64;
+    let user_id = view.user.map(from_user_id);
+    let total = repo::count_raid_history(pool, guild_id, user_id).await?;
+    let pages = ((total + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
+    let page = view.page.clamp(0, pages - 1);
+    let rows = repo::list_raid_history(pool, guild_id, user_id, PAGE_SIZE, page * PAGE_SIZE).await?;
+
+    let title = match view.user {
+        Some(u) => format!("**Raid history of {}**", u.mention()),
+        None => "**Raid history**".to_string(),
+    };
+    if rows.is_empty() {
+        return Ok((format!("{}\nNo past raids yet.", title), vec![]));
+    }
+
+    let tz = guild_tz(view.guild_id.get());
+    let lines = rows
+        .iter()
+        .map(|r| {
+            let state = if r.cancelled_at.is_some() { " · ❌ cancelled" } else { "" };
+            let role = match r.user_main {
+                Some(true) => " · main",
+                Some(false) => " · reserve",
+                None => "",
+            };
+            format!(
+                "{} — **{}** · {} · {}/{} mains, {} reserve(s){}{}",
+                r.scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+                r.raid_name,
+                mention_user(r.owner_id),
+                r.mains,
+                r.max_players,
+                r.reserves,
+                role,
+                state
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let content = format!("{} ({} raids · page {}/{})\n{}", title, total, page + 1, pages, lines);
+
+    let options: Vec<CreateSelectMenuOption> = rows
+        .iter()
+        .map(|r| {
+            let label = format!("{} · {}", r.scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M"), r.raid_name);
+            CreateSelectMenuOption::new(label.chars().take(100).collect::<String>(), r.id.to_string())
+        })
+        .collect();
+    let select = CreateSelectMenu::new(format!("r:rhd:{}", view.key(page)), CreateSelectMenuKind::String { options })
+        .placeholder("Show a raid's roster");
+    let buttons = vec![
+        CreateButton::new(format!("r:rhp:{}", view.key(page - 1)))
+            .label("◀ Prev")
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new(format!("r:rhp:{}", view.key(page + 1)))
+            .label("Next ▶")
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= pages),
+    ];
+    Ok((content, vec![CreateActionRow::SelectMenu(select), CreateActionRow::Buttons(buttons)]))
+}
+
+/* Prev / Next, and Back from a raid: `key` names the page to show */
+pub async fn page(ctx: &Context, it: &ComponentInteraction, key: &str) -> anyhow::Result<()> {
+    let Some(view) = it.guild_id.and_then(|gid| HistoryView::from_key(gid, key)) else { return Ok(()); };
+    let pool = pool_from_ctx(ctx).await?;
+    let (content, rows) = render_list(&pool, &view).await?;
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new().content(content).embeds(vec![]).components(rows)
+    )).await?;
+    Ok(())
+}
+
+/* Select: the raid's final roster through the same embed the raid was shown with */
+pub async fn detail(ctx: &Context, it: &ComponentInteraction, key: &str) -> anyhow::Result<()> {
+    let Some(view) = it.guild_id.and_then(|gid| HistoryView::from_key(gid, key)) else { return Ok(()); };
+    let ComponentInteractionDataKind::StringSelect { values } = &it.data.kind else { return Ok(()); };
+    let Some(raid_id) = values.first().and_then(|v| Uuid::parse_str(v).ok()) else { return Ok(()); };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if raid.guild_id != view.guild_id.get() as i64 {
+        return Ok(());
+    }
+    let parts = repo::list_participants(&pool, raid_id).await?;
+    let tz = guild_tz(view.guild_id.get());
+    let status = match raid.cancelled_at {
+        Some(at) => format!("❌ Cancelled {}", at.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z")),
+        None if raid.is_active => "Over, channel still open".to_string(),
+        None => "✅ Finished".to_string(),
+    };
+    let embed = embeds::render_raid_embed_plain(&raid, &parts)
+        .field("Status", status, true)
+        .footer(CreateEmbedFooter::new(format!("Raid ID: {}", raid.id)));
+
+    let back = CreateButton::new(format!("r:rhp:{}", view.key(view.page)))
+        .label("◀ Back to list")
+        .style(ButtonStyle::Secondary);
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content("")
+            .embed(embed)
+            .components(vec![CreateActionRow::Buttons(vec![back])])
+    )).await?;
+    Ok(())
+}
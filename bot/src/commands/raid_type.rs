@@ -0,0 +1,191 @@
+use serenity::all::*;
+use serenity::builder::{CreateAutocompleteResponse, EditInteractionResponse};
+
+use crate::db::models::RaidType;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::{role_mention, GuildRole};
+use crate::utils::{is_bot_admin, raid_slug};
+
+/* Optional fields shared by `add` and `edit` */
+fn type_fields(sub: CreateCommandOption) -> CreateCommandOption {
+    sub.add_sub_option(CreateCommandOption::new(CommandOptionType::String, "emoji", "Emoji used in the channel name"))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "slug", "Channel name part (default: from name)"))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "max_players", "Default main slots").min_int_value(1))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "duration_minutes", "Default raid length (minutes)").min_int_value(1))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "allow_alts", "Allow alts by default"))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "max_alts", "Default alt slots").min_int_value(0))
+}
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_type")
+            .description("Manage this server's raid types (bot admins only)")
+            .add_option(type_fields(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a raid type")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Raid name, e.g. Fernon").required(true))
+            ))
+            .add_option(type_fields(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "edit", "Change a raid type's defaults")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Raid type").required(true).set_autocomplete(true))
+            ))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Hide a raid type from /raid")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Raid type").required(true).set_autocomplete(true))
+            )
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List raid types"))
+    ).await?;
+    Ok(())
+}
+
+pub async fn autocomplete_raid_types(ctx: &Context, ac: &CommandInteraction, typed: &str) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let guild_id = ac.guild_id.map(|g| g.get() as i64).unwrap_or(0);
+    let rows = repo::search_raid_types(&pool, guild_id, typed.trim(), 25).await?; // Discord limit for choices
+    let mut resp = CreateAutocompleteResponse::new();
+    for t in rows {
+        resp = resp.add_string_choice(format!("{} {}", t.emoji, t.name), t.name);
+    }
+    ac.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(resp)).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    // Listing is open to everyone; changing the catalog needs the permissions role
+    if sub.name != "list" && !is_bot_admin(&ctx.http, cmd).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("Only {} can change raid types.", role_mention(gid.get(), GuildRole::Admin)))
+        ).await?;
+        return Ok(());
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let reply = match sub.name.as_str() {
+        "add" => handle_add(&pool, gid, opts).await?,
+        "edit" => handle_edit(&pool, gid, opts).await?,
+        "remove" => handle_remove(&pool, gid, opts).await?,
+        "list" => handle_list(&pool, gid).await?,
+        _ => return Ok(()),
+    };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+fn opt_string(opts: &[CommandDataOption], name: &str) -> Option<String> {
+    opts.iter().find(|o| o.name == name).and_then(|o| match &o.value {
+        CommandDataOptionValue::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        _ => None,
+    })
+}
+
+fn opt_int(opts: &[CommandDataOption], name: &str) -> Option<i32> {
+    opts.iter().find(|o| o.name == name).and_then(|o| match &o.value {
+        CommandDataOptionValue::Integer(n) => Some(*n as i32),
+        _ => None,
+    })
+}
+
+fn opt_bool(opts: &[CommandDataOption], name: &str) -> Option<bool> {
+    opts.iter().find(|o| o.name == name).and_then(|o| match &o.value {
+        CommandDataOptionValue::Boolean(b) => Some(*b),
+        _ => None,
+    })
+}
+
+/* Overlay only the options the admin actually filled in */
+fn apply_fields(mut t: RaidType, opts: &[CommandDataOption]) -> RaidType {
+    if let Some(v) = opt_string(opts, "emoji") { t.emoji = v; }
+    if let Some(v) = opt_string(opts, "slug") { t.slug = raid_slug(&v); }
+    if let Some(v) = opt_int(opts, "max_players") { t.default_max_players = v; }
+    if let Some(v) = opt_int(opts, "duration_minutes") { t.default_duration_minutes = v; }
+    if let Some(v) = opt_bool(opts, "allow_alts") { t.default_allow_alts = v; }
+    if let Some(v) = opt_int(opts, "max_alts") { t.default_max_alts = v; }
+    t
+}
+
+fn describe(t: &RaidType) -> String {
+    format!(
+        "{} **{}** (`{}`) — {} slots, {} min, alts: {} (max {})",
+        t.emoji,
+        t.name,
+        t.slug,
+        t.default_max_players,
+        t.default_duration_minutes,
+        if t.default_allow_alts { "yes" } else { "no" },
+        t.default_max_alts
+    )
+}
+
+async fn handle_add(pool: &sqlx::PgPool, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let Some(name) = opt_string(opts, "name") else { return Ok("Missing name.".to_string()); };
+
+    let existing = repo::get_raid_type(pool, gid.get() as i64, &name).await?;
+    if existing.as_ref().is_some_and(|t| t.is_active) {
+        return Ok(format!("Raid type `{}` already exists. Use `/raid_type edit`.", name));
+    }
+
+    // A removed type comes back under its original spelling
+    let name = existing.map(|t| t.name).unwrap_or(name);
+    let base = RaidType {
+        guild_id: gid.get() as i64,
+        slug: raid_slug(&name),
+        name,
+        emoji: "🏷️".to_string(),
+        default_max_players: 12,
+        default_duration_minutes: 60,
+        default_allow_alts: true,
+        default_max_alts: 1,
+        is_active: true,
+    };
+    let saved = repo::upsert_raid_type(pool, gid.get() as i64, &apply_fields(base, opts)).await?;
+    Ok(format!("Added {}", describe(&saved)))
+}
+
+async fn handle_edit(pool: &sqlx::PgPool, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let Some(name) = opt_string(opts, "name") else { return Ok("Missing name.".to_string()); };
+    let Some(current) = repo::get_raid_type(pool, gid.get() as i64, &name).await?.filter(|t| t.is_active) else {
+        return Ok(format!("Raid type `{}` not found.", name));
+    };
+    let saved = repo::upsert_raid_type(pool, gid.get() as i64, &apply_fields(current, opts)).await?;
+    Ok(format!("Updated {}", describe(&saved)))
+}
+
+async fn handle_remove(pool: &sqlx::PgPool, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let Some(name) = opt_string(opts, "name") else { return Ok("Missing name.".to_string()); };
+    // Soft delete: existing raids and templates keep their name, emoji and slug
+    if repo::deactivate_raid_type(pool, gid.get() as i64, &name).await? {
+        Ok(format!("Raid type `{}` removed from /raid on this server.", name))
+    } else {
+        Ok(format!("Raid type `{}` not found.", name))
+    }
+}
+
+async fn handle_list(pool: &sqlx::PgPool, gid: GuildId) -> anyhow::Result<String> {
+    let rows = repo::search_raid_types(pool, gid.get() as i64, "", 100).await?;
+    if rows.is_empty() {
+        return Ok("No raid types.".to_string());
+    }
+    // Defaults are shared by every server; editing one makes a copy for this server
+    let mut out = rows
+        .iter()
+        .map(|t| if t.guild_id == 0 { format!("{} · default", describe(t)) } else { describe(t) })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if out.len() > 1900 {
+        out = out.chars().take(1890).collect::<String>() + "\n…";
+    }
+    Ok(out)
+}
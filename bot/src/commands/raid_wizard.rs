@@ -0,0 +1,349 @@
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::all::*;
+use serenity::builder::{
+    CreateInputText, CreateModal, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::db::models::RaidType;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::settings::guild_tz;
+use crate::ui::embeds;
+use crate::utils::parse_raid_datetime;
+
+use super::raid::NewRaid;
+
+/* One in-progress `/raid_wizard` per id; the id travels in every custom_id of the flow */
+struct Wizard {
+    user_id: UserId,
+    guild_id: GuildId,
+    raid_type: Option<RaidType>,
+    date: String,
+    time: String,
+    duration: String,
+    priority_hours: String,
+    description: String,
+    priority_role_ids: Vec<i64>,
+    scheduled_for: Option<chrono::DateTime<chrono::Utc>>,
+    touched: Instant,
+}
+
+static WIZARDS: Lazy<DashMap<Uuid, Wizard>> = Lazy::new(DashMap::new);
+const WIZARD_TTL: Duration = Duration::from_secs(15 * 60);
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_wizard").description("Create a raid step by step")
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    let Some(gid) = cmd.guild_id else {
+        cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("Use this in a server.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let types = repo::search_raid_types(&pool, gid.get() as i64, "", 25).await?; // select menu limit
+    if types.is_empty() {
+        cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("No raid types configured. See `/raid_type add`.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+
+    // Forget wizards nobody finished
+    WIZARDS.retain(|_, w| w.touched.elapsed() < WIZARD_TTL);
+    let wizard_id = Uuid::new_v4();
+    WIZARDS.insert(wizard_id, Wizard {
+        user_id: cmd.user.id,
+        guild_id: gid,
+        raid_type: None,
+        date: String::new(),
+        time: String::new(),
+        duration: String::new(),
+        priority_hours: String::new(),
+        description: String::new(),
+        priority_role_ids: Vec::new(),
+        scheduled_for: None,
+        touched: Instant::now(),
+    });
+
+    let options = types
+        .into_iter()
+        .map(|t| CreateSelectMenuOption::new(format!("{} {}", t.emoji, t.name), t.name))
+        .collect();
+    let select = CreateSelectMenu::new(format!("r:wzt:{wizard_id}"), CreateSelectMenuKind::String { options })
+        .placeholder("Raid type");
+
+    cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content("**New raid — step 1/3:** pick the raid type.")
+            .ephemeral(true)
+            .components(vec![CreateActionRow::SelectMenu(select), buttons_row(wizard_id, false, false)])
+    )).await?;
+    Ok(())
+}
+
+fn buttons_row(wizard_id: Uuid, create: bool, back: bool) -> CreateActionRow {
+    let mut buttons = Vec::new();
+    if create {
+        buttons.push(CreateButton::new(format!("r:wzc:{wizard_id}")).label("Create").style(ButtonStyle::Success));
+    }
+    if back {
+        buttons.push(CreateButton::new(format!("r:wzb:{wizard_id}")).label("Back").style(ButtonStyle::Primary));
+    }
+    buttons.push(CreateButton::new(format!("r:wzx:{wizard_id}")).label("Cancel").style(ButtonStyle::Secondary));
+    CreateActionRow::Buttons(buttons)
+}
+
+/* Takes the wizard out for this step if it belongs to the clicker and hasn't expired */
+fn take_wizard(wizard_id: Uuid, user_id: UserId) -> Option<Wizard> {
+    WIZARDS
+        .remove_if(&wizard_id, |_, w| w.user_id == user_id)
+        .map(|(_, w)| w)
+        .filter(|w| w.touched.elapsed() < WIZARD_TTL)
+}
+
+fn put_wizard(wizard_id: Uuid, mut w: Wizard) {
+    w.touched = Instant::now();
+    WIZARDS.insert(wizard_id, w);
+}
+
+fn expired_response() -> CreateInteractionResponse {
+    CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content("This wizard has expired. Run `/raid_wizard` again.")
+            .embeds(vec![])
+            .components(vec![])
+    )
+}
+
+fn details_modal(wizard_id: Uuid, w: &Wizard) -> CreateModal {
+    let tz = guild_tz(w.guild_id.get());
+    let title = w.raid_type.as_ref().map(|t| format!("New raid: {}", t.name)).unwrap_or_else(|| "New raid".to_string());
+    let input = |style, label: &str, id: &str, value: &str, placeholder: String, required: bool| {
+        let mut field = CreateInputText::new(style, label, id).placeholder(placeholder).required(required);
+        if !value.is_empty() {
+            field = field.value(value);
+        }
+        CreateActionRow::InputText(field)
+    };
+    CreateModal::new(format!("r:wzm:{wizard_id}"), title).components(vec![
+        input(InputTextStyle::Short, "Date", "date", &w.date, "today, tomorrow, fri, +2d, 24.12 or 2025-06-01".to_string(), true),
+        input(InputTextStyle::Short, "Time (HH:MM)", "time", &w.time, format!("20:00 ({})", tz.name()), true),
+        input(InputTextStyle::Short, "Duration (minutes)", "duration", &w.duration, "60".to_string(), true),
+        input(InputTextStyle::Short, "Priority window, hours before start", "priority_hours", &w.priority_hours, "Leave empty for no priority".to_string(), false),
+        input(InputTextStyle::Paragraph, "Description", "description", &w.description, "Short description".to_string(), true),
+    ])
+}
+
+/* Step 1 → 2: raid type picked, ask for the details */
+pub async fn pick_type(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    let Some(mut w) = take_wizard(wizard_id, it.user.id) else {
+        it.create_response(&ctx.http, expired_response()).await?;
+        return Ok(());
+    };
+    let ComponentInteractionDataKind::StringSelect { values } = &it.data.kind else { return Ok(()); };
+    let Some(name) = values.first() else { return Ok(()); };
+
+    let pool = pool_from_ctx(ctx).await?;
+    let Some(raid_type) = repo::get_raid_type(&pool, w.guild_id.get() as i64, name).await?.filter(|t| t.is_active) else {
+        put_wizard(wizard_id, w);
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("That raid type is no longer available.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    };
+    if w.duration.is_empty() || w.raid_type.as_ref().is_some_and(|t| t.name != raid_type.name) {
+        w.duration = raid_type.default_duration_minutes.to_string();
+    }
+    w.raid_type = Some(raid_type);
+
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(details_modal(wizard_id, &w))).await?;
+    put_wizard(wizard_id, w);
+    Ok(())
+}
+
+/* "Back" on the preview (or after an input error): reopen the details, prefilled */
+pub async fn back(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    let Some(w) = take_wizard(wizard_id, it.user.id) else {
+        it.create_response(&ctx.http, expired_response()).await?;
+        return Ok(());
+    };
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(details_modal(wizard_id, &w))).await?;
+    put_wizard(wizard_id, w);
+    Ok(())
+}
+
+/* Step 2 → 3: details submitted, validate and show the preview */
+pub async fn submit_details(ctx: &Context, m: &ModalInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    let Some(mut w) = take_wizard(wizard_id, m.user.id) else {
+        m.create_response(&ctx.http, expired_response()).await?;
+        return Ok(());
+    };
+
+    for row in &m.data.components {
+        for c in &row.components {
+            let ActionRowComponent::InputText(t) = c else { continue; };
+            let v = t.value.as_deref().unwrap_or("").trim().to_string();
+            match t.custom_id.as_str() {
+                "date" => w.date = v,
+                "time" => w.time = v,
+                "duration" => w.duration = v,
+                "priority_hours" => w.priority_hours = v,
+                "description" => w.description = v,
+                _ => {}
+            }
+        }
+    }
+
+    let tz = guild_tz(w.guild_id.get());
+    let input = format!("{} {}", w.date, w.time);
+    let checked = parse_raid_datetime(&input, tz)
+        .map_err(|msg| format!("{}\nTimes are in server time ({}).", msg, tz.name()))
+        .and_then(|dt| {
+            parse_duration(&w.duration)?;
+            parse_priority_hours(&w.priority_hours)?;
+            Ok(dt)
+        });
+
+    let response = match (checked, &w.raid_type) {
+        (Ok(dt), Some(t)) => {
+            w.scheduled_for = Some(dt);
+            let duration = parse_duration(&w.duration).unwrap_or(t.default_duration_minutes);
+            let embed = embeds::render_new_raid_embed(&t.name, &w.description, dt, duration, &(t.default_max_players as i64), tz);
+            let mut rows = Vec::new();
+            if parse_priority_hours(&w.priority_hours).ok().flatten().is_some() {
+                rows.push(CreateActionRow::SelectMenu(
+                    CreateSelectMenu::new(format!("r:wzr:{wizard_id}"), CreateSelectMenuKind::Role { default_roles: None })
+                        .placeholder("Priority roles (default: server priority role)")
+                        .min_values(0)
+                        .max_values(5)
+                ));
+            }
+            rows.push(buttons_row(wizard_id, true, true));
+            CreateInteractionResponseMessage::new()
+                .content(format!("**New raid — step 3/3:** {}", super::raid::preview_content(&input, dt, tz)))
+                .embed(embed)
+                .components(rows)
+        }
+        (Err(msg), _) => CreateInteractionResponseMessage::new()
+            .content(format!("❌ {}", msg))
+            .embeds(vec![])
+            .components(vec![buttons_row(wizard_id, false, true)]),
+        (Ok(_), None) => CreateInteractionResponseMessage::new()
+            .content("❌ Pick a raid type first.")
+            .components(vec![buttons_row(wizard_id, false, false)]),
+    };
+    m.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response)).await?;
+    put_wizard(wizard_id, w);
+    Ok(())
+}
+
+fn parse_duration(s: &str) -> Result<i32, String> {
+    s.parse::<i32>()
+        .ok()
+        .filter(|m| (1..=24 * 60).contains(m))
+        .ok_or_else(|| format!("Duration `{}` must be a number of minutes (1–1440).", s))
+}
+
+/* Empty or 0 means no priority window */
+fn parse_priority_hours(s: &str) -> Result<Option<i64>, String> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    match s.parse::<i64>() {
+        Ok(0) => Ok(None),
+        Ok(h) if h > 0 => Ok(Some(h)),
+        _ => Err(format!("Priority window `{}` must be a number of hours.", s)),
+    }
+}
+
+/* Priority role select on the preview; only remembered until "Create" */
+pub async fn pick_roles(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    let Some(mut w) = take_wizard(wizard_id, it.user.id) else {
+        it.create_response(&ctx.http, expired_response()).await?;
+        return Ok(());
+    };
+    if let ComponentInteractionDataKind::RoleSelect { values } = &it.data.kind {
+        w.priority_role_ids = values.iter().map(|r| r.get() as i64).collect();
+    }
+    put_wizard(wizard_id, w);
+    it.create_response(&ctx.http, CreateInteractionResponse::Acknowledge).await?;
+    Ok(())
+}
+
+/* "Create": same raid as `/raid` would post with the type's default limits */
+pub async fn create(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    let Some(w) = take_wizard(wizard_id, it.user.id) else {
+        it.create_response(&ctx.http, expired_response()).await?;
+        return Ok(());
+    };
+    let (Some(t), Some(scheduled_for)) = (w.raid_type.clone(), w.scheduled_for) else {
+        put_wizard(wizard_id, w);
+        return Ok(());
+    };
+
+    let priority_hours = parse_priority_hours(&w.priority_hours).ok().flatten();
+    let mut priority_role_id: Option<Vec<i64>> = None;
+    if priority_hours.is_some() {
+        // Same rule as `/raid`: default priority role, and the author must hold one of the roles
+        let ids = if w.priority_role_ids.is_empty() {
+            match super::raid::resolve_priority_role_ids(ctx, w.guild_id, w.user_id, &[]).await? {
+                Ok(ids) => ids,
+                Err(msg) => return retry_with(ctx, it, wizard_id, w, msg).await,
+            }
+        } else {
+            let member = w.guild_id.member(&ctx.http, w.user_id).await?;
+            if !member.roles.iter().any(|r| w.priority_role_ids.contains(&(r.get() as i64))) {
+                return retry_with(ctx, it, wizard_id, w, "You don't have any of the selected priority roles.".to_string()).await;
+            }
+            w.priority_role_ids.clone()
+        };
+        priority_role_id = Some(ids);
+    }
+
+    let new = NewRaid {
+        guild_id: w.guild_id,
+        created_by: w.user_id,
+        raid_name: t.name.clone(),
+        scheduled_for,
+        duration_minutes: parse_duration(&w.duration).unwrap_or(t.default_duration_minutes),
+        max_players: t.default_max_players as i64,
+        allow_alts: t.default_allow_alts,
+        max_alts: t.default_max_alts as i64,
+        priority: priority_hours.is_some(),
+        priority_role_id,
+        priority_until: priority_hours.map(|h| scheduled_for - chrono::Duration::hours(h)),
+        description: w.description,
+    };
+    super::raid::post_new_raid(ctx, it, new).await
+}
+
+/* Keep the wizard alive and show why "Create" didn't go through */
+async fn retry_with(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid, w: Wizard, msg: String) -> anyhow::Result<()> {
+    put_wizard(wizard_id, w);
+    it.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content(format!("❌ {}", msg)).ephemeral(true)
+    )).await?;
+    Ok(())
+}
+
+pub async fn cancel(ctx: &Context, it: &ComponentInteraction, wizard_id: Uuid) -> anyhow::Result<()> {
+    WIZARDS.remove(&wizard_id);
+    it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content("Raid not created.")
+            .embeds(vec![])
+            .components(vec![])
+    )).await?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use serenity::all::*;
+use serenity::builder::EditInteractionResponse;
+use uuid::Uuid;
+
+use crate::db::models::RaidTemplate;
+use crate::db::repo;
+use crate::handlers::pool_from_ctx;
+use crate::tasks;
+use crate::settings::{role_mention, GuildRole};
+use crate::utils::{member_has_guild_role, parse_list_unique, weekday_from_index};
+
+const WEEKDAYS: [(&str, i16); 7] = [
+    ("monday", 0),
+    ("tuesday", 1),
+    ("wednesday", 2),
+    ("thursday", 3),
+    ("friday", 4),
+    ("saturday", 5),
+    ("sunday", 6),
+];
+
+pub async fn register(ctx: &Context) -> anyhow::Result<()> {
+    let mut weekday = CreateCommandOption::new(CommandOptionType::String, "weekday", "Day of the week").required(true);
+    for (name, _) in WEEKDAYS {
+        weekday = weekday.add_string_choice(name, name);
+    }
+
+    Command::create_global_command(
+        &ctx.http,
+        CreateCommand::new("raid_template")
+            .description("Weekly raids posted automatically (organisers only)")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "create", "Create a weekly raid template")
+                    // Required first
+                    .add_sub_option(crate::commands::raid::raid_name_option())
+                    .add_sub_option(weekday)
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "time", "Format: HH:MM").required(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "max_players", "Main slots").required(true).min_int_value(1))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "allow_alts", "Allow alts").required(true))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "max_alts", "Alt slots").required(true).min_int_value(0))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "description", "Short description").required(true))
+                    // Optional after
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "days_ahead", "Post the raid this many days before it starts (default 7)").min_int_value(1).max_int_value(13))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "priority", "Enable priority role window"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "prioritylist", "Role names for priority (default: the server's priority role)"))
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "priority_hours", "How long priority lasts (hours)"))
+            )
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List this server's raid templates"))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "Stop a raid template")
+                    .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "template_id", "Template UUID").required(true))
+            )
+    ).await?;
+    Ok(())
+}
+
+pub async fn handle(ctx: &Context, cmd: &CommandInteraction) -> anyhow::Result<()> {
+    // Quick ACK
+    let _ = cmd.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let Some(gid) = cmd.guild_id else {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new().content("Use this in a server.")).await?; return Ok(());
+    };
+
+    // Permission: must have the organiser role
+    if !member_has_guild_role(&ctx.http, gid, cmd.user.id, GuildRole::Organiser).await? {
+        cmd.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("Only {} can use this.", role_mention(gid.get(), GuildRole::Organiser)))
+        ).await?; return Ok(());
+    }
+
+    let Some(sub) = cmd.data.options.first() else { return Ok(()); };
+    let CommandDataOptionValue::SubCommand(opts) = &sub.value else { return Ok(()); };
+
+    let reply = match sub.name.as_str() {
+        "create" => handle_create(ctx, cmd, gid, opts).await?,
+        "list" => handle_list(ctx, gid).await?,
+        "delete" => handle_delete(ctx, gid, opts).await?,
+        _ => return Ok(()),
+    };
+    cmd.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    Ok(())
+}
+
+async fn handle_create(ctx: &Context, cmd: &CommandInteraction, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut raid_name = String::new();
+    let mut weekday: Option<i16> = None;
+    let mut time_str = String::new();
+    let mut max_players: i64 = 12;
+    let mut allow_alts = true;
+    let mut max_alts: i64 = 1;
+    let mut description = String::new();
+    let mut days_ahead: i64 = 7;
+    let mut priority = false;
+    let mut priority_role_name: Vec<String> = Vec::new();
+    let mut priority_hours: Option<i64> = None;
+
+    for opt in opts {
+        match opt.name.as_str() {
+            "raid_name" => if let CommandDataOptionValue::String(s) = &opt.value { raid_name = s.clone(); },
+            "weekday" => if let CommandDataOptionValue::String(s) = &opt.value {
+                weekday = WEEKDAYS.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)).map(|(_, idx)| *idx);
+            },
+            "time" => if let CommandDataOptionValue::String(s) = &opt.value { time_str = s.trim().to_string(); },
+            "max_players" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_players = *n; },
+            "allow_alts" => if let CommandDataOptionValue::Boolean(b) = &opt.value { allow_alts = *b; },
+            "max_alts" => if let CommandDataOptionValue::Integer(n) = &opt.value { max_alts = *n; },
+            "description" => if let CommandDataOptionValue::String(s) = &opt.value { description = s.clone(); },
+            "days_ahead" => if let CommandDataOptionValue::Integer(n) = &opt.value { days_ahead = *n; },
+            "priority" => if let CommandDataOptionValue::Boolean(b) = &opt.value { priority = *b; },
+            "prioritylist" => if let CommandDataOptionValue::String(s) = &opt.value { priority_role_name = parse_list_unique(s); },
+            "priority_hours" => if let CommandDataOptionValue::Integer(n) = &opt.value { priority_hours = Some(*n); },
+            _ => {}
+        }
+    }
+
+    let pool = pool_from_ctx(ctx).await?;
+    let raid_name = match repo::get_raid_type(&pool, gid.get() as i64, &raid_name).await? {
+        Some(t) if t.is_active => t.name,
+        _ => return Ok(format!("Unknown raid type `{}`. Pick one from the list.", raid_name)),
+    };
+    let Some(weekday) = weekday else { return Ok("Invalid weekday.".to_string()); };
+    let Ok(start_time) = chrono::NaiveTime::parse_from_str(&time_str, "%H:%M") else {
+        return Ok("Invalid time format. Use `HH:MM`.".to_string());
+    };
+
+    // Same priority rules as `/raid`: default role list, resolved to IDs once at creation
+    let mut priority_role_ids: Vec<i64> = Vec::new();
+    if priority {
+        match crate::commands::raid::resolve_priority_role_ids(ctx, gid, cmd.user.id, &priority_role_name).await? {
+            Ok(ids) => priority_role_ids = ids,
+            Err(msg) => return Ok(msg),
+        }
+    }
+
+    let template = repo::create_raid_template(&pool, &RaidTemplate {
+        id: Uuid::new_v4(),
+        guild_id: gid.get() as i64,
+        created_by: cmd.user.id.get() as i64,
+        raid_name,
+        weekday,
+        start_time,
+        max_players: max_players as i32,
+        allow_alts,
+        max_alts: max_alts as i32,
+        is_priority: priority,
+        priority_role_ids,
+        priority_hours: priority_hours.map(|h| h as i32),
+        description,
+        days_ahead: days_ahead as i32,
+        last_run_for: None,
+        is_active: true,
+    }).await?;
+
+    let next = tasks::schedule_template_run(ctx.clone(), pool, &template);
+    Ok(match next {
+        Some(at) => format!(
+            "Template `{}` created. Next raid is posted <t:{}:R> for <t:{}:F>.",
+            template.id,
+            (at - chrono::Duration::days(template.days_ahead as i64)).timestamp(),
+            at.timestamp()
+        ),
+        None => format!("Template `{}` created.", template.id),
+    })
+}
+
+async fn handle_list(ctx: &Context, gid: GuildId) -> anyhow::Result<String> {
+    let pool = pool_from_ctx(ctx).await?;
+    let rows = repo::list_raid_templates_by_guild(&pool, gid.get() as i64).await?;
+    if rows.is_empty() {
+        return Ok("No raid templates in this server.".to_string());
+    }
+
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|t| {
+            let day = weekday_from_index(t.weekday).map(|d| d.to_string()).unwrap_or_else(|| "?".to_string());
+            format!(
+                "• `{}` **{}** — every {} {} · {} slots · posted {}d ahead{}",
+                t.id,
+                t.raid_name,
+                day,
+                t.start_time.format("%H:%M"),
+                t.max_players,
+                t.days_ahead,
+                if t.is_priority { " · priority" } else { "" }
+            )
+        })
+        .collect();
+
+    let mut out = lines.join("\n");
+    if out.len() > 1900 {
+        out = out.chars().take(1890).collect::<String>() + "\n…";
+    }
+    Ok(out)
+}
+
+async fn handle_delete(ctx: &Context, gid: GuildId, opts: &[CommandDataOption]) -> anyhow::Result<String> {
+    let mut template_s = String::new();
+    for o in opts {
+        if o.name == "template_id" {
+            if let CommandDataOptionValue::String(s) = &o.value { template_s = s.trim().to_string(); }
+        }
+    }
+    let Ok(template_id) = Uuid::parse_str(&template_s) else {
+        return Ok("Invalid template_id".to_string());
+    };
+
+    let pool = pool_from_ctx(ctx).await?;
+    // Raids already posted from this template stay; only future runs stop.
+    if repo::deactivate_raid_template(&pool, gid.get() as i64, template_id).await? {
+        tasks::cancel_template_timer(template_id);
+        Ok("Template deleted. Raids already posted are kept.".to_string())
+    } else {
+        Ok("Template not found.".to_string())
+    }
+}
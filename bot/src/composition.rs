@@ -0,0 +1,180 @@
+use uuid::Uuid;
+
+use crate::db::models::{CompositionRule, RaidParticipant};
+
+/* Per-raid party composition (`/raid_composition`). Only mains count; alts count as the class they play. */
+
+fn matches(rule: &CompositionRule, class: &str, sp: &str) -> bool {
+    rule.class.eq_ignore_ascii_case(class) && rule.sp.as_deref().is_none_or(|s| s.eq_ignore_ascii_case(sp))
+}
+
+fn count(rule: &CompositionRule, mains: &[(&str, &str)]) -> i32 {
+    mains.iter().filter(|(c, s)| matches(rule, c, s)).count() as i32
+}
+
+fn main_slots(parts: &[RaidParticipant]) -> Vec<(&str, &str)> {
+    parts.iter().filter(|p| p.is_main).map(|p| (p.class.as_str(), p.active_sp.as_str())).collect()
+}
+
+/// "MSW" or "MSW SP3"
+pub fn rule_label(rule: &CompositionRule) -> String {
+    match &rule.sp {
+        Some(sp) => format!("{} {}", rule.class, sp),
+        None => rule.class.clone(),
+    }
+}
+
+/// The first rule whose maximum is already reached by the mains, for a new main of `class`/`sp`.
+pub fn full_cap<'a>(rules: &'a [CompositionRule], parts: &[RaidParticipant], class: &str, sp: &str) -> Option<&'a CompositionRule> {
+    let mains = main_slots(parts);
+    rules
+        .iter()
+        .filter(|r| matches(r, class, sp))
+        .find(|r| r.max.is_some_and(|max| count(r, &mains) >= max))
+}
+
+/// Chooses whom to promote from `candidates` (reserve row ids in their usual promotion order):
+/// those who fill an unmet minimum go first, then the rest in order; anyone who would go over a
+/// maximum is skipped. Tentative players only come after every other candidate. At most `limit`
+/// rows are returned, of which at most `alt_limit` are alts.
+pub fn pick(rules: &[CompositionRule], parts: &[RaidParticipant], candidates: &[Uuid], limit: i32, alt_limit: i32) -> Vec<Uuid> {
+    let limit = limit.max(0) as usize;
+    let mut alts_left = alt_limit.max(0);
+    let mut mains = main_slots(parts);
+    let rows: Vec<&RaidParticipant> = candidates
+        .iter()
+        .filter_map(|id| parts.iter().find(|p| p.id == *id))
+        .collect();
+
+    let mut picked: Vec<Uuid> = Vec::new();
+    for (tentative, fill_minimums) in [(false, true), (false, false), (true, true), (true, false)] {
+        for p in rows.iter().filter(|p| p.is_tentative == tentative) {
+            if picked.len() >= limit {
+                return picked;
+            }
+            if picked.contains(&p.id) || (p.is_alt && alts_left <= 0) {
+                continue;
+            }
+            let applicable: Vec<&CompositionRule> = rules.iter().filter(|r| matches(r, &p.class, &p.active_sp)).collect();
+            if applicable.iter().any(|r| r.max.is_some_and(|max| count(r, &mains) >= max)) {
+                continue;
+            }
+            if fill_minimums && !applicable.iter().any(|r| count(r, &mains) < r.min) {
+                continue;
+            }
+            if p.is_alt {
+                alts_left -= 1;
+            }
+            mains.push((p.class.as_str(), p.active_sp.as_str()));
+            picked.push(p.id);
+        }
+    }
+    picked
+}
+
+/// One line per rule for the embed; unmet minimums and exceeded maximums are highlighted.
+pub fn summary(rules: &[CompositionRule], parts: &[RaidParticipant]) -> Vec<String> {
+    let mains = main_slots(parts);
+    rules
+        .iter()
+        .map(|r| {
+            let have = count(r, &mains);
+            let limits = match (r.min, r.max) {
+                (0, Some(max)) => format!("max {}", max),
+                (min, Some(max)) => format!("{}–{}", min, max),
+                (min, None) => format!("min {}", min),
+            };
+            if have < r.min {
+                format!("⚠️ **{}: {} ({}), {} missing**", rule_label(r), have, limits, r.min - have)
+            } else if r.max.is_some_and(|max| have > max) {
+                format!("⚠️ **{}: {} ({})**", rule_label(r), have, limits)
+            } else {
+                format!("✅ {}: {} ({})", rule_label(r), have, limits)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(class: &str, sp: Option<&str>, min: i32, max: Option<i32>) -> CompositionRule {
+        CompositionRule { class: class.to_string(), sp: sp.map(str::to_string), min, max }
+    }
+
+    fn part(n: u128, class: &str, sp: &str, is_main: bool) -> RaidParticipant {
+        RaidParticipant {
+            id: Uuid::from_u128(n),
+            raid_id: Uuid::nil(),
+            user_id: n as i64,
+            is_main,
+            class: class.to_string(),
+            active_sp: sp.to_string(),
+            is_reserve: !is_main,
+            joined_at: chrono::DateTime::<chrono::Utc>::from_timestamp(n as i64, 0).unwrap(),
+            is_alt: false,
+            tag_suffix: String::new(),
+            extra_sps: Vec::new(),
+            note: String::new(),
+            is_tentative: false,
+        }
+    }
+
+    fn ids(ns: &[u128]) -> Vec<Uuid> {
+        ns.iter().map(|n| Uuid::from_u128(*n)).collect()
+    }
+
+    #[test]
+    fn unmet_minimums_go_first() {
+        let rules = [rule("ARCH", None, 1, None)];
+        let parts = [part(1, "SWORD", "SP1", true), part(2, "SWORD", "SP1", false), part(3, "ARCH", "SP2", false)];
+        assert_eq!(pick(&rules, &parts, &ids(&[2, 3]), 1, 0), ids(&[3]));
+        assert_eq!(pick(&rules, &parts, &ids(&[2, 3]), 2, 0), ids(&[3, 2]));
+    }
+
+    #[test]
+    fn full_maximums_are_skipped() {
+        let rules = [rule("MAGE", Some("SP4"), 0, Some(1))];
+        let parts = [
+            part(1, "MAGE", "SP4", true),
+            part(2, "MAGE", "SP4", false),
+            part(3, "MAGE", "SP1", false),
+        ];
+        assert_eq!(pick(&rules, &parts, &ids(&[2, 3]), 2, 0), ids(&[3]));
+        assert_eq!(full_cap(&rules, &parts, "mage", "sp4").map(rule_label), Some("MAGE SP4".to_string()));
+        assert!(full_cap(&rules, &parts, "MAGE", "SP1").is_none());
+    }
+
+    #[test]
+    fn picks_count_towards_the_maximum() {
+        let rules = [rule("MAGE", None, 0, Some(1))];
+        let parts = [part(1, "MAGE", "SP1", false), part(2, "MAGE", "SP2", false), part(3, "ARCH", "SP1", false)];
+        assert_eq!(pick(&rules, &parts, &ids(&[1, 2, 3]), 3, 0), ids(&[1, 3]));
+    }
+
+    #[test]
+    fn alts_stop_at_the_alt_limit() {
+        let mut parts = [part(1, "SWORD", "SP1", false), part(2, "SWORD", "SP2", false), part(3, "ARCH", "SP1", false)];
+        parts[0].is_alt = true;
+        parts[1].is_alt = true;
+        assert_eq!(pick(&[], &parts, &ids(&[1, 2, 3]), 3, 1), ids(&[1, 3]));
+        assert_eq!(pick(&[], &parts, &ids(&[1, 2, 3]), 3, 0), ids(&[3]));
+    }
+
+    #[test]
+    fn tentatives_go_last_even_for_a_minimum() {
+        let rules = [rule("ARCH", None, 1, None)];
+        let mut parts = [part(1, "ARCH", "SP1", false), part(2, "SWORD", "SP1", false), part(3, "SWORD", "SP2", false)];
+        parts[0].is_tentative = true;
+        assert_eq!(pick(&rules, &parts, &ids(&[1, 2, 3]), 2, 0), ids(&[2, 3]));
+        assert_eq!(pick(&rules, &parts, &ids(&[1, 2, 3]), 3, 0), ids(&[2, 3, 1]));
+    }
+
+    #[test]
+    fn limit_and_candidate_order_are_kept() {
+        let parts = [part(1, "SWORD", "SP1", false), part(2, "ARCH", "SP1", false), part(3, "MAGE", "SP1", false)];
+        assert_eq!(pick(&[], &parts, &ids(&[3, 1, 2]), 2, 0), ids(&[3, 1]));
+        assert!(pick(&[], &parts, &ids(&[3, 1, 2]), 0, 0).is_empty());
+    }
+}
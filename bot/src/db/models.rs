@@ -1,6 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
@@ -23,6 +24,52 @@ pub struct Raid {
     pub max_alts: i32,
     pub priority_role_id: Option<Vec<i64>>,
     pub priority_until: Option<DateTime<Utc>>,
+    pub duration_minutes: i32,
+    pub thread_id: Option<i64>, // thread mode: discussion thread under the embed in the raids channel
+    pub event_id: Option<i64>,  // Guild Scheduled Event mirroring the raid
+    pub composition: Json<Vec<CompositionRule>>,
+    pub promotion_policy: Option<String>, // None = the guild's policy
+    pub min_points: i32,                  // points needed for a main slot, 0 = none
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+/* Columns of a new `raids` row; everything else starts at its default */
+#[derive(Debug, Clone)]
+pub struct NewRaidRow {
+    pub id: Uuid,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub message_id: i64,
+    pub thread_id: Option<i64>,
+    pub scheduled_for: DateTime<Utc>,
+    pub duration_minutes: i32,
+    pub created_by: i64,
+    pub owner_id: i64,
+    pub raid_name: String,
+    pub description: String,
+    pub max_players: i32,
+    pub allow_alts: bool,
+    pub max_alts: i32,
+    pub is_priority: bool,
+    pub priority_list: Vec<i64>,
+    pub priority_role_id: Option<Vec<i64>>,
+    pub priority_until: Option<DateTime<Utc>>,
+}
+
+/* One composition requirement of a raid; `sp: None` counts the whole class */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompositionRule {
+    pub class: String,
+    pub sp: Option<String>,
+    pub min: i32,
+    pub max: Option<i32>,
+}
+
+impl Raid {
+    /* Where players should go: the raid's thread in thread mode, otherwise its own channel */
+    pub fn chat_channel_id(&self) -> u64 {
+        self.thread_id.unwrap_or(self.channel_id) as u64
+    }
 }
 
 #[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
@@ -31,10 +78,160 @@ pub struct RaidParticipant {
     pub raid_id: Uuid,
     pub user_id: i64,
     pub is_main: bool,
-    pub joined_as: String,
+    pub class: String,     // catalog class code, e.g. "MSW"
+    pub active_sp: String, // SP code, e.g. "SP1"
     pub is_reserve: bool,
     pub joined_at: DateTime<Utc>,
     pub is_alt: bool,
     pub tag_suffix: String,
     pub extra_sps: Vec<String>,
+    pub note: String,       // signup note, "" = none
+    pub is_tentative: bool, // not main; promoted only when no other reserve is left
+}
+
+impl RaidParticipant {
+    /// "CLASS / SP" as shown in lists and messages.
+    pub fn joined_as(&self) -> String {
+        format!("{} / {}", self.class, self.active_sp)
+    }
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct RaidTemplate {
+    pub id: Uuid,
+    pub guild_id: i64,
+    pub created_by: i64,
+    pub raid_name: String,
+    pub weekday: i16, // 0 = Monday
+    pub start_time: NaiveTime,
+    pub max_players: i32,
+    pub allow_alts: bool,
+    pub max_alts: i32,
+    pub is_priority: bool,
+    pub priority_role_ids: Vec<i64>,
+    pub priority_hours: Option<i32>,
+    pub description: String,
+    pub days_ahead: i32,
+    pub last_run_for: Option<DateTime<Utc>>,
+    pub is_active: bool,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct RaidType {
+    /// 0 = default shared by every guild
+    pub guild_id: i64,
+    pub name: String,
+    pub slug: String,
+    pub emoji: String,
+    pub default_max_players: i32,
+    pub default_duration_minutes: i32,
+    pub default_allow_alts: bool,
+    pub default_max_alts: i32,
+    pub is_active: bool,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct GameClass {
+    pub code: String, // "MSW"
+    pub name: String,
+    pub aliases: Vec<String>, // other spellings of the class, e.g. "warrior"
+    pub position: i32,
+    pub is_active: bool,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct GameSp {
+    pub class_code: String,
+    pub code: String, // "SP1"
+    pub name: String,
+    pub emoji_name: Option<String>, // None = "<CLASS>_<SP>"
+    pub position: i32,
+    pub is_active: bool,
+}
+
+/// A character saved with `/profile`.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerProfile {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub class: String,     // catalog class code
+    pub active_sp: String, // SP joined with
+    pub extra_sps: Vec<String>,
+    pub is_default: bool, // Quick join signs this one as main
+    pub created_at: DateTime<Utc>,
+}
+
+/// One player's attendance for a finished raid.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct RaidAttendance {
+    pub raid_id: Uuid,
+    pub user_id: i64,
+    pub guild_id: i64,
+    pub status: String, // "present" | "late" | "no_show"
+    pub marked_by: i64,
+    pub marked_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct RaidLoot {
+    pub id: i64,
+    pub raid_id: Uuid,
+    pub guild_id: i64,
+    pub user_id: i64, // recipient
+    pub item: String,
+    pub logged_by: i64,
+    pub logged_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct PointsEntry {
+    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub amount: i32,    // negative = taken
+    pub reason: String,
+    pub source: String, // "manual" | "attendance"
+    pub raid_id: Option<Uuid>,
+    pub given_by: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct AttendancePoints {
+    pub guild_id: i64,
+    pub raid_type: String, // "" = every raid type without its own row
+    pub present: i32,
+    pub late: i32,
+    pub no_show: i32,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct JoinRule {
+    pub id: i64,
+    pub guild_id: i64,
+    pub raid_type: String, // "" = whole guild
+    pub role_id: i64,
+    pub access: String, // "main" | "reserve" | "alts"
+    pub tag_suffix: Option<String>,
+    pub position: i32,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct GuildSettings {
+    pub guild_id: i64,
+    pub timezone: String, // IANA name, e.g. "Europe/Warsaw"
+    pub weekday_categories: Json<HashMap<String, i64>>, // "monday" -> category id
+    pub channel_name_template: Option<String>,
+    pub fallback_category_id: Option<i64>,
+    pub max_channels_per_category: Option<i32>,
+    pub raid_thread_channel_id: Option<i64>, // set = thread mode
+    pub organiser_role_id: Option<i64>,
+    pub admin_role_id: Option<i64>,
+    pub reserve_role_id: Option<i64>,
+    pub alt_allow_role_id: Option<i64>,
+    pub c90_role_id: Option<i64>,
+    pub c1_89_role_id: Option<i64>,
+    pub default_priority_role_id: Option<i64>,
+    pub promotion_policy: String, // settings::PromotionPolicy key
 }
@@ -1,47 +1,36 @@
-use super::models::{Raid, RaidParticipant};
+use super::models::{AttendancePoints, CompositionRule, GameClass, GameSp, GuildSettings, JoinRule, NewRaidRow, PlayerProfile, PointsEntry, Raid, RaidAttendance, RaidLoot, RaidParticipant, RaidTemplate, RaidType};
 use chrono::{DateTime, Utc};
 use sqlx::{types::Json, PgPool,FromRow};
+use std::collections::HashMap;
 use uuid::Uuid;
 use crate::db::repo;
+use crate::settings::PromotionPolicy;
 /* RAIDS */
 
-pub async fn create_raid_with_id(
-    pool: &PgPool,
-    id: Uuid,
-    guild_id: i64,
-    channel_id: i64,
-    message_id: i64,
-    scheduled_for: DateTime<Utc>,
-    created_by: i64,
-    owner_id: i64,
-    description: String,
-    priority_list: Vec<i64>,
-    is_priority: bool,
-    raid_name: String,
-    max_players: i32,
-    allow_alts: bool,
-    max_alts: i32,
-    priority_role_id: Option<Vec<i64>>,
-    priority_until: Option<DateTime<Utc>>,
-) -> anyhow::Result<Raid> {
+pub async fn create_raid_with_id(pool: &PgPool, r: NewRaidRow) -> anyhow::Result<Raid> {
     let raid = sqlx::query_as!(
         Raid,
         r#"
         INSERT INTO raids (
             id, guild_id, channel_id, message_id, scheduled_for,
             created_by, owner_id, description, is_priority, is_active, priority_list,
-            raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until
+            raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until,
+            duration_minutes, thread_id
         )
-        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,TRUE,$10,$11,$12,$13,$14,$15,$16)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,TRUE,$10,$11,$12,$13,$14,$15,$16,$17,$18)
         RETURNING
             id, guild_id, channel_id, message_id, scheduled_for, created_by, owner_id,
             description, is_priority, is_active, priority_list as "priority_list: Json<Vec<i64>>",
-            raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until
+            raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until,
+            duration_minutes, thread_id, event_id,
+            composition as "composition: Json<Vec<CompositionRule>>", promotion_policy, min_points,
+            cancelled_at
         "#,
-        id, guild_id, channel_id, message_id, scheduled_for,
-        created_by, owner_id, description, is_priority,
-        Json(priority_list) as Json<Vec<i64>>,
-        raid_name, max_players, allow_alts, max_alts, priority_role_id.as_deref(), priority_until
+        r.id, r.guild_id, r.channel_id, r.message_id, r.scheduled_for,
+        r.created_by, r.owner_id, r.description, r.is_priority,
+        Json(r.priority_list) as Json<Vec<i64>>,
+        r.raid_name, r.max_players, r.allow_alts, r.max_alts, r.priority_role_id.as_deref(), r.priority_until,
+        r.duration_minutes, r.thread_id
     )
         .fetch_one(pool)
         .await?;
@@ -54,7 +43,10 @@ pub async fn get_raid(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<Raid> {
         r#"
         SELECT id, guild_id, channel_id, message_id, scheduled_for, created_by, owner_id,
                description, is_priority, is_active, priority_list as "priority_list: Json<Vec<i64>>",
-               raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until
+               raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until,
+            duration_minutes, thread_id, event_id,
+            composition as "composition: Json<Vec<CompositionRule>>", promotion_policy, min_points,
+            cancelled_at
         FROM raids
         WHERE id = $1
         "#,
@@ -71,7 +63,7 @@ pub async fn list_participants(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<V
     let rows = sqlx::query_as!(
         RaidParticipant,
         r#"
-        SELECT id, raid_id, user_id, is_main, joined_as, is_reserve, joined_at, is_alt, tag_suffix, extra_sps
+        SELECT id, raid_id, user_id, is_main, class, active_sp, is_reserve, joined_at, is_alt, tag_suffix, extra_sps, note, is_tentative
         FROM raid_participants
         WHERE raid_id = $1
         ORDER BY joined_at ASC
@@ -121,7 +113,8 @@ pub async fn insert_or_replace_main(
     pool: &PgPool,
     raid_id: Uuid,
     user_id: i64,
-    joined_as: String,
+    class: &str,
+    active_sp: &str,
     main_now: bool, // if false, goes to reserve (non-alt)
     tag_suffix: String,
 ) -> anyhow::Result<RaidParticipant> {
@@ -130,11 +123,11 @@ pub async fn insert_or_replace_main(
         RaidParticipant,
         r#"
         UPDATE raid_participants
-        SET joined_as = $1, is_main = $2, is_reserve = NOT $2, is_alt = FALSE, tag_suffix = $5
+        SET class = $1, active_sp = $6, is_main = $2, is_reserve = NOT $2, is_alt = FALSE, tag_suffix = $5, is_tentative = FALSE
         WHERE raid_id = $3 AND user_id = $4 AND is_alt = FALSE
-        RETURNING id, raid_id, user_id, is_main, joined_as, is_reserve, joined_at, is_alt, tag_suffix, extra_sps
+        RETURNING id, raid_id, user_id, is_main, class, active_sp, is_reserve, joined_at, is_alt, tag_suffix, extra_sps, note, is_tentative
         "#,
-        joined_as, main_now, raid_id, user_id,tag_suffix
+        class, main_now, raid_id, user_id, tag_suffix, active_sp
     ).fetch_optional(pool).await?;
 
     if let Some(row) = maybe {
@@ -145,11 +138,11 @@ pub async fn insert_or_replace_main(
     let row = sqlx::query_as!(
         RaidParticipant,
         r#"
-        INSERT INTO raid_participants (id, raid_id, user_id, is_main, joined_as, is_reserve, is_alt,tag_suffix)
-        VALUES ($1,$2,$3,$4,$5,$6,FALSE,$7)
-        RETURNING id, raid_id, user_id, is_main, joined_as, is_reserve, joined_at, is_alt,tag_suffix, extra_sps
+        INSERT INTO raid_participants (id, raid_id, user_id, is_main, class, active_sp, is_reserve, is_alt, tag_suffix)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,FALSE,$8)
+        RETURNING id, raid_id, user_id, is_main, class, active_sp, is_reserve, joined_at, is_alt, tag_suffix, extra_sps, note, is_tentative
         "#,
-        id, raid_id, user_id, main_now, joined_as, !main_now,tag_suffix
+        id, raid_id, user_id, main_now, class, active_sp, !main_now, tag_suffix
     ).fetch_one(pool).await?;
     Ok(row)
 }
@@ -158,7 +151,8 @@ pub async fn insert_alt(
     pool: &PgPool,
     raid_id: Uuid,
     user_id: i64,
-    joined_as: String,
+    class: &str,
+    active_sp: &str,
     main_now: bool, // if false → reserve alt
     tag_suffix: String,
 ) -> anyhow::Result<RaidParticipant> {
@@ -166,11 +160,11 @@ pub async fn insert_alt(
     let row = sqlx::query_as!(
         RaidParticipant,
         r#"
-        INSERT INTO raid_participants (id, raid_id, user_id, is_main, joined_as, is_reserve, is_alt,tag_suffix)
-        VALUES ($1,$2,$3,$4,$5,$6,TRUE,$7)
-        RETURNING id, raid_id, user_id, is_main, joined_as, is_reserve, joined_at, is_alt,tag_suffix, extra_sps
+        INSERT INTO raid_participants (id, raid_id, user_id, is_main, class, active_sp, is_reserve, is_alt, tag_suffix)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,TRUE,$8)
+        RETURNING id, raid_id, user_id, is_main, class, active_sp, is_reserve, joined_at, is_alt, tag_suffix, extra_sps, note, is_tentative
         "#,
-        id, raid_id, user_id, main_now, joined_as, !main_now,tag_suffix
+        id, raid_id, user_id, main_now, class, active_sp, !main_now, tag_suffix
     ).fetch_one(pool).await?;
     Ok(row)
 }
@@ -180,7 +174,7 @@ pub async fn get_user_main_row(pool: &PgPool, raid_id: Uuid, user_id: i64) -> an
     let row = sqlx::query_as!(
         RaidParticipant,
         r#"
-        SELECT id, raid_id, user_id, is_main, joined_as, is_reserve, joined_at, is_alt, tag_suffix, extra_sps
+        SELECT id, raid_id, user_id, is_main, class, active_sp, is_reserve, joined_at, is_alt, tag_suffix, extra_sps, note, is_tentative
         FROM raid_participants
         WHERE raid_id = $1 AND user_id = $2 AND is_alt = FALSE
         ORDER BY is_main DESC, joined_at ASC
@@ -203,19 +197,42 @@ pub async fn append_extra_sp(pool: &PgPool, raid_id: Uuid, user_id: i64, sp: &st
     Ok(())
 }
 
-pub async fn set_active_sp(pool: &PgPool, raid_id: Uuid, user_id: i64, class_part: &str, sp: &str) -> anyhow::Result<()> {
-    let new_joined = format!("{} / {}", class_part, sp);
+pub async fn set_active_sp(pool: &PgPool, raid_id: Uuid, user_id: i64, sp: &str) -> anyhow::Result<()> {
     sqlx::query!(
         r#"
         UPDATE raid_participants
-        SET joined_as = $3
+        SET active_sp = $3
         WHERE raid_id = $1 AND user_id = $2 AND is_alt = FALSE
         "#,
-        raid_id, user_id, new_joined
+        raid_id, user_id, sp
     ).execute(pool).await?;
     Ok(())
 }
 
+/* Signup note and tentative status */
+pub async fn set_participant_note(pool: &PgPool, raid_id: Uuid, user_id: i64, note: &str) -> anyhow::Result<u64> {
+    let res = sqlx::query!(
+        "UPDATE raid_participants SET note = $3 WHERE raid_id = $1 AND user_id = $2 AND is_alt = FALSE",
+        raid_id, user_id, note
+    ).execute(pool).await?;
+    Ok(res.rows_affected())
+}
+
+/// Marks all of the user's rows tentative (mains step down to the bench) or back to plain reserve.
+pub async fn set_tentative(pool: &PgPool, raid_id: Uuid, user_id: i64, tentative: bool) -> anyhow::Result<u64> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE raid_participants
+        SET is_tentative = $3,
+            is_main = is_main AND NOT $3,
+            is_reserve = is_reserve OR $3
+        WHERE raid_id = $1 AND user_id = $2
+        "#,
+        raid_id, user_id, tentative
+    ).execute(pool).await?;
+    Ok(res.rows_affected())
+}
+
 pub async fn remove_participant(pool: &PgPool, raid_id: Uuid, user_id: i64) -> anyhow::Result<u64> {
     let res = sqlx::query!(
         "DELETE FROM raid_participants WHERE raid_id = $1 AND user_id = $2",
@@ -253,64 +270,7 @@ pub async fn promote_reserves_with_alt_limits(
     max_players: i32,
     max_alts: i32,
 ) -> anyhow::Result<()> {
-    let mains = count_mains(pool, raid_id).await? as i32;
-    let mut free = (max_players - mains).max(0);
-
-    if free <= 0 {
-        return Ok(());
-    }
-
-    // 1) Promote non-alt reserves
-    let free_i64 = free as i64;
-    let promoted_non_alt = sqlx::query_scalar!(
-        r#"
-        WITH c AS (
-          SELECT id FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = FALSE
-          ORDER BY joined_at ASC
-          LIMIT $2
-        )
-        UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
-        FROM c
-        WHERE p.id = c.id
-        RETURNING 1
-        "#,
-        raid_id, free_i64
-    ).fetch_all(pool).await?.len() as i32;
-
-    free -= promoted_non_alt;
-    if free <= 0 {
-        return Ok(());
-    }
-
-    // 2) Promote alt reserves within alt cap
-    let current_alt_mains = count_alt_mains(pool, raid_id).await? as i32;
-    let alt_left = (max_alts - current_alt_mains).max(0);
-    if alt_left <= 0 {
-        return Ok(());
-    }
-
-    let promote_alt = free.min(alt_left);
-    let promote_alt_i64 = promote_alt as i64;
-    let _promoted_alts = sqlx::query_scalar!(
-        r#"
-        WITH c AS (
-          SELECT id FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = TRUE
-          ORDER BY joined_at ASC
-          LIMIT $2
-        )
-        UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
-        FROM c
-        WHERE p.id = c.id
-        RETURNING 1
-        "#,
-        raid_id, promote_alt_i64
-    ).fetch_all(pool).await?.len() as i32;
-
-    Ok(())
+    promote_reserves_with_alt_limits_excluding(pool, raid_id, max_players, max_alts, &[]).await
 }
 
 // repo.rs
@@ -322,73 +282,191 @@ pub async fn promote_reserves_with_alt_limits_excluding(
     exclude_user_ids: &[i64], // users with "reserve" role — never auto-promote
 ) -> anyhow::Result<()> {
     let mains = super::repo::count_mains(pool, raid_id).await? as i32;
-    let mut free = (max_players - mains).max(0);
+    let free = (max_players - mains).max(0);
     if free <= 0 { return Ok(()); }
 
-    // 1) Promote non-alt reserves (excluding listed users)
-    let promoted_non_alt = sqlx::query_scalar!(
+    // Non-alt reserves first, then alt reserves (excluding listed users)
+    let candidates = sqlx::query_scalar!(
         r#"
-        WITH c AS (
-          SELECT id FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = FALSE
-            AND NOT (user_id = ANY($3::BIGINT[]))
-          ORDER BY joined_at ASC
-          LIMIT $2
-        )
-        UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
-        FROM c
-        WHERE p.id = c.id
-        RETURNING 1
+        SELECT id FROM raid_participants
+        WHERE raid_id = $1 AND is_main = FALSE
+          AND NOT (user_id = ANY($2::BIGINT[]))
+        ORDER BY is_alt ASC, joined_at ASC
         "#,
         raid_id,
-        free as i64,
         exclude_user_ids
     )
         .fetch_all(pool)
-        .await?
-        .len() as i32;
-
-    free -= promoted_non_alt;
-    if free <= 0 { return Ok(()); }
+        .await?;
 
-    // 2) Promote alt reserves within alt cap (excluding listed users)
     let current_alt_mains = super::repo::count_alt_mains(pool, raid_id).await? as i32;
     let alt_left = (max_alts - current_alt_mains).max(0);
-    if alt_left <= 0 { return Ok(()); }
+    promote_picked(pool, raid_id, &candidates, free, alt_left, true).await
+}
 
-    let promote_alt = free.min(alt_left);
-    let _promoted_alts = sqlx::query_scalar!(
+/// Promotes up to `free` of `candidates` (in order, at most `alt_left` alts), letting the raid's
+/// composition rules go first: reserves that fill an unmet minimum are taken before the rest and
+/// nobody is promoted past a class/SP maximum. Under the reliability policy the candidates are
+/// re-ranked by `reliability_scores` first (stable, so join order still breaks ties); with
+/// `alts_last` non-alt rows stay ahead of alts. Players below the raid's `min_points` stay on reserve.
+async fn promote_picked(
+    pool: &PgPool,
+    raid_id: Uuid,
+    candidates: &[Uuid],
+    free: i32,
+    alt_left: i32,
+    alts_last: bool,
+) -> anyhow::Result<()> {
+    if candidates.is_empty() { return Ok(()); }
+    let raid = sqlx::query!(
+        r#"
+        SELECT r.guild_id, r.min_points,
+               r.composition as "composition: Json<Vec<CompositionRule>>",
+               COALESCE(r.promotion_policy, g.promotion_policy) as policy
+        FROM raids r
+        LEFT JOIN guild_settings g ON g.guild_id = r.guild_id
+        WHERE r.id = $1
+        "#,
+        raid_id
+    )
+        .fetch_one(pool)
+        .await?;
+    let parts = list_participants(pool, raid_id).await?;
+
+    let mut order = candidates.to_vec();
+    if raid.min_points > 0 {
+        let user_ids: Vec<i64> = parts.iter().map(|p| p.user_id).collect();
+        let balances = points_balances(pool, raid.guild_id, &user_ids).await?;
+        let eligible = |id: &Uuid| {
+            parts.iter().find(|p| p.id == *id)
+                .is_some_and(|p| balances.get(&p.user_id).copied().unwrap_or(0) >= raid.min_points as i64)
+        };
+        order.retain(eligible);
+    }
+    let policy = PromotionPolicy::from_key(raid.policy.as_deref().unwrap_or_default());
+    if policy == PromotionPolicy::Reliability {
+        let rows: Vec<&RaidParticipant> = order.iter().filter_map(|id| parts.iter().find(|p| p.id == *id)).collect();
+        let user_ids: Vec<i64> = rows.iter().map(|p| p.user_id).collect();
+        let scores = reliability_scores(pool, raid.guild_id, raid_id, &user_ids).await?;
+        let mut ranked: Vec<(bool, i64, Uuid)> = rows
+            .iter()
+            .map(|p| (alts_last && p.is_alt, -scores.get(&p.user_id).copied().unwrap_or(0), p.id))
+            .collect();
+        ranked.sort_by_key(|(alt, score, _)| (*alt, *score));
+        order = ranked.into_iter().map(|(_, _, id)| id).collect();
+    }
+
+    let ids = crate::composition::pick(&raid.composition.0, &parts, &order, free, alt_left);
+    if ids.is_empty() { return Ok(()); }
+
+    sqlx::query!(
+        "UPDATE raid_participants SET is_main = TRUE, is_reserve = FALSE WHERE raid_id = $1 AND id = ANY($2::UUID[])",
+        raid_id,
+        &ids
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/* Reliability: attendance marks of the last RELIABILITY_DAYS, bench streak capped so it can't outweigh everything */
+const RELIABILITY_DAYS: i32 = 60;
+const BENCH_STREAK_CAP: i64 = 5;
+
+/// user_id -> reliability score in this guild: +2 per raid in the current bench streak (recent past
+/// raids in a row the user signed up for but never got a main slot), −3 per no-show and −1 per late
+/// mark. Users without history score 0. `raid_id` itself is left out of the streak.
+pub async fn reliability_scores(
+    pool: &PgPool,
+    guild_id: i64,
+    raid_id: Uuid,
+    user_ids: &[i64],
+) -> anyhow::Result<HashMap<i64, i64>> {
+    if user_ids.is_empty() { return Ok(HashMap::new()); }
+    let rows = sqlx::query!(
+        r#"
+        WITH signups AS (
+          SELECT p.user_id, r.scheduled_for, bool_or(p.is_main) AS played
+          FROM raid_participants p
+          JOIN raids r ON r.id = p.raid_id
+          WHERE r.guild_id = $1 AND r.id <> $2 AND r.scheduled_for < now()
+            AND p.user_id = ANY($3::BIGINT[])
+          GROUP BY p.user_id, r.id, r.scheduled_for
+        ), ranked AS (
+          -- mains counted from the newest raid backwards; 0 = still inside the bench streak
+          SELECT user_id,
+                 COUNT(*) FILTER (WHERE played) OVER (PARTITION BY user_id ORDER BY scheduled_for DESC) AS mains_since
+          FROM signups
+        ), streaks AS (
+          SELECT user_id, COUNT(*) FILTER (WHERE mains_since = 0) AS bench_streak
+          FROM ranked
+          GROUP BY user_id
+        ), marks AS (
+          SELECT user_id,
+                 COUNT(*) FILTER (WHERE status = 'no_show') AS no_shows,
+                 COUNT(*) FILTER (WHERE status = 'late') AS lates
+          FROM raid_attendance
+          WHERE guild_id = $1 AND user_id = ANY($3::BIGINT[])
+            AND marked_at > now() - make_interval(days => $4)
+          GROUP BY user_id
+        )
+        SELECT u.user_id as "user_id!",
+               COALESCE(s.bench_streak, 0) as "bench_streak!",
+               COALESCE(m.no_shows, 0) as "no_shows!",
+               COALESCE(m.lates, 0) as "lates!"
+        FROM unnest($3::BIGINT[]) AS u(user_id)
+        LEFT JOIN streaks s ON s.user_id = u.user_id
+        LEFT JOIN marks m ON m.user_id = u.user_id
+        "#,
+        guild_id,
+        raid_id,
+        user_ids,
+        RELIABILITY_DAYS
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.user_id, 2 * r.bench_streak.min(BENCH_STREAK_CAP) - 3 * r.no_shows - r.lates))
+        .collect())
+}
+
+/// Moves the `count` most recently joined mains back to reserve (newest first, ties by row id so the
+/// choice is stable). With `alts_only` only alt rows are considered. Used when slots shrink.
+pub async fn demote_latest_mains(
+    pool: &PgPool,
+    raid_id: Uuid,
+    count: i64,
+    alts_only: bool,
+) -> anyhow::Result<u64> {
+    if count <= 0 { return Ok(0); }
+    let res = sqlx::query!(
         r#"
         WITH c AS (
           SELECT id FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = TRUE
-            AND NOT (user_id = ANY($3::BIGINT[]))
-          ORDER BY joined_at ASC
+          WHERE raid_id = $1 AND is_main = TRUE AND ($3 = FALSE OR is_alt = TRUE)
+          ORDER BY joined_at DESC, id DESC
           LIMIT $2
         )
         UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
+        SET is_main = FALSE, is_reserve = TRUE
         FROM c
         WHERE p.id = c.id
-        RETURNING 1
         "#,
         raid_id,
-        promote_alt as i64,
-        exclude_user_ids
+        count,
+        alts_only
     )
-        .fetch_all(pool)
-        .await?
-        .len() as i32;
-
-    Ok(())
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
 }
 
 /// Promote reserves giving priority to users who hold the priority role(s) during an active window.
 /// Order:
 /// 1) Non-alt reserves first, priority users first within that group by (priority -> joined_at ASC)
 /// 2) Alt reserves next within remaining free slots and alt cap, also ordered by (priority -> joined_at ASC)
-/// Users listed in `exclude_user_ids` are skipped (e.g., members with RESERVE_ROLE_NAME role).
+/// Users listed in `exclude_user_ids` are skipped (e.g., members with the guild's reserve role).
 pub async fn promote_reserves_with_priority_excluding(
     pool: &PgPool,
     raid_id: Uuid,
@@ -398,72 +476,29 @@ pub async fn promote_reserves_with_priority_excluding(
     exclude_user_ids: &[i64],
 ) -> anyhow::Result<()> {
     let mains = super::repo::count_mains(pool, raid_id).await? as i32;
-    let mut free = (max_players - mains).max(0);
+    let free = (max_players - mains).max(0);
     if free <= 0 { return Ok(()); }
 
-    // 1) Promote non-alt reserves (priority first)
-    let promoted_non_alt = sqlx::query_scalar!(
+    // Priority users only: non-alt reserves first, then alt reserves under the alt cap
+    let candidates = sqlx::query_scalar!(
         r#"
-        WITH c AS (
-          SELECT id
-          FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = FALSE
-            AND NOT (user_id = ANY($4::BIGINT[]))
-            AND (user_id = ANY($3::BIGINT[]))
-          ORDER BY joined_at ASC
-          LIMIT $2
-        )
-        UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
-        FROM c
-        WHERE p.id = c.id
-        RETURNING 1
+        SELECT id
+        FROM raid_participants
+        WHERE raid_id = $1 AND is_main = FALSE
+          AND NOT (user_id = ANY($3::BIGINT[]))
+          AND (user_id = ANY($2::BIGINT[]))
+        ORDER BY is_alt ASC, joined_at ASC
         "#,
         raid_id,
-        free as i64,
         priority_user_ids,
         exclude_user_ids
     )
         .fetch_all(pool)
-        .await?
-        .len() as i32;
-
-    free -= promoted_non_alt;
-    if free <= 0 { return Ok(()); }
+        .await?;
 
-    // 2) Alt reserves under alt cap (priority first)
     let current_alt_mains = super::repo::count_alt_mains(pool, raid_id).await? as i32;
     let alt_left = (max_alts - current_alt_mains).max(0);
-    if alt_left <= 0 { return Ok(()); }
-
-    let promote_alt = free.min(alt_left);
-    let _promoted_alts = sqlx::query_scalar!(
-        r#"
-        WITH c AS (
-          SELECT id
-          FROM raid_participants
-          WHERE raid_id = $1 AND is_main = FALSE AND is_alt = TRUE
-            AND NOT (user_id = ANY($4::BIGINT[]))
-            AND (user_id = ANY($3::BIGINT[]))
-          ORDER BY joined_at ASC
-          LIMIT $2
-        )
-        UPDATE raid_participants p
-        SET is_main = TRUE, is_reserve = FALSE
-        FROM c
-        WHERE p.id = c.id
-        RETURNING 1
-        "#,
-        raid_id,
-        promote_alt as i64,
-        priority_user_ids,
-        exclude_user_ids
-    )
-        .fetch_all(pool)
-        .await?
-        .len() as i32;
-
-    Ok(())
+    promote_picked(pool, raid_id, &candidates, free, alt_left, true).await
 }
 #[derive(Debug, FromRow)]
 pub struct RestoreRaidRow {
@@ -473,7 +508,7 @@ pub struct RestoreRaidRow {
     pub message_id: i64,
     pub scheduled_for: DateTime<Utc>,
     pub priority_until: Option<DateTime<Utc>>,
-    pub description: String,
+    pub duration_minutes: i32,
     pub is_active: bool,
 }
 
@@ -482,7 +517,7 @@ pub async fn list_active_raids_for_restore(pool: &PgPool) -> anyhow::Result<Vec<
         RestoreRaidRow,
         r#"
         SELECT id, guild_id, channel_id, message_id, scheduled_for,
-               priority_until, description, is_active
+               priority_until, duration_minutes, is_active
         FROM raids
         WHERE is_active = TRUE
         "#
@@ -491,15 +526,40 @@ pub async fn list_active_raids_for_restore(pool: &PgPool) -> anyhow::Result<Vec<
         .await?;
     Ok(rows)
 }
+/* `channel_id` is either a raid's own channel, a raid thread, or the guild's raids channel (thread mode) */
 pub async fn inactive_raid_after_delete_channel(pool: &PgPool, channel_id: i64) -> anyhow::Result<bool> {
     let res = sqlx::query!(
-        "UPDATE raids SET is_active = FALSE WHERE channel_id = $1 AND is_active = TRUE",
+        "UPDATE raids SET is_active = FALSE WHERE (channel_id = $1 OR thread_id = $1) AND is_active = TRUE",
         channel_id
     )
         .execute(pool)
         .await?;
     Ok(res.rows_affected() > 0)
 }
+pub async fn set_raid_event_id(pool: &PgPool, raid_id: Uuid, event_id: Option<i64>) -> anyhow::Result<()> {
+    sqlx::query!("UPDATE raids SET event_id = $2 WHERE id = $1", raid_id, event_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/* Owner cancellation: the raid closes right away, its channel goes later */
+pub async fn cancel_raid(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<()> {
+    sqlx::query!("UPDATE raids SET is_active = FALSE, cancelled_at = now() WHERE id = $1", raid_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn deactivate_raid(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "UPDATE raids SET is_active = FALSE WHERE id = $1 AND is_active = TRUE",
+        raid_id
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
 pub async fn promote_reserves_global_order_excluding(
         pool: &PgPool,
         raid_id: Uuid,
@@ -508,7 +568,7 @@ pub async fn promote_reserves_global_order_excluding(
         exclude_user_ids: &[i64],  // osoby z rolą "reserve" – nigdy auto-promocja
     ) -> anyhow::Result<()> {
     let mains = count_mains(pool, raid_id).await? as i32;
-        let mut free = (max_players - mains).max(0);
+        let free = (max_players - mains).max(0);
         if free <= 0 { return Ok(()); }
 
         let candidates = sqlx::query!(
@@ -521,46 +581,21 @@ pub async fn promote_reserves_global_order_excluding(
             raid_id
         ).fetch_all(pool).await?;
 
-        for c in candidates {
-            if free <= 0 { break; }
-            if exclude_user_ids.contains(&c.user_id) { continue; }
-
-            if c.is_alt {
-                // ALT może wejść jeśli jest slot (limit per-player egzekwowany przy zapisie)
-                sqlx::query!(
-                    "UPDATE raid_participants SET is_main = TRUE, is_reserve = FALSE WHERE id = $1",
-                    c.id
-                ).execute(pool).await?;
-                free -= 1;
-            } else {
-                // Promote main
-                sqlx::query!(
-                    "UPDATE raid_participants SET is_main = TRUE, is_reserve = FALSE WHERE id = $1",
-                    c.id
-                ).execute(pool).await?;
-                free -= 1;
-
-                // Spróbuj od razu promować najstarszego alta tego samego usera
-                if free > 0 {
-                    if let Some(alt_row) = sqlx::query!(
-                        r#"
-                    SELECT id FROM raid_participants
-                    WHERE raid_id = $1 AND user_id = $2 AND is_main = FALSE AND is_alt = TRUE
-                    ORDER BY joined_at ASC
-                    LIMIT 1
-                    "#,
-                        raid_id, c.user_id
-                    ).fetch_optional(pool).await? {
-                        sqlx::query!(
-                            "UPDATE raid_participants SET is_main = TRUE, is_reserve = FALSE WHERE id = $1",
-                            alt_row.id
-                        ).execute(pool).await?;
-                        free -= 1;
-                    }
+        // Kolejność zapisu; za mainem od razu najstarszy alt tego samego usera
+        let mut order: Vec<Uuid> = Vec::new();
+        for c in candidates.iter().filter(|c| !exclude_user_ids.contains(&c.user_id)) {
+            if !order.contains(&c.id) {
+                order.push(c.id);
+            }
+            if !c.is_alt {
+                if let Some(alt) = candidates.iter().find(|a| a.is_alt && a.user_id == c.user_id && !order.contains(&a.id)) {
+                    order.push(alt.id);
                 }
             }
         }
-        Ok(())
+
+        // ALT może wejść jeśli jest slot (limit per-player egzekwowany przy zapisie)
+        promote_picked(pool, raid_id, &order, free, free, false).await
     }
 
 #[derive(Debug, FromRow, Clone)]
@@ -576,6 +611,9 @@ pub struct GuildRaidListRow {
     pub max_players: i32,
     pub allow_alts: bool,
     pub max_alts: i32,
+    pub thread_id: Option<i64>,
+    pub description: String,
+    pub duration_minutes: i32,
 }
 
 pub async fn list_active_raids_by_guild(pool: &PgPool, guild_id: i64) -> anyhow::Result<Vec<GuildRaidListRow>> {
@@ -583,7 +621,8 @@ pub async fn list_active_raids_by_guild(pool: &PgPool, guild_id: i64) -> anyhow:
         GuildRaidListRow,
         r#"
         SELECT id, guild_id, channel_id, message_id, scheduled_for,
-               created_by, owner_id, raid_name, max_players, allow_alts, max_alts
+               created_by, owner_id, raid_name, max_players, allow_alts, max_alts, thread_id,
+               description, duration_minutes
         FROM raids
         WHERE is_active = TRUE AND guild_id = $1
         ORDER BY scheduled_for ASC
@@ -595,6 +634,23 @@ pub async fn list_active_raids_by_guild(pool: &PgPool, guild_id: i64) -> anyhow:
     Ok(rows)
 }
 
+/* Active raids in a guild the user is signed up for: raid id -> has a MAIN row */
+pub async fn list_user_signups(pool: &PgPool, guild_id: i64, user_id: i64) -> anyhow::Result<HashMap<Uuid, bool>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.raid_id, bool_or(p.is_main) as "main_any!"
+        FROM raid_participants p
+        JOIN raids r ON r.id = p.raid_id
+        WHERE r.guild_id = $1 AND r.is_active = TRUE AND p.user_id = $2
+        GROUP BY p.raid_id
+        "#,
+        guild_id, user_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|r| (r.raid_id, r.main_any)).collect())
+}
+
 /* GUILD RAID LIST MAPPING (persistence for consolidated list) */
 
 #[derive(Debug, FromRow, Clone)]
@@ -690,3 +746,932 @@ pub async fn get_guild_raid_list_by_channel(pool: &PgPool, channel_id: i64) -> a
 }
 
 
+
+/* RAID TEMPLATES (weekly recurring raids) */
+
+pub async fn create_raid_template(pool: &PgPool, t: &RaidTemplate) -> anyhow::Result<RaidTemplate> {
+    let row = sqlx::query_as!(
+        RaidTemplate,
+        r#"
+        INSERT INTO raid_templates (
+            id, guild_id, created_by, raid_name, weekday, start_time, max_players, allow_alts,
+            max_alts, is_priority, priority_role_ids, priority_hours, description, days_ahead
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14)
+        RETURNING id, guild_id, created_by, raid_name, weekday, start_time, max_players, allow_alts,
+                  max_alts, is_priority, priority_role_ids, priority_hours, description, days_ahead,
+                  last_run_for, is_active
+        "#,
+        t.id, t.guild_id, t.created_by, t.raid_name, t.weekday, t.start_time, t.max_players, t.allow_alts,
+        t.max_alts, t.is_priority, &t.priority_role_ids, t.priority_hours, t.description, t.days_ahead
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn get_raid_template(pool: &PgPool, template_id: Uuid) -> anyhow::Result<Option<RaidTemplate>> {
+    let row = sqlx::query_as!(
+        RaidTemplate,
+        r#"
+        SELECT id, guild_id, created_by, raid_name, weekday, start_time, max_players, allow_alts,
+               max_alts, is_priority, priority_role_ids, priority_hours, description, days_ahead,
+               last_run_for, is_active
+        FROM raid_templates
+        WHERE id = $1
+        "#,
+        template_id
+    )
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn list_raid_templates_by_guild(pool: &PgPool, guild_id: i64) -> anyhow::Result<Vec<RaidTemplate>> {
+    let rows = sqlx::query_as!(
+        RaidTemplate,
+        r#"
+        SELECT id, guild_id, created_by, raid_name, weekday, start_time, max_players, allow_alts,
+               max_alts, is_priority, priority_role_ids, priority_hours, description, days_ahead,
+               last_run_for, is_active
+        FROM raid_templates
+        WHERE guild_id = $1 AND is_active = TRUE
+        ORDER BY weekday ASC, start_time ASC
+        "#,
+        guild_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+pub async fn list_active_raid_templates(pool: &PgPool) -> anyhow::Result<Vec<RaidTemplate>> {
+    let rows = sqlx::query_as!(
+        RaidTemplate,
+        r#"
+        SELECT id, guild_id, created_by, raid_name, weekday, start_time, max_players, allow_alts,
+               max_alts, is_priority, priority_role_ids, priority_hours, description, days_ahead,
+               last_run_for, is_active
+        FROM raid_templates
+        WHERE is_active = TRUE
+        "#
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Soft-delete so a timer that is already sleeping sees `is_active = FALSE` and stops.
+pub async fn deactivate_raid_template(pool: &PgPool, guild_id: i64, template_id: Uuid) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "UPDATE raid_templates SET is_active = FALSE WHERE id = $1 AND guild_id = $2 AND is_active = TRUE",
+        template_id, guild_id
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Marks `occurrence` as posted. Returns false if it was already claimed (or the template is gone),
+/// so the same week is never created twice.
+pub async fn claim_raid_template_run(pool: &PgPool, template_id: Uuid, occurrence: DateTime<Utc>) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE raid_templates
+        SET last_run_for = $2
+        WHERE id = $1 AND is_active = TRUE AND (last_run_for IS NULL OR last_run_for < $2)
+        "#,
+        template_id, occurrence
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Undoes `claim_raid_template_run` after a failed post so the week is tried again.
+pub async fn release_raid_template_run(
+    pool: &PgPool,
+    template_id: Uuid,
+    occurrence: DateTime<Utc>,
+    previous: Option<DateTime<Utc>>,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "UPDATE raid_templates SET last_run_for = $3 WHERE id = $1 AND last_run_for = $2",
+        template_id, occurrence, previous
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/* RAID TYPES (catalog behind /raid autocomplete) */
+
+/// Case-insensitive lookup of the type as `guild_id` sees it: the guild's own row, else the
+/// default (guild 0). Returns removed types too so old raids keep their emoji/slug.
+pub async fn get_raid_type(pool: &PgPool, guild_id: i64, name: &str) -> anyhow::Result<Option<RaidType>> {
+    let row = sqlx::query_as!(
+        RaidType,
+        r#"
+        SELECT guild_id, name, slug, emoji, default_max_players, default_duration_minutes,
+               default_allow_alts, default_max_alts, is_active
+        FROM raid_types
+        WHERE guild_id IN (0, $1) AND lower(name) = lower($2)
+        ORDER BY guild_id DESC
+        LIMIT 1
+        "#,
+        guild_id, name
+    )
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Active types of `guild_id` (its own rows over the defaults) whose name contains `query`
+/// (case-insensitive), for autocomplete.
+pub async fn search_raid_types(pool: &PgPool, guild_id: i64, query: &str, limit: i64) -> anyhow::Result<Vec<RaidType>> {
+    let rows = sqlx::query_as!(
+        RaidType,
+        r#"
+        SELECT guild_id as "guild_id!", name as "name!", slug as "slug!", emoji as "emoji!",
+               default_max_players as "default_max_players!", default_duration_minutes as "default_duration_minutes!",
+               default_allow_alts as "default_allow_alts!", default_max_alts as "default_max_alts!",
+               is_active as "is_active!"
+        FROM (
+            SELECT DISTINCT ON (lower(name)) *
+            FROM raid_types
+            WHERE guild_id IN (0, $1)
+            ORDER BY lower(name), guild_id DESC
+        ) t
+        WHERE is_active = TRUE AND name ILIKE '%' || $2 || '%'
+        ORDER BY name ASC
+        LIMIT $3
+        "#,
+        guild_id, query, limit
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Inserts or overwrites `guild_id`'s own copy of a type (re-activating it). Defaults stay
+/// untouched; the guild's row shadows the default of the same name.
+pub async fn upsert_raid_type(pool: &PgPool, guild_id: i64, t: &RaidType) -> anyhow::Result<RaidType> {
+    let row = sqlx::query_as!(
+        RaidType,
+        r#"
+        INSERT INTO raid_types (
+            guild_id, name, slug, emoji, default_max_players, default_duration_minutes,
+            default_allow_alts, default_max_alts, is_active
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,TRUE)
+        ON CONFLICT (guild_id, lower(name)) DO UPDATE
+          SET slug = EXCLUDED.slug,
+              emoji = EXCLUDED.emoji,
+              default_max_players = EXCLUDED.default_max_players,
+              default_duration_minutes = EXCLUDED.default_duration_minutes,
+              default_allow_alts = EXCLUDED.default_allow_alts,
+              default_max_alts = EXCLUDED.default_max_alts,
+              is_active = TRUE
+        RETURNING guild_id, name, slug, emoji, default_max_players, default_duration_minutes,
+                  default_allow_alts, default_max_alts, is_active
+        "#,
+        guild_id, t.name, t.slug, t.emoji, t.default_max_players, t.default_duration_minutes,
+        t.default_allow_alts, t.default_max_alts
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Hides a type from `guild_id` only. A default is shadowed by a removed guild copy, so other
+/// guilds keep it.
+pub async fn deactivate_raid_type(pool: &PgPool, guild_id: i64, name: &str) -> anyhow::Result<bool> {
+    let Some(t) = get_raid_type(pool, guild_id, name).await?.filter(|t| t.is_active) else {
+        return Ok(false);
+    };
+    sqlx::query!(
+        r#"
+        INSERT INTO raid_types (
+            guild_id, name, slug, emoji, default_max_players, default_duration_minutes,
+            default_allow_alts, default_max_alts, is_active
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,FALSE)
+        ON CONFLICT (guild_id, lower(name)) DO UPDATE SET is_active = FALSE
+        "#,
+        guild_id, t.name, t.slug, t.emoji, t.default_max_players, t.default_duration_minutes,
+        t.default_allow_alts, t.default_max_alts
+    )
+        .execute(pool)
+        .await?;
+    Ok(true)
+}
+
+/* GAME CATALOG (classes and SPs) */
+
+/// All classes, removed ones included, in menu order.
+pub async fn list_game_classes(pool: &PgPool) -> anyhow::Result<Vec<GameClass>> {
+    let rows = sqlx::query_as!(
+        GameClass,
+        "SELECT code, name, aliases, position, is_active FROM game_classes ORDER BY position ASC, code ASC"
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// All SPs, removed ones included, in menu order.
+pub async fn list_game_sps(pool: &PgPool) -> anyhow::Result<Vec<GameSp>> {
+    let rows = sqlx::query_as!(
+        GameSp,
+        r#"
+        SELECT class_code, code, name, emoji_name, position, is_active
+        FROM game_sps
+        ORDER BY class_code ASC, position ASC, code ASC
+        "#
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Inserts a class, or re-activates and overwrites one with the same code.
+pub async fn upsert_game_class(pool: &PgPool, c: &GameClass) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO game_classes (code, name, aliases, position, is_active)
+        VALUES ($1,$2,$3,$4,TRUE)
+        ON CONFLICT (code) DO UPDATE
+          SET name = EXCLUDED.name,
+              aliases = EXCLUDED.aliases,
+              position = EXCLUDED.position,
+              is_active = TRUE
+        "#,
+        c.code, c.name, &c.aliases, c.position
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Inserts an SP, or re-activates and overwrites one with the same class and code.
+pub async fn upsert_game_sp(pool: &PgPool, sp: &GameSp) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO game_sps (class_code, code, name, emoji_name, position, is_active)
+        VALUES ($1,$2,$3,$4,$5,TRUE)
+        ON CONFLICT (class_code, code) DO UPDATE
+          SET name = EXCLUDED.name,
+              emoji_name = EXCLUDED.emoji_name,
+              position = EXCLUDED.position,
+              is_active = TRUE
+        "#,
+        sp.class_code, sp.code, sp.name, sp.emoji_name, sp.position
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Soft delete: existing signups keep showing the SP.
+pub async fn deactivate_game_sp(pool: &PgPool, class_code: &str, code: &str) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "UPDATE game_sps SET is_active = FALSE WHERE class_code = $1 AND code = $2 AND is_active = TRUE",
+        class_code, code
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn deactivate_game_class(pool: &PgPool, code: &str) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "UPDATE game_classes SET is_active = FALSE WHERE code = $1 AND is_active = TRUE",
+        code
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/* JOIN RULES */
+
+/// Every rule of the guild (all scopes), in evaluation order.
+pub async fn list_join_rules(pool: &PgPool, guild_id: i64) -> anyhow::Result<Vec<JoinRule>> {
+    let rows = sqlx::query_as!(
+        JoinRule,
+        r#"
+        SELECT id, guild_id, raid_type, role_id, access, tag_suffix, position
+        FROM join_rules
+        WHERE guild_id = $1
+        ORDER BY raid_type ASC, position ASC, id ASC
+        "#,
+        guild_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Adds a rule, or updates tag and position of the same role/access in that scope.
+pub async fn upsert_join_rule(pool: &PgPool, r: &JoinRule) -> anyhow::Result<JoinRule> {
+    let row = sqlx::query_as!(
+        JoinRule,
+        r#"
+        INSERT INTO join_rules (guild_id, raid_type, role_id, access, tag_suffix, position)
+        VALUES ($1,$2,$3,$4,$5,$6)
+        ON CONFLICT (guild_id, raid_type, role_id, access) DO UPDATE
+          SET tag_suffix = EXCLUDED.tag_suffix,
+              position = EXCLUDED.position
+        RETURNING id, guild_id, raid_type, role_id, access, tag_suffix, position
+        "#,
+        r.guild_id, r.raid_type, r.role_id, r.access, r.tag_suffix, r.position
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn delete_join_rule(pool: &PgPool, guild_id: i64, id: i64) -> anyhow::Result<bool> {
+    let res = sqlx::query!("DELETE FROM join_rules WHERE guild_id = $1 AND id = $2", guild_id, id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Drops all rules of one scope (`raid_type` "" = the guild's own rules).
+pub async fn delete_join_rules_scope(pool: &PgPool, guild_id: i64, raid_type: &str) -> anyhow::Result<u64> {
+    let res = sqlx::query!(
+        "DELETE FROM join_rules WHERE guild_id = $1 AND lower(raid_type) = lower($2)",
+        guild_id, raid_type
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn list_guild_settings(pool: &PgPool) -> anyhow::Result<Vec<GuildSettings>> {
+    let rows = sqlx::query_as!(
+        GuildSettings,
+        r#"
+        SELECT guild_id, timezone,
+               weekday_categories as "weekday_categories: Json<HashMap<String, i64>>",
+               channel_name_template, fallback_category_id, max_channels_per_category,
+               raid_thread_channel_id,
+               organiser_role_id, admin_role_id, reserve_role_id, alt_allow_role_id,
+               c90_role_id, c1_89_role_id, default_priority_role_id, promotion_policy
+        FROM guild_settings
+        "#
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Writes every setting of the guild (the row is created on first save) and returns the stored row.
+pub async fn upsert_guild_settings(pool: &PgPool, s: &GuildSettings) -> anyhow::Result<GuildSettings> {
+    let row = sqlx::query_as!(
+        GuildSettings,
+        r#"
+        INSERT INTO guild_settings (guild_id, timezone, weekday_categories, channel_name_template,
+                                    fallback_category_id, max_channels_per_category, raid_thread_channel_id,
+                                    organiser_role_id, admin_role_id, reserve_role_id, alt_allow_role_id,
+                                    c90_role_id, c1_89_role_id, default_priority_role_id, promotion_policy)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT (guild_id) DO UPDATE
+          SET timezone = EXCLUDED.timezone,
+              weekday_categories = EXCLUDED.weekday_categories,
+              channel_name_template = EXCLUDED.channel_name_template,
+              fallback_category_id = EXCLUDED.fallback_category_id,
+              max_channels_per_category = EXCLUDED.max_channels_per_category,
+              raid_thread_channel_id = EXCLUDED.raid_thread_channel_id,
+              organiser_role_id = EXCLUDED.organiser_role_id,
+              admin_role_id = EXCLUDED.admin_role_id,
+              reserve_role_id = EXCLUDED.reserve_role_id,
+              alt_allow_role_id = EXCLUDED.alt_allow_role_id,
+              c90_role_id = EXCLUDED.c90_role_id,
+              c1_89_role_id = EXCLUDED.c1_89_role_id,
+              default_priority_role_id = EXCLUDED.default_priority_role_id,
+              promotion_policy = EXCLUDED.promotion_policy,
+              updated_at = now()
+        RETURNING guild_id, timezone,
+                  weekday_categories as "weekday_categories: Json<HashMap<String, i64>>",
+                  channel_name_template, fallback_category_id, max_channels_per_category,
+                  raid_thread_channel_id,
+                  organiser_role_id, admin_role_id, reserve_role_id, alt_allow_role_id,
+                  c90_role_id, c1_89_role_id, default_priority_role_id, promotion_policy
+        "#,
+        s.guild_id,
+        s.timezone,
+        &s.weekday_categories as &Json<HashMap<String, i64>>,
+        s.channel_name_template,
+        s.fallback_category_id,
+        s.max_channels_per_category,
+        s.raid_thread_channel_id,
+        s.organiser_role_id,
+        s.admin_role_id,
+        s.reserve_role_id,
+        s.alt_allow_role_id,
+        s.c90_role_id,
+        s.c1_89_role_id,
+        s.default_priority_role_id,
+        s.promotion_policy
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/* How many active raids live in any of these channels (used for the per-category cap) */
+pub async fn count_active_raids_in_channels(pool: &PgPool, channel_ids: &[i64]) -> anyhow::Result<i64> {
+    let rec = sqlx::query_scalar!(
+        r#"SELECT COUNT(*)::BIGINT FROM raids WHERE is_active = TRUE AND channel_id = ANY($1)"#,
+        channel_ids
+    ).fetch_one(pool).await?;
+    Ok(rec.unwrap_or(0))
+}
+
+pub async fn set_raid_composition(pool: &PgPool, raid_id: Uuid, rules: &[CompositionRule]) -> anyhow::Result<()> {
+    sqlx::query!(
+        "UPDATE raids SET composition = $2 WHERE id = $1",
+        raid_id,
+        Json(rules) as Json<&[CompositionRule]>
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/* ATTENDANCE */
+
+/// True only for the first caller, so the end-of-raid panel is sent once.
+pub async fn claim_attendance_request(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "UPDATE raids SET attendance_requested = TRUE WHERE id = $1 AND attendance_requested = FALSE",
+        raid_id
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn upsert_attendance(
+    pool: &PgPool,
+    raid_id: Uuid,
+    guild_id: i64,
+    user_ids: &[i64],
+    status: &str,
+    marked_by: i64,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO raid_attendance (raid_id, user_id, guild_id, status, marked_by)
+        SELECT $1, u, $2, $4, $5 FROM unnest($3::BIGINT[]) AS u
+        ON CONFLICT (raid_id, user_id) DO UPDATE
+          SET status = EXCLUDED.status, marked_by = EXCLUDED.marked_by, marked_at = now()
+        "#,
+        raid_id, guild_id, user_ids, status, marked_by
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_raid_attendance(pool: &PgPool, raid_id: Uuid) -> anyhow::Result<Vec<RaidAttendance>> {
+    let rows = sqlx::query_as!(
+        RaidAttendance,
+        r#"
+        SELECT raid_id, user_id, guild_id, status, marked_by, marked_at
+        FROM raid_attendance
+        WHERE raid_id = $1
+        ORDER BY marked_at ASC
+        "#,
+        raid_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+#[derive(Debug, FromRow)]
+pub struct UserAttendanceRow {
+    pub raid_id: Uuid,
+    pub raid_name: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub status: String,
+}
+
+/// A player's attendance in one guild, newest raid first.
+pub async fn list_user_attendance(pool: &PgPool, guild_id: i64, user_id: i64) -> anyhow::Result<Vec<UserAttendanceRow>> {
+    let rows = sqlx::query_as!(
+        UserAttendanceRow,
+        r#"
+        SELECT a.raid_id, r.raid_name, r.scheduled_for, a.status
+        FROM raid_attendance a
+        JOIN raids r ON r.id = a.raid_id
+        WHERE a.guild_id = $1 AND a.user_id = $2
+        ORDER BY r.scheduled_for DESC
+        "#,
+        guild_id, user_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/* PLAYER PROFILES */
+
+/// The user's saved characters, default first.
+pub async fn list_player_profiles(pool: &PgPool, user_id: i64) -> anyhow::Result<Vec<PlayerProfile>> {
+    let rows = sqlx::query_as!(
+        PlayerProfile,
+        r#"
+        SELECT id, user_id, name, class, active_sp, extra_sps, is_default, created_at
+        FROM player_profiles
+        WHERE user_id = $1
+        ORDER BY is_default DESC, created_at ASC
+        "#,
+        user_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Inserts or updates the profile with this name (matched case-insensitively); keeps its default flag.
+pub async fn upsert_player_profile(pool: &PgPool, p: &PlayerProfile) -> anyhow::Result<PlayerProfile> {
+    let updated = sqlx::query_as!(
+        PlayerProfile,
+        r#"
+        UPDATE player_profiles
+        SET class = $3, active_sp = $4, extra_sps = $5
+        WHERE user_id = $1 AND lower(name) = lower($2)
+        RETURNING id, user_id, name, class, active_sp, extra_sps, is_default, created_at
+        "#,
+        p.user_id, p.name, p.class, p.active_sp, &p.extra_sps
+    )
+        .fetch_optional(pool)
+        .await?;
+    if let Some(row) = updated {
+        return Ok(row);
+    }
+
+    let row = sqlx::query_as!(
+        PlayerProfile,
+        r#"
+        INSERT INTO player_profiles (user_id, name, class, active_sp, extra_sps, is_default)
+        VALUES ($1,$2,$3,$4,$5,$6)
+        RETURNING id, user_id, name, class, active_sp, extra_sps, is_default, created_at
+        "#,
+        p.user_id, p.name, p.class, p.active_sp, &p.extra_sps, p.is_default
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Makes the named profile the user's only default; false if there's no such profile.
+pub async fn set_default_player_profile(pool: &PgPool, user_id: i64, name: &str) -> anyhow::Result<bool> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("UPDATE player_profiles SET is_default = FALSE WHERE user_id = $1 AND is_default", user_id)
+        .execute(&mut *tx)
+        .await?;
+    let res = sqlx::query!(
+        "UPDATE player_profiles SET is_default = TRUE WHERE user_id = $1 AND lower(name) = lower($2)",
+        user_id, name
+    )
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn delete_player_profile(pool: &PgPool, user_id: i64, name: &str) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "DELETE FROM player_profiles WHERE user_id = $1 AND lower(name) = lower($2)",
+        user_id, name
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Replaces the extra SPs of a signup row (Quick join copies them from the profile).
+pub async fn set_extra_sps(pool: &PgPool, participant_id: Uuid, sps: &[String]) -> anyhow::Result<()> {
+    sqlx::query!("UPDATE raid_participants SET extra_sps = $2 WHERE id = $1", participant_id, sps)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Writes the editable raid fields (`/raid_edit`) and returns the stored row.
+pub async fn update_raid_settings(pool: &PgPool, raid: &Raid) -> anyhow::Result<Raid> {
+    let row = sqlx::query_as!(
+        Raid,
+        r#"
+        UPDATE raids
+        SET scheduled_for = $2,
+            max_players = $3,
+            allow_alts = $4,
+            max_alts = $5,
+            description = $6,
+            duration_minutes = $7,
+            is_priority = $8,
+            priority_role_id = $9,
+            priority_until = $10,
+            promotion_policy = $11,
+            min_points = $12
+        WHERE id = $1
+        RETURNING
+            id, guild_id, channel_id, message_id, scheduled_for, created_by, owner_id,
+            description, is_priority, is_active, priority_list as "priority_list: Json<Vec<i64>>",
+            raid_name, max_players, allow_alts, max_alts, priority_role_id, priority_until,
+            duration_minutes, thread_id, event_id,
+            composition as "composition: Json<Vec<CompositionRule>>", promotion_policy, min_points,
+            cancelled_at
+        "#,
+        raid.id, raid.scheduled_for, raid.max_players, raid.allow_alts, raid.max_alts,
+        raid.description, raid.duration_minutes, raid.is_priority,
+        raid.priority_role_id.as_deref(), raid.priority_until, raid.promotion_policy,
+        raid.min_points
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/* POINTS */
+
+pub async fn add_points(
+    pool: &PgPool,
+    guild_id: i64,
+    user_id: i64,
+    amount: i32,
+    reason: &str,
+    given_by: i64,
+) -> anyhow::Result<PointsEntry> {
+    let row = sqlx::query_as!(
+        PointsEntry,
+        r#"
+        INSERT INTO points_ledger (guild_id, user_id, amount, reason, given_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, guild_id, user_id, amount, reason, source, raid_id, given_by, created_at
+        "#,
+        guild_id, user_id, amount, reason, given_by
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// user_id -> balance in the guild for each of `user_ids` (users without rows are left out).
+pub async fn points_balances(pool: &PgPool, guild_id: i64, user_ids: &[i64]) -> anyhow::Result<HashMap<i64, i64>> {
+    if user_ids.is_empty() { return Ok(HashMap::new()); }
+    let rows = sqlx::query!(
+        r#"
+        SELECT user_id, SUM(amount)::BIGINT as "balance!"
+        FROM points_ledger
+        WHERE guild_id = $1 AND user_id = ANY($2::BIGINT[])
+        GROUP BY user_id
+        "#,
+        guild_id, user_ids
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|r| (r.user_id, r.balance)).collect())
+}
+
+pub async fn points_balance(pool: &PgPool, guild_id: i64, user_id: i64) -> anyhow::Result<i64> {
+    Ok(points_balances(pool, guild_id, &[user_id]).await?.get(&user_id).copied().unwrap_or(0))
+}
+
+/// The user's most recent ledger rows in the guild, newest first.
+pub async fn list_points_history(pool: &PgPool, guild_id: i64, user_id: i64, limit: i64) -> anyhow::Result<Vec<PointsEntry>> {
+    let rows = sqlx::query_as!(
+        PointsEntry,
+        r#"
+        SELECT id, guild_id, user_id, amount, reason, source, raid_id, given_by, created_at
+        FROM points_ledger
+        WHERE guild_id = $1 AND user_id = $2
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+        "#,
+        guild_id, user_id, limit
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// (user_id, balance) of the guild's top balances.
+pub async fn points_leaderboard(pool: &PgPool, guild_id: i64, limit: i64) -> anyhow::Result<Vec<(i64, i64)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT user_id, SUM(amount)::BIGINT as "balance!"
+        FROM points_ledger
+        WHERE guild_id = $1
+        GROUP BY user_id
+        HAVING SUM(amount) <> 0
+        ORDER BY 2 DESC, user_id
+        LIMIT $2
+        "#,
+        guild_id, limit
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|r| (r.user_id, r.balance)).collect())
+}
+
+pub async fn list_attendance_points(pool: &PgPool, guild_id: i64) -> anyhow::Result<Vec<AttendancePoints>> {
+    let rows = sqlx::query_as!(
+        AttendancePoints,
+        "SELECT guild_id, raid_type, present, late, no_show FROM attendance_points WHERE guild_id = $1 ORDER BY raid_type",
+        guild_id
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+pub async fn upsert_attendance_points(pool: &PgPool, p: &AttendancePoints) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO attendance_points (guild_id, raid_type, present, late, no_show)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (guild_id, raid_type) DO UPDATE
+          SET present = EXCLUDED.present, late = EXCLUDED.late, no_show = EXCLUDED.no_show
+        "#,
+        p.guild_id, p.raid_type, p.present, p.late, p.no_show
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_attendance_points(pool: &PgPool, guild_id: i64, raid_type: &str) -> anyhow::Result<bool> {
+    let res = sqlx::query!(
+        "DELETE FROM attendance_points WHERE guild_id = $1 AND lower(raid_type) = lower($2)",
+        guild_id, raid_type
+    )
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Sets the attendance award of a raid for `user_ids` to `amount` (replacing an earlier one when
+/// the mark changes); 0 removes it.
+pub async fn set_attendance_award(
+    pool: &PgPool,
+    raid_id: Uuid,
+    guild_id: i64,
+    user_ids: &[i64],
+    amount: i32,
+    reason: &str,
+    given_by: i64,
+) -> anyhow::Result<()> {
+    if amount == 0 {
+        sqlx::query!(
+            "DELETE FROM points_ledger WHERE source = 'attendance' AND raid_id = $1 AND user_id = ANY($2::BIGINT[])",
+            raid_id, user_ids
+        )
+            .execute(pool)
+            .await?;
+        return Ok(());
+    }
+    sqlx::query!(
+        r#"
+        INSERT INTO points_ledger (guild_id, user_id, amount, reason, source, raid_id, given_by)
+        SELECT $2, u, $4, $5, 'attendance', $1, $6 FROM unnest($3::BIGINT[]) AS u
+        ON CONFLICT (raid_id, user_id) WHERE source = 'attendance' DO UPDATE
+          SET amount = EXCLUDED.amount, reason = EXCLUDED.reason, given_by = EXCLUDED.given_by, created_at = now()
+        "#,
+        raid_id, guild_id, user_ids, amount, reason, given_by
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/* LOOT */
+
+pub async fn insert_raid_loot(
+    pool: &PgPool,
+    raid_id: Uuid,
+    guild_id: i64,
+    user_id: i64,
+    item: &str,
+    logged_by: i64,
+) -> anyhow::Result<RaidLoot> {
+    let row = sqlx::query_as!(
+        RaidLoot,
+        r#"
+        INSERT INTO raid_loot (raid_id, guild_id, user_id, item, logged_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, raid_id, guild_id, user_id, item, logged_by, logged_at
+        "#,
+        raid_id, guild_id, user_id, item, logged_by
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+#[derive(Debug, FromRow)]
+pub struct LootHistoryRow {
+    pub item: String,
+    pub user_id: i64,
+    pub raid_id: Uuid,
+    pub raid_name: String,
+    pub scheduled_for: DateTime<Utc>,
+}
+
+/// Loot of the guild, newest first; `user_id` / `item` (substring, any case) narrow it down.
+pub async fn list_loot_history(
+    pool: &PgPool,
+    guild_id: i64,
+    user_id: Option<i64>,
+    item: Option<&str>,
+    limit: i64,
+) -> anyhow::Result<Vec<LootHistoryRow>> {
+    let rows = sqlx::query_as!(
+        LootHistoryRow,
+        r#"
+        SELECT l.item, l.user_id, l.raid_id, r.raid_name, r.scheduled_for
+        FROM raid_loot l
+        JOIN raids r ON r.id = l.raid_id
+        WHERE l.guild_id = $1
+          AND ($2::BIGINT IS NULL OR l.user_id = $2)
+          AND ($3::TEXT IS NULL OR l.item ILIKE '%' || $3 || '%')
+        ORDER BY l.logged_at DESC, l.id DESC
+        LIMIT $4
+        "#,
+        guild_id, user_id, item, limit
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/* HISTORY */
+
+#[derive(Debug, FromRow)]
+pub struct RaidHistoryRow {
+    pub id: Uuid,
+    pub raid_name: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub owner_id: i64,
+    pub max_players: i32,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub mains: i64,
+    pub reserves: i64,
+    pub user_main: Option<bool>, // with a user filter: whether they ended up main
+}
+
+/// Past raids of the guild (closed, cancelled, or over), newest first. With `user_id` only raids
+/// the user signed up for.
+pub async fn list_raid_history(
+    pool: &PgPool,
+    guild_id: i64,
+    user_id: Option<i64>,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<RaidHistoryRow>> {
+    let rows = sqlx::query_as!(
+        RaidHistoryRow,
+        r#"
+        SELECT r.id, r.raid_name, r.scheduled_for, r.owner_id, r.max_players, r.cancelled_at,
+               (SELECT COUNT(*) FROM raid_participants p WHERE p.raid_id = r.id AND p.is_main) as "mains!",
+               (SELECT COUNT(*) FROM raid_participants p WHERE p.raid_id = r.id AND NOT p.is_main) as "reserves!",
+               (SELECT bool_or(p.is_main) FROM raid_participants p WHERE p.raid_id = r.id AND p.user_id = $2) as user_main
+        FROM raids r
+        WHERE r.guild_id = $1
+          AND (NOT r.is_active OR r.scheduled_for + make_interval(mins => r.duration_minutes) < now())
+          AND ($2::BIGINT IS NULL OR EXISTS (SELECT 1 FROM raid_participants p WHERE p.raid_id = r.id AND p.user_id = $2))
+        ORDER BY r.scheduled_for DESC, r.id
+        LIMIT $3 OFFSET $4
+        "#,
+        guild_id, user_id, limit, offset
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+pub async fn count_raid_history(pool: &PgPool, guild_id: i64, user_id: Option<i64>) -> anyhow::Result<i64> {
+    let n = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "n!"
+        FROM raids r
+        WHERE r.guild_id = $1
+          AND (NOT r.is_active OR r.scheduled_for + make_interval(mins => r.duration_minutes) < now())
+          AND ($2::BIGINT IS NULL OR EXISTS (SELECT 1 FROM raid_participants p WHERE p.raid_id = r.id AND p.user_id = $2))
+        "#,
+        guild_id, user_id
+    )
+        .fetch_one(pool)
+        .await?;
+    Ok(n)
+}
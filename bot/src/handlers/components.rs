@@ -2,7 +2,13 @@ use crate::db::repo;
 use crate::handlers::{pool_from_ctx, redis_from_ctx};
 use crate::queue;
 use crate::ui::{embeds, menus};
-use crate::utils::{from_user_id, parse_component_id,mention_user,user_name_best,notify_raid_now,dm_user,ORGANISER_ROLE_NAME};
+use crate::utils::{from_user_id, parse_component_id,mention_user,user_name_best,notify_raid_now,dm_user};
+use crate::catalog;
+use crate::commands::profile::profile_label;
+use crate::composition;
+use crate::db::models::PlayerProfile;
+use crate::join_rules::{self, Access, JoinAccess};
+use crate::settings::{self, GuildRole};
 use dashmap::DashMap;
 use std::collections::{HashMap,HashSet};
 use once_cell::sync::Lazy;
@@ -13,12 +19,11 @@ use serenity::builder::{
     CreateInteractionResponseMessage,
     EditInteractionResponse,
 };
-use serenity::builder::{CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption};
-use chrono_tz::Europe::Warsaw;
+use serenity::builder::{CreateSelectMenu, CreateSelectMenuKind};
+use crate::settings::guild_tz;
 use serenity::builder::{EditMessage, CreateMessage};
 use tokio::time::{sleep, Duration};
 use uuid::Uuid;
-use std::env;
 
 #[derive(Default, Clone)]
 struct JoinSelection {
@@ -41,6 +46,17 @@ pub async fn handle_component(ctx: &Context, it: &ComponentInteraction) -> anyho
         ("pc", "") => save_pick(ctx, it, raid_id, true).await?,
         ("ps", "") => save_pick(ctx, it, raid_id, false).await?,
         ("ok", "") => confirm_join(ctx, it, raid_id).await?,
+        ("qj", "") => quick_join(ctx, it, raid_id).await?,
+        ("qjp", "") => quick_join_pick(ctx, it, raid_id).await?,
+        ("nt", "") => open_note_modal(ctx, it, raid_id).await?,
+        ("tn", "") => toggle_tentative(ctx, it, raid_id).await?,
+        ("atu", "") => crate::commands::attendance::pick(ctx, it, raid_id).await?,
+        ("atm", code) => crate::commands::attendance::mark(ctx, it, raid_id, code).await?,
+        ("atr", "") => crate::commands::attendance::mark(ctx, it, raid_id, "").await?,
+        ("lt", "") => crate::commands::loot::start(ctx, it, raid_id).await?,
+        ("ltu", "") => crate::commands::loot::pick_recipient(ctx, it, raid_id).await?,
+        ("rhd", view) => crate::commands::raid_history::detail(ctx, it, view).await?,
+        ("rhp", view) => crate::commands::raid_history::page(ctx, it, view).await?,
         ("l",  "") => leave_all(ctx, it, raid_id).await?,
         ("la", "") => leave_alts(ctx, it, raid_id).await?,
         ("mg", "") => owner_manage(ctx, it, raid_id).await?,
@@ -59,6 +75,30 @@ pub async fn handle_component(ctx: &Context, it: &ComponentInteraction) -> anyho
         ("aspick", "") => add_sp_pick(ctx, it, raid_id).await?,
         ("csp", "") => change_sp_start(ctx, it, raid_id).await?,
         ("cspick", "") => change_sp_pick(ctx, it, raid_id).await?,
+        ("nc", "") => crate::commands::raid::confirm_new_raid(ctx, it, raid_id).await?, // /raid preview
+        ("nx", "") => crate::commands::raid::cancel_new_raid(ctx, it, raid_id).await?,
+        ("ed", "") => crate::commands::raid_edit::open_edit_modal(ctx, it, raid_id).await?,
+        ("wzt", "") => crate::commands::raid_wizard::pick_type(ctx, it, raid_id).await?,
+        ("wzr", "") => crate::commands::raid_wizard::pick_roles(ctx, it, raid_id).await?,
+        ("wzb", "") => crate::commands::raid_wizard::back(ctx, it, raid_id).await?,
+        ("wzc", "") => crate::commands::raid_wizard::create(ctx, it, raid_id).await?,
+        ("wzx", "") => crate::commands::raid_wizard::cancel(ctx, it, raid_id).await?,
+        ("cln", "") => crate::commands::raid_clone::open_clone_modal(ctx, it, raid_id).await?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub async fn handle_modal(ctx: &Context, m: &ModalInteraction) -> anyhow::Result<()> {
+    let Some((kind, which, raid_id)) = parse_component_id(&m.data.custom_id) else { return Ok(()); };
+
+    match kind.as_str() {
+        "edm" => crate::commands::raid_edit::handle_edit_modal(ctx, m, raid_id).await?,
+        "wzm" => crate::commands::raid_wizard::submit_details(ctx, m, raid_id).await?,
+        "clnm" => crate::commands::raid_clone::handle_clone_modal(ctx, m, raid_id).await?,
+        "ntm" => save_note(ctx, m, raid_id).await?,
+        "ltm" => crate::commands::loot::save(ctx, m, raid_id, &which).await?,
         _ => {}
     }
 
@@ -101,17 +141,8 @@ async fn save_pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid, is_c
             if is_class {
                 // jeżeli zmienił klasę, wyczyść SP jeśli nie pasuje do nowej listy
                 entry.class = Some(v.clone());
-                if let Some(sp) = &entry.sp {
-                    let msw_only = matches!(v.as_str(), "MSW" | "msw");
-                    let sp_num_ok = |s: &str| {
-                        let s = s.trim().to_ascii_uppercase();
-                        if !s.starts_with("SP") { return false; }
-                        let n: i32 = s[2..].parse().unwrap_or(0);
-                        if msw_only { [1,2,3,4,9,10,11].contains(&n) } else { (1..=11).contains(&n) }
-                    };
-                    if !sp_num_ok(sp) {
-                        entry.sp = None;
-                    }
+                if entry.sp.as_deref().is_some_and(|sp| !catalog::sp_allowed(v, sp)) {
+                    entry.sp = None;
                 }
             } else {
                 entry.sp = Some(v.clone());
@@ -141,6 +172,18 @@ async fn save_pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid, is_c
 }
 
 /* === SP management (extra SPs and changing active SP) === */
+
+/* Catalog check for a picked SP against the class of the user's main row; Some = message to show */
+async fn check_sp_for_main(pool: &sqlx::PgPool, raid_id: Uuid, user_id: i64, sp: &str) -> anyhow::Result<Option<String>> {
+    let Some(main) = repo::get_user_main_row(pool, raid_id, user_id).await? else {
+        return Ok(Some("You need a MAIN row to manage SPs.".to_string()));
+    };
+    if catalog::sp_allowed(&main.class, sp) {
+        Ok(None)
+    } else {
+        Ok(Some(format!("{} is not an SP of class {}.", sp, main.class)))
+    }
+}
 async fn add_sp_start(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
     // Load user's main row to determine class and existing SPs
     let pool = pool_from_ctx(ctx).await?;
@@ -151,23 +194,17 @@ async fn add_sp_start(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
         )).await?;
         return Ok(());
     };
-    let class = main.joined_as.split('/').next().map(|s| s.trim().to_string()).unwrap_or_default();
-    let active_sp = main.joined_as.split('/').nth(1).map(|s| s.trim().to_ascii_uppercase());
-    let mut sp_list: Vec<i32> = if class.eq_ignore_ascii_case("MSW") { vec![1,2,3,4,9,10,11] } else { (1..=11).collect() };
+    let class = main.class.clone();
     // Filter out already present SPs
-    let existing: std::collections::HashSet<String> = main.extra_sps.iter().map(|s| s.trim().to_ascii_uppercase()).chain(active_sp.clone().into_iter()).collect();
-    sp_list.retain(|i| !existing.contains(&format!("SP{}", i)));
-    if sp_list.is_empty() {
+    let existing: Vec<String> = main.extra_sps.iter().cloned().chain([main.active_sp.clone()]).collect();
+    let options = menus::sp_options(&class, None, &existing);
+    if options.is_empty() {
         it.create_response(&ctx.http, CreateInteractionResponse::Message(
             CreateInteractionResponseMessage::new().content("No additional SPs available for your class.").ephemeral(true)
         )).await?;
         return Ok(());
     }
     // Build select
-    let options: Vec<CreateSelectMenuOption> = sp_list.into_iter().map(|i| {
-        let label = format!("SP{}", i);
-        CreateSelectMenuOption::new(&label, &label)
-    }).collect();
     let menu = CreateSelectMenu::new(
         format!("r:aspick:{raid_id}"),
         CreateSelectMenuKind::String { options }
@@ -187,8 +224,15 @@ async fn add_sp_pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) ->
     let Some(sp) = values.first() else { return Ok(()); };
     let user_id = from_user_id(it.user.id);
 
-    // Publish to queue for DB write
     let pool = pool_from_ctx(ctx).await?;
+    if let Some(msg) = check_sp_for_main(&pool, raid_id, user_id, sp).await? {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(msg).ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+
+    // Publish to queue for DB write
     let raid = repo::get_raid(&pool, raid_id).await?;
     let redis = redis_from_ctx(ctx).await?;
     let ev = queue::RaidEvent::AddSp { raid_id, guild_id: raid.guild_id, user_id, sp: sp.clone() };
@@ -221,37 +265,17 @@ async fn change_sp_start(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid
         )).await?;
         return Ok(());
     };
-    // Derive class and active SP
-    let class = main
-        .joined_as
-        .split('/')
-        .next()
-        .map(|s| s.trim().to_string())
-        .unwrap_or_else(|| "MSW".to_string());
-    let active_sp = main
-        .joined_as
-        .split('/')
-        .nth(1)
-        .map(|s| s.trim().to_ascii_uppercase())
-        .unwrap_or_else(|| "SP1".to_string());
+    let class = main.class.clone();
+    let active_sp = main.active_sp.clone();
 
     // Full allowed SP set for the class (not just saved extras)
-    let allowed: Vec<i32> = if class.eq_ignore_ascii_case("MSW") {
-        vec![1, 2, 3, 4, 9, 10, 11]
-    } else {
-        (1..=11).collect()
-    };
-    let options: Vec<CreateSelectMenuOption> = allowed
-        .into_iter()
-        .map(|i| {
-            let label = format!("SP{}", i);
-            let mut opt = CreateSelectMenuOption::new(&label, &label);
-            if label.eq_ignore_ascii_case(&active_sp) {
-                opt = opt.default_selection(true);
-            }
-            opt
-        })
-        .collect();
+    let options = menus::sp_options(&class, Some(&active_sp), &[]);
+    if options.is_empty() {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(format!("No SPs known for class {}.", class)).ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
 
     let menu = CreateSelectMenu::new(
         format!("r:cspick:{raid_id}"),
@@ -274,6 +298,12 @@ async fn change_sp_pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid)
     let pool = pool_from_ctx(ctx).await?;
     let raid = repo::get_raid(&pool, raid_id).await?;
     let user_id = from_user_id(it.user.id);
+    if let Some(msg) = check_sp_for_main(&pool, raid_id, user_id, sp).await? {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(msg).ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
 
     // Publish to queue for DB write
     let redis = redis_from_ctx(ctx).await?;
@@ -320,75 +350,248 @@ async fn confirm_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
         let _ = it.delete_response(&ctx.http).await;
         return Ok(());
     }
+    let (class, sp) = (sel.class.clone().unwrap_or_default(), sel.sp.clone().unwrap_or_default());
+    if !catalog::sp_allowed(&class, &sp) {
+        it.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("{} is not an SP of class {}.", sp, class))
+        ).await?;
+        sleep(Duration::from_secs(5)).await;
+        let _ = it.delete_response(&ctx.http).await;
+        return Ok(());
+    }
+
+    sign_up(ctx, it, raid_id, Signup { class, sp, is_alt: !sel.main, extra_sps: None }).await?;
+    JOIN_STATE.remove(&key);
+    Ok(())
+}
+
+/* === Signup note and tentative status === */
+
+const NOTE_MAX: usize = 100;
+
+async fn open_note_modal(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let Some(main) = repo::get_user_main_row(&pool, raid_id, from_user_id(it.user.id)).await? else {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("You need to be signed up to add a note.").ephemeral(true)
+        )).await?;
+        return Ok(());
+    };
+    let modal = CreateModal::new(format!("r:ntm:{raid_id}"), "Signup note").components(vec![
+        CreateActionRow::InputText(
+            CreateInputText::new(InputTextStyle::Short, "Note (empty removes it)", "note")
+                .placeholder("e.g. late 10 min, only if needed")
+                .value(main.note)
+                .max_length(NOTE_MAX as u16)
+                .required(false)
+        ),
+    ]);
+    it.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await?;
+    Ok(())
+}
+
+async fn save_note(ctx: &Context, m: &ModalInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let _ = m.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content("⏳ Processing…").ephemeral(true)
+    )).await;
+
+    let note: String = m.data.components.iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|c| match c {
+            ActionRowComponent::InputText(t) if t.custom_id == "note" => t.value.clone(),
+            _ => None,
+        })
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(NOTE_MAX)
+        .collect();
 
     let pool = pool_from_ctx(ctx).await?;
     let raid = repo::get_raid(&pool, raid_id).await?;
-    if !raid.is_active {
-        it.edit_response(&ctx.http, EditInteractionResponse::new()
-            .content("This raid has been cancelled.")
-        ).await?;
+    let redis = redis_from_ctx(ctx).await?;
+    let ev = queue::RaidEvent::SetNote { raid_id, guild_id: raid.guild_id, user_id: from_user_id(m.user.id), note: note.clone() };
+    let corr = queue::publish(&redis, &ev).await?;
+    let ack = queue::wait_for_ack(&redis, &corr, 900).await?;
+    if ack.is_some_and(|a| !a.ok) {
+        m.edit_response(&ctx.http, EditInteractionResponse::new().content("You're no longer signed up for this raid.")).await?;
         return Ok(());
     }
 
-    // === WYMÓG ról c1-89 lub c90 ===
-    let mut allowed_by_crole = false;
-    let mut has_c1_89 = false;
+    refresh_raid_message(ctx, &pool, raid_id).await?;
+    let reply = if note.is_empty() { "Note removed.".to_string() } else { format!("Note saved: _{}_", note) };
+    m.edit_response(&ctx.http, EditInteractionResponse::new().content(reply)).await?;
+    sleep(Duration::from_secs(5)).await;
+    let _ = m.delete_response(&ctx.http).await;
+    Ok(())
+}
+
+async fn toggle_tentative(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let _ = it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new().content("⏳ Processing your request...").components(Vec::new())
+    )).await;
 
-    if let Some(gid) = it.guild_id {
-        let roles_map = gid.roles(&ctx.http).await?;
-        if let Ok(member) = gid.member(&ctx.http, it.user.id).await {
-            for rid in &member.roles {
-                if let Some(r) = roles_map.get(rid) {
-                    let name = r.name.to_ascii_lowercase();
-                    if name == "c1-89" || name == "c90" {
-                        if name == "c1-89" { has_c1_89 = true; }
-                        allowed_by_crole = true;
-                        break;
-                    }
-                }
-            }
-        }
+    let pool = pool_from_ctx(ctx).await?;
+    let user_id = from_user_id(it.user.id);
+    let Some(main) = repo::get_user_main_row(&pool, raid_id, user_id).await? else {
+        it.edit_response(&ctx.http, EditInteractionResponse::new().content("You're no longer signed up for this raid.")).await?;
+        return Ok(());
+    };
+    let tentative = !main.is_tentative;
+
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    let redis = redis_from_ctx(ctx).await?;
+    let ev = queue::RaidEvent::SetTentative { raid_id, guild_id: raid.guild_id, user_id, tentative };
+    let corr = queue::publish(&redis, &ev).await?;
+    let _ack = queue::wait_for_ack(&redis, &corr, 900).await?;
+
+    refresh_raid_message(ctx, &pool, raid_id).await?;
+    let content = if tentative {
+        "You're **tentative** now: you wait behind the other reserves and only get a slot if nobody else can take it."
+    } else {
+        "You're no longer tentative ✅ — back in line as a reserve (or main, if a slot is free)."
+    };
+    it.edit_response(&ctx.http, EditInteractionResponse::new()
+        .content(content)
+        .components(vec![menus::signup_extras_row(raid_id, tentative)])
+    ).await?;
+    Ok(())
+}
+
+async fn refresh_raid_message(ctx: &Context, pool: &sqlx::PgPool, raid_id: Uuid) -> anyhow::Result<()> {
+    let raid = repo::get_raid(pool, raid_id).await?;
+    let parts = repo::list_participants(pool, raid_id).await?;
+    let embed = embeds::render_raid_embed(ctx, raid.guild_id as u64, &raid, &parts);
+    ChannelId::new(raid.channel_id as u64)
+        .edit_message(&ctx.http, raid.message_id as u64,
+                      EditMessage::new().embed(embed).components(vec![menus::main_buttons_row(raid_id), menus::sp_buttons_row(raid_id)])).await?;
+    Ok(())
+}
+
+/* === Quick join: saved /profile characters instead of the class/SP menus === */
+
+/* Default character as main, or a picker of the other characters as alts once a main is signed up */
+async fn quick_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let pool = pool_from_ctx(ctx).await?;
+    let user_id = from_user_id(it.user.id);
+    let profiles = repo::list_player_profiles(&pool, user_id).await?;
+    let Some(main) = profiles.first() else {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("You have no saved characters yet. Save one with `/profile add`, or use Join (Main).")
+                .ephemeral(true)
+        )).await?;
+        return Ok(());
+    };
+
+    if !repo::user_has_main(&pool, raid_id, user_id).await? {
+        let _ = it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content("⏳ Processing your request...").ephemeral(true)
+        )).await;
+        return quick_join_profile(ctx, it, raid_id, main, false).await;
     }
 
-    if !allowed_by_crole {
+    let options: Vec<CreateSelectMenuOption> = profiles
+        .iter()
+        .skip(1)
+        .take(25)
+        .map(|p| CreateSelectMenuOption::new(format!("{} — {}", p.name, profile_label(p)), p.id.to_string()))
+        .collect();
+    if options.is_empty() {
+        it.create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("You're already signed up. Save your alts with `/profile add` to quick-join them too.")
+                .ephemeral(true)
+        )).await?;
+        return Ok(());
+    }
+    let menu = CreateSelectMenu::new(
+        format!("r:qjp:{raid_id}"),
+        CreateSelectMenuKind::String { options }
+    ).placeholder("Sign up a saved alt").min_values(1).max_values(1);
+
+    it.create_response(&ctx.http, CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content("You're signed up with your main. Pick an alt to add:")
+            .ephemeral(true)
+            .components(vec![CreateActionRow::SelectMenu(menu)])
+    )).await?;
+    Ok(())
+}
+
+async fn quick_join_pick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> anyhow::Result<()> {
+    let ComponentInteractionDataKind::StringSelect { values } = &it.data.kind else { return Ok(()); };
+    let Some(profile_id) = values.first().and_then(|v| v.parse::<i64>().ok()) else { return Ok(()); };
+
+    let _ = it.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new().content("⏳ Processing your request...").components(Vec::new())
+    )).await;
+
+    let pool = pool_from_ctx(ctx).await?;
+    let profiles = repo::list_player_profiles(&pool, from_user_id(it.user.id)).await?;
+    let Some(profile) = profiles.iter().find(|p| p.id == profile_id) else {
+        it.edit_response(&ctx.http, EditInteractionResponse::new().content("That character is no longer saved.")).await?;
+        return Ok(());
+    };
+    quick_join_profile(ctx, it, raid_id, profile, true).await
+}
+
+/* The catalog may have changed since the profile was saved */
+async fn quick_join_profile(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid, profile: &PlayerProfile, is_alt: bool) -> anyhow::Result<()> {
+    if !catalog::sp_allowed(&profile.class, &profile.active_sp) {
         it.edit_response(&ctx.http, EditInteractionResponse::new()
-            .content("You need role **c1-89** or **c90**, to join this raid.")
+            .content(format!("{} is not an SP of class {} any more. Update **{}** with `/profile add`.", profile.active_sp, profile.class, profile.name))
         ).await?;
         return Ok(());
     }
+    let extra_sps = profile.extra_sps.iter().filter(|sp| catalog::sp_allowed(&profile.class, sp)).cloned().collect();
+    sign_up(ctx, it, raid_id, Signup {
+        class: profile.class.clone(),
+        sp: profile.active_sp.clone(),
+        is_alt,
+        extra_sps: Some(extra_sps),
+    }).await
+}
 
-    let tag_suffix = if has_c1_89 { " [-c90]".to_string() } else { String::new() };
+/* What confirm_join or Quick join signs up */
+struct Signup {
+    class: String,
+    sp: String,
+    is_alt: bool,
+    extra_sps: Option<Vec<String>>, // saved profile SPs; None keeps the row's
+}
 
-    // reserve role always → reserve
-    let reserve_role_name = env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string());
-    let mut force_reserve = false;
-    if let Some(gid) = it.guild_id {
-        let roles_map = gid.roles(&ctx.http).await?;
-        if let Ok(member) = gid.member(&ctx.http, it.user.id).await {
-            for rid in &member.roles {
-                if let Some(r) = roles_map.get(rid) {
-                    if r.name.eq_ignore_ascii_case(&reserve_role_name) { force_reserve = true; break; }
-                }
-            }
-        }
+/* Checks and queue publish shared by the join menu and Quick join; the interaction is already ACKed */
+async fn sign_up(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid, signup: Signup) -> anyhow::Result<()> {
+    let Signup { class, sp, is_alt: is_alt_join, extra_sps } = signup;
+    let pool = pool_from_ctx(ctx).await?;
+    let raid = repo::get_raid(&pool, raid_id).await?;
+    if !raid.is_active {
+        it.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content("This raid has been cancelled.")
+        ).await?;
+        return Ok(());
     }
 
-    let alt_allow_role_name = std::env::var("ALT_ALLOW_ROLE_NAME").unwrap_or_else(|_| "Alt_allow".to_string());
-    let mut has_alt_allow_role = false;
-    if let Some(gid) = it.guild_id {
-        let roles_map = gid.roles(&ctx.http).await?;
-        if let Ok(member) = gid.member(&ctx.http, it.user.id).await {
-            for rid in &member.roles {
-                if let Some(r) = roles_map.get(rid) {
-                    if r.name.eq_ignore_ascii_case(&alt_allow_role_name) {
-                        has_alt_allow_role = true;
-                        break;
-                    }
-                }
-            }
-        }
+    // Join rules of this raid type (or the guild's, or the defaults from `/config role`)
+    let member_roles: Vec<RoleId> = match it.guild_id {
+        Some(gid) => gid.member(&ctx.http, it.user.id).await.map(|m| m.roles).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let rules = join_rules::rules_for(&pool, raid.guild_id as u64, &raid.raid_name).await?;
+    let (access, tag_suffix) = rules.join_access(&member_roles);
+    if access == JoinAccess::Denied {
+        it.edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("You need role {} to join this raid.", rules.roles_for(&[Access::Main, Access::Reserve])))
+        ).await?;
+        return Ok(());
     }
 
+    // reserve-only rule → reserve
+    let force_reserve = access == JoinAccess::ReserveOnly;
+
+    let has_alt_allow_role = rules.may_add_alts(&member_roles);
+
     // Priority: jeśli okno aktywne i brak roli → reserve
     let mut must_reserve = force_reserve;
     if let Some(until) = raid.priority_until {
@@ -410,8 +613,6 @@ async fn confirm_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
     let free_main = (raid.max_players - mains_cnt).max(0);
 
     // Pola
-    let joined_as = format!("{} / {}", sel.class.clone().unwrap(), sel.sp.clone().unwrap());
-    let is_alt_join = !sel.main;
     let requester_id = from_user_id(it.user.id);
 
     if is_alt_join && !repo::user_has_main(&pool, raid_id, requester_id).await? {
@@ -424,7 +625,7 @@ async fn confirm_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
     if is_alt_join {
         if !has_alt_allow_role {
             it.edit_response(&ctx.http, EditInteractionResponse::new()
-                .content(&format!("You are not allowed to sign in an alt. Missing role: {}", alt_allow_role_name))
+                .content(&format!("You are not allowed to sign in an alt. Missing role: {}", rules.roles_for(&[Access::Alts])))
             ).await?;
             return Ok(());
         }
@@ -444,18 +645,40 @@ async fn confirm_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
         }
     }
 
+    // Composition: a full class/SP cap sends the signup to reserve (their own main row doesn't count when switching)
+    let mut reserve_note = String::new();
+    if free_main > 0 && !must_reserve && !raid.composition.is_empty() {
+        let mut parts = repo::list_participants(&pool, raid_id).await?;
+        parts.retain(|p| is_alt_join || p.is_alt || p.user_id != requester_id);
+        if let Some(rule) = composition::full_cap(&raid.composition, &parts, &class, &sp) {
+            must_reserve = true;
+            reserve_note = format!("\nRaid already has {} × {} — you're on reserve.", rule.max.unwrap_or_default(), composition::rule_label(rule));
+        }
+    }
+
+    // Points: below the raid's minimum only a reserve spot is open
+    if free_main > 0 && !must_reserve && raid.min_points > 0 {
+        let balance = repo::points_balance(&pool, raid.guild_id, requester_id).await?;
+        if balance < raid.min_points as i64 {
+            must_reserve = true;
+            reserve_note = format!("\nMain slots need {} points (you have {}) — you're on reserve.", raid.min_points, balance);
+        }
+    }
+
     // Publish to Redis queue and wait briefly for ACK
     let can_be_main = free_main > 0 && !must_reserve;
     let redis = redis_from_ctx(ctx).await?;
-    let ev = queue::RaidEvent::Join {
+    let ev = queue::RaidEvent::Join(queue::JoinEvent {
         raid_id,
         guild_id: raid.guild_id,
         user_id: from_user_id(it.user.id),
-        joined_as,
+        class,
+        active_sp: sp,
         main_now: can_be_main,
         tag_suffix: tag_suffix.clone(),
         is_alt: is_alt_join,
-    };
+        extra_sps,
+    });
     let corr = queue::publish(&redis, &ev).await?;
     let _ack = queue::wait_for_ack(&redis, &corr, 900).await?; // best-effort
 
@@ -473,15 +696,22 @@ async fn confirm_join(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
 
     // Finalny komunikat do użytkownika – edycja tej samej odpowiedzi
     it.edit_response(&ctx.http, EditInteractionResponse::new()
-        .content("You're signed in! ✅")
+        .content(format!("You're signed in! ✅{}\nRunning late or not sure yet? Add a note or mark yourself tentative.", reserve_note))
+        .components(vec![menus::signup_extras_row(raid_id, false)])
     ).await?;
-    sleep(Duration::from_secs(5)).await;
-    let _ = it.delete_response(&ctx.http).await;
 
     // also try to refresh guild list immediately (force, non-debounced)
     let _ = crate::commands::raid::force_refresh_guild_raid_list(ctx, raid.guild_id as u64).await;
 
-    JOIN_STATE.remove(&key);
+    // Leave the note/tentative buttons up for a minute without holding the handler
+    tokio::spawn({
+        let http = ctx.http.clone();
+        let it = it.clone();
+        async move {
+            sleep(Duration::from_secs(60)).await;
+            let _ = it.delete_response(&http).await;
+        }
+    });
     Ok(())
 }
 
@@ -518,13 +748,13 @@ async fn leave_all(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -> a
                     "Heads up: user {user} signed off from raid \"{name}\".\nChannel: <#{chan}>\nStarts in: {left}",
                     user = mention_user(user_id),
                     name = raid.raid_name,
-                    chan = raid.channel_id as u64,
+                    chan = raid.chat_channel_id(),
                     left = time_left
                 )),
             )
             .await?;
 
-        ChannelId::new(raid.channel_id as u64)
+        ChannelId::new(raid.chat_channel_id())
             .send_message(
                 &ctx.http,
                 CreateMessage::new().content(format!(
@@ -625,7 +855,7 @@ async fn owner_manage(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
                 .cloned()
                 .unwrap_or_else(|| format!("user {}", p.user_id));
             (
-                format!("{} {}{}", p.joined_as, name, if p.is_alt { " (ALT)" } else { "" }),
+                format!("{} {}{}", p.joined_as(), name, if p.is_alt { " (ALT)" } else { "" }),
                 p.id.to_string(),
             )
         })
@@ -639,7 +869,7 @@ async fn owner_manage(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
                 .cloned()
                 .unwrap_or_else(|| format!("user {}", p.user_id));
             (
-                format!("{} {}{}", p.joined_as, name, if p.is_alt { " (ALT)" } else { "" }),
+                format!("{} {}{}", p.joined_as(), name, if p.is_alt { " (ALT)" } else { "" }),
                 p.id.to_string(),
             )
         })
@@ -654,7 +884,7 @@ async fn owner_manage(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
             (
                 format!(
                     "{} {}{}{}",
-                    p.joined_as,
+                    p.joined_as(),
                     name,
                     if p.is_main { " [MAIN]" } else { " [RES]" },
                     if p.is_alt { " (ALT)" } else { "" }
@@ -716,6 +946,17 @@ async fn owner_manage(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
                 CreateButton::new(format!("r:cx:{raid_id}"))
                     .label("Cancel Raid")
                     .style(ButtonStyle::Danger),
+            ]),
+            CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("r:ed:{raid_id}"))
+                    .label("Edit raid")
+                    .style(ButtonStyle::Primary),
+                CreateButton::new(format!("r:cln:{raid_id}"))
+                    .label("Clone raid")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new(format!("r:lt:{raid_id}"))
+                    .label("Log loot")
+                    .style(ButtonStyle::Secondary),
             ])
         ])
     ).await?;
@@ -772,10 +1013,10 @@ async fn owner_promote(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid)
         ).execute(&pool).await?;
 
         if let Some(uid) = target_user {
-            let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
+            let when_local = raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
             let msg = format!(
                 "✅ You were **promoted to MAIN** for **{}** on {}.\nChannel: <#{}>",
-                raid.raid_name, when_local, raid.channel_id as u64
+                raid.raid_name, when_local, raid.chat_channel_id()
             );
             dm_user(&ctx.http, uid as u64, msg).await;
         }
@@ -824,10 +1065,10 @@ async fn owner_move_to_reserve(ctx: &Context, it: &ComponentInteraction, raid_id
             raid_id, uid
         ).execute(&pool).await?;
         if let Some(uid) = target_user {
-            let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
+            let when_local = raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
             let msg = format!(
                 "↩️ You were **moved to RESERVE** for **{}** on {}.\nChannel: <#{}>",
-                raid.raid_name, when_local, raid.channel_id as u64
+                raid.raid_name, when_local, raid.chat_channel_id()
             );
             dm_user(&ctx.http, uid as u64, msg).await;
         }
@@ -861,18 +1102,14 @@ async fn owner_kick(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) ->
 
         let should_try_promote = raid.priority_until.map(|t| chrono::Utc::now() >= t).unwrap_or(true);
         if should_try_promote {
-            // zbuduj exclude_ids – użytkownicy z rolą RESERVE
+            // zbuduj exclude_ids – użytkownicy z regułą reserve-only
             let mut exclude_ids: Vec<i64> = Vec::new();
             if let Some(gid) = it.guild_id {
-                let roles_map = gid.roles(&ctx.http).await?;
-                let reserve_role_name = std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string());
+                let rules = join_rules::rules_for(&pool, gid.get(), &raid.raid_name).await?;
                 let parts_for_check = repo::list_participants(&pool, raid_id).await?;
                 for p in &parts_for_check {
                     if let Ok(member) = gid.member(&ctx.http, UserId::new(p.user_id as u64)).await {
-                        let has_reserve = member.roles.iter().any(|rid| {
-                            roles_map.get(rid).map_or(false, |r| r.name.eq_ignore_ascii_case(&reserve_role_name))
-                        });
-                        if has_reserve { exclude_ids.push(p.user_id); }
+                        if rules.keeps_on_reserve(&member.roles) { exclude_ids.push(p.user_id); }
                     }
                 }
             }
@@ -899,7 +1136,8 @@ async fn owner_cancel(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
     let raid = repo::get_raid(&pool, raid_id).await?;
     if raid.owner_id != it.user.id.get() as i64 { return Ok(()); }
 
-    sqlx::query!("UPDATE raids SET is_active = FALSE WHERE id = $1", raid_id).execute(&pool).await?;
+    repo::cancel_raid(&pool, raid_id).await?;
+    crate::scheduled_events::delete_raid_event(&ctx.http, &pool, &raid).await;
 
     let parts = repo::list_participants(&pool, raid_id).await?;
     for p in &parts {
@@ -918,10 +1156,11 @@ async fn owner_cancel(ctx: &Context, it: &ComponentInteraction, raid_id: Uuid) -
 
     tokio::spawn({
         let http = ctx.http.clone();
-        let channel_id = raid.channel_id as u64;
+        let pool = pool.clone();
+        let raid_id = raid.id;
         async move {
             tokio::time::sleep(std::time::Duration::from_secs(7200)).await;
-            let _ = ChannelId::new(channel_id).delete(&http).await;
+            let _ = crate::tasks::close_raid(&http, &pool, raid_id).await;
         }
     });
 
@@ -1028,17 +1267,13 @@ async fn owner_change_start(ctx: &Context, it: &ComponentInteraction, raid_id: U
         }
     };
 
-    // Find organiser role id
-    let roles_map = gid.roles(&ctx.http).await?;
-    let organiser_role_id = match roles_map.iter()
-        .find(|(_, r)| r.name.eq_ignore_ascii_case(ORGANISER_ROLE_NAME))
-        .map(|(id, _)| *id)
-    {
+    // Organiser role id
+    let organiser_role_id = match settings::role_id(gid.get(), GuildRole::Organiser) {
         Some(id) => id,
         None => {
             it.create_response(&ctx.http, CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::new()
-                    .content(format!("Role `{}` not found on this server.", ORGANISER_ROLE_NAME))
+                    .content(format!("No organiser role: {}", settings::role_mention(gid.get(), GuildRole::Organiser)))
                     .ephemeral(true)
             )).await?;
             return Ok(());
@@ -1070,14 +1305,14 @@ async fn owner_change_start(ctx: &Context, it: &ComponentInteraction, raid_id: U
     }
 
     if options.is_empty() {
-        options.push((format!("No users with role {}", ORGANISER_ROLE_NAME), "none".into()));
+        options.push(("No users with the organiser role".to_string(), "none".into()));
     }
 
     OWNER_CHANGE.remove(&(it.user.id.get(), raid_id)); // reset previous pick if any
 
     it.create_response(&ctx.http, CreateInteractionResponse::Message(
         CreateInteractionResponseMessage::new()
-            .content(format!("Pick a new owner (role: `{}`), then press **Transfer ownership**.", ORGANISER_ROLE_NAME))
+            .content(format!("Pick a new owner (role: {}), then press **Transfer ownership**.", settings::role_mention(gid.get(), GuildRole::Organiser)))
             .ephemeral(true)
             .components(vec![
                 menus::user_select_row(format!("r:chp:{raid_id}"), "New owner", options),
@@ -1154,16 +1389,12 @@ async fn owner_change_confirm(ctx: &Context, it: &ComponentInteraction, raid_id:
 
     // Optional: verify the selected still has organiser role
     if let Some(gid) = it.guild_id {
-        let roles_map = gid.roles(&ctx.http).await?;
-        if let Some(role_id) = roles_map.iter()
-            .find(|(_, r)| r.name.eq_ignore_ascii_case(ORGANISER_ROLE_NAME))
-            .map(|(id, _)| *id)
-        {
+        if let Some(role_id) = settings::role_id(gid.get(), GuildRole::Organiser) {
             if let Ok(member) = gid.member(&ctx.http, UserId::new(new_owner_u64)).await {
                 if !member.roles.contains(&role_id) {
                     it.create_response(&ctx.http, CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
-                            .content(format!("Selected user no longer has `{}` role.", ORGANISER_ROLE_NAME))
+                            .content(format!("Selected user no longer has the {} role.", settings::role_mention(gid.get(), GuildRole::Organiser)))
                             .ephemeral(true)
                     )).await?;
                     return Ok(());
@@ -1178,13 +1409,13 @@ async fn owner_change_confirm(ctx: &Context, it: &ComponentInteraction, raid_id:
         .await?;
 
     // Notify both owners
-    let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
+    let when_local = raid.scheduled_for.with_timezone(&guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
     let old_owner_u64 = raid.owner_id as u64;
     let new_owner_name = user_name_best(ctx, Some(raid.guild_id as u64), new_owner_u64 as i64).await;
 
     dm_user(&ctx.http, new_owner_u64, format!(
         "👑 You are now **owner** of raid **{}** ({}). Channel: <#{}>",
-        raid.raid_name, when_local, raid.channel_id as u64
+        raid.raid_name, when_local, raid.chat_channel_id()
     )).await;
 
     dm_user(&ctx.http, old_owner_u64, format!(
@@ -1194,6 +1425,7 @@ async fn owner_change_confirm(ctx: &Context, it: &ComponentInteraction, raid_id:
 
     // Refresh message
     raid = repo::get_raid(&pool, raid_id).await?;
+    crate::scheduled_events::sync_raid_event(&ctx.http, &pool, &raid).await;
     let parts = repo::list_participants(&pool, raid_id).await?;
     let embed = embeds::render_raid_embed(ctx, raid.guild_id as u64, &raid, &parts);
     ChannelId::new(raid.channel_id as u64)
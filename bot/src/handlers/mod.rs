@@ -1,6 +1,6 @@
 pub mod components;
 
-use serenity::all::{Context, EventHandler, Interaction, Ready, GuildChannel, Message};
+use serenity::all::{Context, EventHandler, Interaction, Ready, GuildChannel, Message, PartialGuildChannel};
 use serenity::async_trait;
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -25,17 +25,30 @@ impl EventHandler for Handler {
             data.insert::<RedisKey>(self.redis.clone());
         }
 
+        // Guild settings must be cached before anything formats or schedules raid times
+        if let Err(e) = crate::settings::load_all(&self.pool).await {
+            eprintln!("Failed to load guild settings: {e:#}");
+        }
+        for g in &ready.guilds {
+            if let Err(e) = crate::settings::adopt_legacy_roles(&ctx.http, &self.pool, g.id).await {
+                eprintln!("adopt_legacy_roles for guild {} failed: {e:#}", g.id);
+            }
+        }
+        if let Err(e) = crate::catalog::load(&self.pool).await {
+            eprintln!("Failed to load class/SP catalog: {e:#}");
+        }
+
         // (Optional) register slash commands
         if let Err(e) = crate::commands::register_commands(&ctx).await {
             eprintln!("Failed to register commands: {e}");
         }
 
         // Restore all scheduled jobs after restart (non-blocking)
-        let http = ctx.http.clone();
+        let ctx_restore = ctx.clone();
         let pool = self.pool.clone();
         let redis = self.redis.clone();
         tokio::spawn(async move {
-            if let Err(e) = crate::tasks::restore_schedules(http, pool, redis).await {
+            if let Err(e) = crate::tasks::restore_schedules(ctx_restore, pool, redis).await {
                 eprintln!("restore_schedules failed: {e:#}");
             }
         });
@@ -74,14 +87,30 @@ impl EventHandler for Handler {
                     eprintln!("command error: {e}");
                 }
             }
+            Autocomplete(ac) => {
+                if let Err(e) = crate::commands::raid::handle_autocomplete(&ctx, &ac).await {
+                    eprintln!("autocomplete error: {e}");
+                }
+            }
             Component(comp) => {
                 if let Err(e) = components::handle_component(&ctx, &comp).await {
                     eprintln!("component error: {e}");
                 }
             }
+            Modal(m) => {
+                if let Err(e) = components::handle_modal(&ctx, &m).await {
+                    eprintln!("modal error: {e}");
+                }
+            }
             _ => {}
         }
     }
+    async fn thread_delete(&self, _ctx: Context, thread: PartialGuildChannel, _full: Option<GuildChannel>) {
+        if let Err(e) = crate::db::repo::inactive_raid_after_delete_channel(&self.pool, thread.id.get() as i64).await {
+            eprintln!("inactive_raid_after_delete_channel (thread) failed: {e:#}");
+        }
+    }
+
     async fn channel_delete(&self, _ctx: Context, channel: GuildChannel, _messages: Option<Vec<Message>>) {
         let ch_id = channel.id.get() as i64;
         if let Err(e) = crate::db::repo::inactive_raid_after_delete_channel(&self.pool, ch_id).await {
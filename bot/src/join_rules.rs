@@ -0,0 +1,249 @@
+use serenity::all::RoleId;
+use sqlx::PgPool;
+
+use crate::db::models::JoinRule;
+use crate::db::repo;
+use crate::settings::{self, GuildRole};
+
+/* What a rule grants to members holding its role */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Access {
+    Main,        // may join as main
+    Reserve,     // may join, only as reserve (and is never auto-promoted)
+    KeepReserve, // lets nobody in, but keeps members another rule admitted on reserve
+    Alts,        // may sign up alts
+}
+
+impl Access {
+    pub const ALL: [Access; 4] = [Access::Main, Access::Reserve, Access::KeepReserve, Access::Alts];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Access::Main => "main",
+            Access::Reserve => "reserve",
+            Access::KeepReserve => "keep_reserve",
+            Access::Alts => "alts",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Access> {
+        Access::ALL.into_iter().find(|a| a.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Access::Main => "join as main",
+            Access::Reserve => "join as reserve only",
+            Access::KeepReserve => "stay on reserve (needs another rule to join)",
+            Access::Alts => "sign up alts",
+        }
+    }
+}
+
+/// How a member may join one raid.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JoinAccess {
+    Main,
+    ReserveOnly,
+    Denied,
+}
+
+/* The rules that apply to one raid, in evaluation order */
+pub struct RuleSet {
+    pub rules: Vec<JoinRule>,
+    pub is_default: bool,
+}
+
+/// Built-in rules from the guild's `/config role` settings, matching how joining always worked:
+/// c90 and c1-89 may join (c1-89 tagged " [-c90]"), the reserve role keeps an admitted member on
+/// reserve (it doesn't admit anyone by itself) and the alt role may sign up alts. Roles that aren't
+/// configured add no rule.
+pub fn default_rules(guild_id: u64) -> Vec<JoinRule> {
+    let rule = |role: GuildRole, access: Access, tag: Option<&str>, position: i32| {
+        settings::role_id(guild_id, role).map(|id| JoinRule {
+            id: 0,
+            guild_id: guild_id as i64,
+            raid_type: String::new(),
+            role_id: id.get() as i64,
+            access: access.key().to_string(),
+            tag_suffix: tag.map(str::to_string),
+            position,
+        })
+    };
+    [
+        rule(GuildRole::C90, Access::Main, None, 0),
+        rule(GuildRole::C1to89, Access::Main, Some(" [-c90]"), 1),
+        rule(GuildRole::Reserve, Access::KeepReserve, None, 2),
+        rule(GuildRole::AltAllow, Access::Alts, None, 3),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// The raid type's own rules if it has any, else the guild's, else the defaults.
+pub fn pick_scope(guild_id: u64, all: Vec<JoinRule>, raid_type: &str) -> RuleSet {
+    let (typed, rest): (Vec<JoinRule>, Vec<JoinRule>) = all
+        .into_iter()
+        .partition(|r| !r.raid_type.is_empty() && r.raid_type.eq_ignore_ascii_case(raid_type));
+    if !typed.is_empty() {
+        return RuleSet { rules: typed, is_default: false };
+    }
+    let guild: Vec<JoinRule> = rest.into_iter().filter(|r| r.raid_type.is_empty()).collect();
+    if !guild.is_empty() {
+        return RuleSet { rules: guild, is_default: false };
+    }
+    RuleSet { rules: default_rules(guild_id), is_default: true }
+}
+
+pub async fn rules_for(pool: &PgPool, guild_id: u64, raid_type: &str) -> anyhow::Result<RuleSet> {
+    let all = repo::list_join_rules(pool, guild_id as i64).await?;
+    Ok(pick_scope(guild_id, all, raid_type))
+}
+
+fn holds(roles: &[RoleId], rule: &JoinRule) -> bool {
+    roles.iter().any(|r| r.get() as i64 == rule.role_id)
+}
+
+impl RuleSet {
+    fn with_access<'a>(&'a self, access: &'a [Access]) -> impl Iterator<Item = &'a JoinRule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |r| Access::from_key(&r.access).is_some_and(|a| access.contains(&a)))
+    }
+
+    /// Whether a member with `roles` may join, and the tag shown next to their name.
+    /// Without any main/reserve rule everyone may join as main; holding any reserve or
+    /// keep-reserve rule's role keeps the member on reserve; the first held main/reserve rule
+    /// gives the tag.
+    pub fn join_access(&self, roles: &[RoleId]) -> (JoinAccess, String) {
+        let mut gate = self.with_access(&[Access::Main, Access::Reserve]).peekable();
+        let tag = if gate.peek().is_none() {
+            String::new()
+        } else {
+            let Some(first) = gate.find(|r| holds(roles, r)) else {
+                return (JoinAccess::Denied, String::new());
+            };
+            first.tag_suffix.clone().unwrap_or_default()
+        };
+        if self.keeps_on_reserve(roles) {
+            (JoinAccess::ReserveOnly, tag)
+        } else {
+            (JoinAccess::Main, tag)
+        }
+    }
+
+    /// Holds a reserve or keep-reserve rule's role: never joins as main nor gets auto-promoted.
+    pub fn keeps_on_reserve(&self, roles: &[RoleId]) -> bool {
+        self.with_access(&[Access::Reserve, Access::KeepReserve]).any(|r| holds(roles, r))
+    }
+
+    /// Without any alts rule everyone may sign up alts.
+    pub fn may_add_alts(&self, roles: &[RoleId]) -> bool {
+        let mut alts = self.with_access(&[Access::Alts]).peekable();
+        alts.peek().is_none() || alts.any(|r| holds(roles, r))
+    }
+
+    /// "<@&a> or <@&b>" for rejection messages.
+    pub fn roles_for(&self, access: &[Access]) -> String {
+        let mut mentions: Vec<String> = Vec::new();
+        for r in self.with_access(access) {
+            let m = format!("<@&{}>", r.role_id);
+            if !mentions.contains(&m) {
+                mentions.push(m);
+            }
+        }
+        match mentions.len() {
+            0 => "none".to_string(),
+            1 => mentions.remove(0),
+            _ => {
+                let last = mentions.pop().unwrap_or_default();
+                format!("{} or {}", mentions.join(", "), last)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const C90: u64 = 90;
+    const C1TO89: u64 = 89;
+    const RESERVE: u64 = 7;
+    const ALT: u64 = 5;
+
+    fn rule(role: u64, access: Access, tag: Option<&str>, position: i32) -> JoinRule {
+        JoinRule {
+            id: 0,
+            guild_id: 1,
+            raid_type: String::new(),
+            role_id: role as i64,
+            access: access.key().to_string(),
+            tag_suffix: tag.map(str::to_string),
+            position,
+        }
+    }
+
+    /* Same shape as `default_rules` with every `/config role` set */
+    fn defaults() -> RuleSet {
+        RuleSet {
+            rules: vec![
+                rule(C90, Access::Main, None, 0),
+                rule(C1TO89, Access::Main, Some(" [-c90]"), 1),
+                rule(RESERVE, Access::KeepReserve, None, 2),
+                rule(ALT, Access::Alts, None, 3),
+            ],
+            is_default: true,
+        }
+    }
+
+    fn roles(ids: &[u64]) -> Vec<RoleId> {
+        ids.iter().map(|id| RoleId::new(*id)).collect()
+    }
+
+    #[test]
+    fn defaults_match_the_original_role_checks() {
+        let set = defaults();
+        let cases: [(&[u64], JoinAccess, &str); 8] = [
+            (&[], JoinAccess::Denied, ""),
+            (&[RESERVE], JoinAccess::Denied, ""),
+            (&[ALT], JoinAccess::Denied, ""),
+            (&[C90], JoinAccess::Main, ""),
+            (&[C1TO89], JoinAccess::Main, " [-c90]"),
+            (&[C90, C1TO89], JoinAccess::Main, ""),
+            (&[C90, RESERVE], JoinAccess::ReserveOnly, ""),
+            (&[C1TO89, RESERVE], JoinAccess::ReserveOnly, " [-c90]"),
+        ];
+        for (held, access, tag) in cases {
+            assert_eq!(set.join_access(&roles(held)), (access, tag.to_string()), "roles {:?}", held);
+        }
+        assert_eq!(set.roles_for(&[Access::Main, Access::Reserve]), "<@&90> or <@&89>");
+    }
+
+    #[test]
+    fn defaults_without_level_roles_let_everyone_in() {
+        let set = RuleSet { rules: vec![rule(RESERVE, Access::KeepReserve, None, 0)], is_default: true };
+        assert_eq!(set.join_access(&[]), (JoinAccess::Main, String::new()));
+        assert_eq!(set.join_access(&roles(&[RESERVE])), (JoinAccess::ReserveOnly, String::new()));
+    }
+
+    #[test]
+    fn reserve_rule_admits_on_its_own() {
+        let set = RuleSet {
+            rules: vec![rule(C90, Access::Main, None, 0), rule(RESERVE, Access::Reserve, Some(" [trial]"), 1)],
+            is_default: false,
+        };
+        assert_eq!(set.join_access(&roles(&[RESERVE])), (JoinAccess::ReserveOnly, " [trial]".to_string()));
+        assert_eq!(set.join_access(&[]).0, JoinAccess::Denied);
+        assert!(set.keeps_on_reserve(&roles(&[C90, RESERVE])));
+    }
+
+    #[test]
+    fn alts_need_the_alt_role_once_configured() {
+        let set = defaults();
+        assert!(set.may_add_alts(&roles(&[C90, ALT])));
+        assert!(!set.may_add_alts(&roles(&[C90])));
+        assert!(RuleSet { rules: vec![], is_default: true }.may_add_alts(&[]));
+    }
+}
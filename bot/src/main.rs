@@ -6,6 +6,11 @@ mod utils;
 mod tasks;
 mod redis_ext;
 mod queue;
+mod settings;
+mod join_rules;
+mod catalog;
+mod scheduled_events;
+mod composition;
 
 use crate::handlers::Handler;
 use dotenvy::dotenv;
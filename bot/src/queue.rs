@@ -3,6 +3,7 @@ use redis::{AsyncCommands, aio::MultiplexedConnection, Value};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::db::repo;
+use crate::join_rules;
 use serenity::all::{Context as DiscordContext, GuildId, UserId};
 
 const STREAM_KEY: &str = "raid_events";
@@ -11,15 +12,7 @@ const GROUP_NAME: &str = "raid_bot";
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum RaidEvent {
-    Join {
-        raid_id: Uuid,
-        guild_id: i64,
-        user_id: i64,
-        joined_as: String,
-        main_now: bool,
-        tag_suffix: String,
-        is_alt: bool,
-    },
+    Join(JoinEvent),
     LeaveAll {
         raid_id: Uuid,
         guild_id: i64,
@@ -42,6 +35,34 @@ pub enum RaidEvent {
         user_id: i64,
         sp: String,
     },
+    SetNote {
+        raid_id: Uuid,
+        guild_id: i64,
+        user_id: i64,
+        note: String,
+    },
+    SetTentative {
+        raid_id: Uuid,
+        guild_id: i64,
+        user_id: i64,
+        tentative: bool,
+    },
+}
+
+/* A signup (main or alt); the checks already ran where it was published */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinEvent {
+    pub raid_id: Uuid,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub class: String,
+    pub active_sp: String,
+    pub main_now: bool,
+    pub tag_suffix: String,
+    pub is_alt: bool,
+    // Quick join: SPs saved in the profile; None keeps what the row has
+    #[serde(default)]
+    pub extra_sps: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -155,9 +176,7 @@ pub async fn run_consumer(ctx: DiscordContext, pool: sqlx::PgPool, redis: redis:
                 let Some(payload_s) = payload else { continue; };
                 let evt: Result<RaidEvent, _> = serde_json::from_str(&payload_s);
                 let ack_res = match evt {
-                    Ok(RaidEvent::Join { raid_id, guild_id, user_id, joined_as, main_now, tag_suffix, is_alt }) => {
-                        handle_join(&ctx, &pool, raid_id, guild_id, user_id, joined_as, main_now, tag_suffix, is_alt).await
-                    }
+                    Ok(RaidEvent::Join(join)) => handle_join(&ctx, &pool, join).await,
                     Ok(RaidEvent::LeaveAll { raid_id, guild_id, user_id }) => {
                         handle_leave_all(&ctx, &pool, raid_id, guild_id, user_id).await
                     }
@@ -170,6 +189,12 @@ pub async fn run_consumer(ctx: DiscordContext, pool: sqlx::PgPool, redis: redis:
                     Ok(RaidEvent::ChangeSp { raid_id, guild_id, user_id, sp }) => {
                         handle_change_sp(&ctx, &pool, raid_id, guild_id, user_id, sp).await
                     }
+                    Ok(RaidEvent::SetNote { raid_id, guild_id, user_id, note }) => {
+                        handle_set_note(&ctx, &pool, raid_id, guild_id, user_id, note).await
+                    }
+                    Ok(RaidEvent::SetTentative { raid_id, guild_id, user_id, tentative }) => {
+                        handle_set_tentative(&ctx, &pool, raid_id, guild_id, user_id, tentative).await
+                    }
                     Err(e) => { eprintln!("queue: bad payload: {e:#}"); Ok(AckPayload { ok: false, removed_main: None, removed_alts: None }) }
                 };
 
@@ -188,6 +213,39 @@ pub async fn run_consumer(ctx: DiscordContext, pool: sqlx::PgPool, redis: redis:
     }
 }
 
+async fn handle_set_note(
+    ctx: &DiscordContext,
+    pool: &sqlx::PgPool,
+    raid_id: Uuid,
+    guild_id: i64,
+    user_id: i64,
+    note: String,
+) -> anyhow::Result<AckPayload> {
+    let updated = repo::set_participant_note(pool, raid_id, user_id, &note).await?;
+    crate::commands::raid::trigger_refresh(ctx, guild_id as u64).await;
+    Ok(AckPayload { ok: updated > 0, removed_main: None, removed_alts: None })
+}
+
+async fn handle_set_tentative(
+    ctx: &DiscordContext,
+    pool: &sqlx::PgPool,
+    raid_id: Uuid,
+    guild_id: i64,
+    user_id: i64,
+    tentative: bool,
+) -> anyhow::Result<AckPayload> {
+    let updated = repo::set_tentative(pool, raid_id, user_id, tentative).await?;
+
+    // A main stepping down frees a slot for someone else (not straight back to them); a confirmed
+    // player may take one
+    let raid = repo::get_raid(pool, raid_id).await?;
+    let stepped_down = if tentative { vec![user_id] } else { Vec::new() };
+    let _ = crate::tasks::promote_reserves_for_raid(&ctx.http, pool, &raid, &stepped_down).await;
+
+    let _ = crate::commands::raid::force_refresh_guild_raid_list(ctx, guild_id as u64).await;
+    Ok(AckPayload { ok: updated > 0, removed_main: None, removed_alts: None })
+}
+
 fn val_to_string(v: &Value) -> Option<String> {
     match v {
         Value::Data(bytes) => String::from_utf8(bytes.clone()).ok(),
@@ -209,19 +267,18 @@ fn val_to_string(v: &Value) -> Option<String> {
 async fn handle_join(
     ctx: &DiscordContext,
     pool: &sqlx::PgPool,
-    raid_id: Uuid,
-    guild_id: i64,
-    user_id: i64,
-    joined_as: String,
-    main_now: bool,
-    tag_suffix: String,
-    is_alt: bool,
+    join: JoinEvent,
 ) -> anyhow::Result<AckPayload> {
+    let JoinEvent { raid_id, guild_id, user_id, class, active_sp, main_now, tag_suffix, is_alt, extra_sps } = join;
+    let (class, active_sp) = (class.as_str(), active_sp.as_str());
     // Upsert main or insert alt
-    if is_alt {
-        let _ = repo::insert_alt(pool, raid_id, user_id, joined_as, main_now, tag_suffix).await?;
+    let row = if is_alt {
+        repo::insert_alt(pool, raid_id, user_id, class, active_sp, main_now, tag_suffix).await?
     } else {
-        let _ = repo::insert_or_replace_main(pool, raid_id, user_id, joined_as, main_now, tag_suffix).await?;
+        repo::insert_or_replace_main(pool, raid_id, user_id, class, active_sp, main_now, tag_suffix).await?
+    };
+    if let Some(sps) = extra_sps {
+        repo::set_extra_sps(pool, row.id, &sps).await?;
     }
 
     // After join, run promotion
@@ -230,30 +287,26 @@ async fn handle_join(
     let mut exclude_ids: Vec<i64> = Vec::new();
     let mut priority_user_ids: Vec<i64> = Vec::new();
     let gid = GuildId::new(guild_id as u64);
-    if let Ok(roles_map) = gid.roles(&ctx.http).await {
-        let reserve_role_name = std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string());
-        let parts_for_check = repo::list_participants(pool, raid_id).await?;
-        // Priority role IDs configured on the raid (array of BIGINT)
-        let pr_ids: Vec<i64> = raid
-            .priority_role_id
-            .as_ref()
-            .map(|v| v.clone())
-            .unwrap_or_default();
-        let pr_set: std::collections::HashSet<u64> = pr_ids.iter().map(|x| *x as u64).collect();
+    let rules = join_rules::rules_for(pool, guild_id as u64, &raid.raid_name).await?;
+    let parts_for_check = repo::list_participants(pool, raid_id).await?;
+    // Priority role IDs configured on the raid (array of BIGINT)
+    let pr_ids: Vec<i64> = raid
+        .priority_role_id
+        .as_ref()
+        .map(|v| v.clone())
+        .unwrap_or_default();
+    let pr_set: std::collections::HashSet<u64> = pr_ids.iter().map(|x| *x as u64).collect();
 
-        for p in &parts_for_check {
-            if let Ok(member) = gid.member(&ctx.http, UserId::new(p.user_id as u64)).await {
-                // exclude: users with reserve role name
-                let has_reserve = member.roles.iter().any(|rid| {
-                    roles_map.get(rid).map_or(false, |r| r.name.eq_ignore_ascii_case(&reserve_role_name))
-                });
-                if has_reserve { exclude_ids.push(p.user_id); }
+    for p in &parts_for_check {
+        if let Ok(member) = gid.member(&ctx.http, UserId::new(p.user_id as u64)).await {
+            // exclude: users a join rule keeps on reserve
+            let has_reserve = rules.keeps_on_reserve(&member.roles);
+            if has_reserve { exclude_ids.push(p.user_id); }
 
-                // priority: users that have any of the configured priority role IDs
-                if !pr_set.is_empty() {
-                    let has_priority = member.roles.iter().any(|rid| pr_set.contains(&rid.get()));
-                    if has_priority { priority_user_ids.push(p.user_id); }
-                }
+            // priority: users that have any of the configured priority role IDs
+            if !pr_set.is_empty() {
+                let has_priority = member.roles.iter().any(|rid| pr_set.contains(&rid.get()));
+                if has_priority { priority_user_ids.push(p.user_id); }
             }
         }
     }
@@ -289,47 +342,7 @@ async fn handle_leave_all(
 
     // Promote immediately after a leave.
     let raid = repo::get_raid(pool, raid_id).await?;
-    let now = chrono::Utc::now();
-    let mut exclude_ids: Vec<i64> = Vec::new();
-    let mut priority_user_ids: Vec<i64> = Vec::new();
-    let gid = GuildId::new(guild_id as u64);
-    if let Ok(roles_map) = gid.roles(&ctx.http).await {
-        let reserve_role_name = std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string());
-        let parts_for_check = repo::list_participants(pool, raid_id).await?;
-        // Priority role IDs configured on the raid (array of BIGINT)
-        let pr_ids: Vec<i64> = raid
-            .priority_role_id
-            .as_ref()
-            .map(|v| v.clone())
-            .unwrap_or_default();
-        let pr_set: std::collections::HashSet<u64> = pr_ids.iter().map(|x| *x as u64).collect();
-
-        for p in &parts_for_check {
-            if let Ok(member) = gid.member(&ctx.http, UserId::new(p.user_id as u64)).await {
-                let has_reserve = member.roles.iter().any(|rid| {
-                    roles_map.get(rid).map_or(false, |r| r.name.eq_ignore_ascii_case(&reserve_role_name))
-                });
-                if has_reserve { exclude_ids.push(p.user_id); }
-
-                if !pr_set.is_empty() {
-                    let has_priority = member.roles.iter().any(|rid| pr_set.contains(&rid.get()));
-                    if has_priority { priority_user_ids.push(p.user_id); }
-                }
-            }
-        }
-    }
-
-    // During active priority window (or indefinite when is_priority=true and no until): promote ONLY users with priority roles.
-    let active_priority = raid.is_priority && raid.priority_until.map(|u| now < u).unwrap_or(true);
-    if active_priority {
-        let _ = repo::promote_reserves_with_priority_excluding(
-            pool, raid_id, raid.max_players, raid.max_alts, &priority_user_ids, &exclude_ids
-        ).await;
-    } else {
-        let _ = repo::promote_reserves_with_alt_limits_excluding(
-            pool, raid_id, raid.max_players, raid.max_alts, &exclude_ids
-        ).await;
-    }
+    let _ = crate::tasks::promote_reserves_for_raid(&ctx.http, pool, &raid, &[]).await;
 
     let _ = crate::commands::raid::force_refresh_guild_raid_list(ctx, guild_id as u64).await;
     Ok(AckPayload { ok: true, removed_main: Some(removed_main), removed_alts: Some(removed_alts) })
@@ -372,10 +385,8 @@ async fn handle_change_sp(
     user_id: i64,
     sp: String,
 ) -> anyhow::Result<AckPayload> {
-    // Read current main row to get class part
-    if let Some(main) = repo::get_user_main_row(pool, raid_id, user_id).await? {
-        let class_part = main.joined_as.split('/').next().map(|s| s.trim().to_string()).unwrap_or_else(|| "MSW".to_string());
-        repo::set_active_sp(pool, raid_id, user_id, &class_part, &sp).await?;
+    if repo::get_user_main_row(pool, raid_id, user_id).await?.is_some() {
+        repo::set_active_sp(pool, raid_id, user_id, &sp).await?;
         let _ = crate::commands::raid::force_refresh_guild_raid_list(ctx, guild_id as u64).await;
         crate::commands::raid::trigger_refresh(ctx, guild_id as u64).await;
         Ok(AckPayload { ok: true, removed_main: None, removed_alts: None })
@@ -383,3 +394,20 @@ async fn handle_change_sp(
         Ok(AckPayload { ok: false, removed_main: None, removed_alts: None })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_keeps_its_flat_wire_format() {
+        // Shape published before JoinEvent existed; such entries may still sit in the stream
+        let json = r#"{"kind":"join","raid_id":"00000000-0000-0000-0000-000000000001","guild_id":1,"user_id":2,
+            "class":"MSW","active_sp":"SP1","main_now":true,"tag_suffix":"","is_alt":false}"#;
+        let Ok(RaidEvent::Join(join)) = serde_json::from_str::<RaidEvent>(json) else { panic!("not a join") };
+        assert_eq!((join.user_id, join.class.as_str(), join.extra_sps.is_none()), (2, "MSW", true));
+        let back = serde_json::to_value(RaidEvent::Join(join)).unwrap();
+        assert_eq!(back["kind"], "join");
+        assert_eq!(back["active_sp"], "SP1");
+    }
+}
@@ -14,7 +14,8 @@ struct GuildListRecord {
 
 fn key_for(guild_id: u64) -> String { format!("{}{}", KEY_PREFIX, guild_id) }
 
-fn reminder_15m_key(raid_id: Uuid) -> String { format!("{}{}", REMINDER_15M_PREFIX, raid_id) }
+// Keyed by start time too, so a raid moved by /raid_edit gets a fresh reminder
+fn reminder_15m_key(raid_id: Uuid, starts_at: i64) -> String { format!("{}{}:{}", REMINDER_15M_PREFIX, raid_id, starts_at) }
 
 pub async fn get_guild_list(client: &redis::Client, guild_id: u64) -> anyhow::Result<Option<(u64, Vec<u64>)>> {
     let mut conn = client
@@ -59,12 +60,12 @@ pub async fn del_guild_list(client: &redis::Client, guild_id: u64) -> anyhow::Re
     Ok(())
 }
 
-pub async fn claim_raid_reminder_15m(client: &redis::Client, raid_id: Uuid) -> anyhow::Result<bool> {
+pub async fn claim_raid_reminder_15m(client: &redis::Client, raid_id: Uuid, starts_at: i64) -> anyhow::Result<bool> {
     let mut conn = client
         .get_multiplexed_async_connection()
         .await
         .context("redis connect")?;
-    let key = reminder_15m_key(raid_id);
+    let key = reminder_15m_key(raid_id, starts_at);
     let ttl_seconds = 60 * 60 * 48;
     let res: Option<String> = redis::cmd("SET")
         .arg(&key)
@@ -0,0 +1,128 @@
+use serenity::all::{
+    CreateScheduledEvent, EditScheduledEvent, GuildId, ScheduledEventStatus, ScheduledEventType, UserId,
+};
+use serenity::http::Http;
+use sqlx::PgPool;
+
+use crate::db::models::Raid;
+use crate::db::repo;
+
+/* Guild Scheduled Events mirroring raids in the Events tab. Failures are logged and never block the raid itself. */
+
+fn is_not_found(e: &serenity::Error) -> bool {
+    matches!(e, serenity::Error::Http(serenity::http::HttpError::UnsuccessfulRequest(r)) if r.status_code.as_u16() == 404)
+}
+
+async fn event_description(http: &Http, raid: &Raid) -> String {
+    let gid = GuildId::new(raid.guild_id as u64);
+    let owner = match gid.member(http, UserId::new(raid.owner_id as u64)).await {
+        Ok(m) => m.display_name().to_string(),
+        Err(_) => format!("user {}", raid.owner_id),
+    };
+    let mut text = format!("Organiser: {}\nSign up in the raid channel.", owner);
+    if !raid.description.trim().is_empty() {
+        text = format!("{}\n\n{}", raid.description.trim(), text);
+    }
+    text.chars().take(1000).collect() // Discord's description limit
+}
+
+fn event_location(raid: &Raid) -> String {
+    format!("https://discord.com/channels/{}/{}", raid.guild_id, raid.chat_channel_id())
+}
+
+/// Creates the raid's event, or brings an existing one in line with the raid (time, name, owner).
+/// Returns the event id the raid ends up with.
+pub async fn sync_raid_event(http: &Http, pool: &PgPool, raid: &Raid) -> Option<i64> {
+    match try_sync(http, pool, raid).await {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("scheduled event sync for raid {} failed: {e:#}", raid.id);
+            raid.event_id
+        }
+    }
+}
+
+async fn try_sync(http: &Http, pool: &PgPool, raid: &Raid) -> anyhow::Result<Option<i64>> {
+    let gid = GuildId::new(raid.guild_id as u64);
+    let start = raid.scheduled_for;
+    let end = start + chrono::Duration::minutes(raid.duration_minutes as i64);
+
+    if let Some(event_id) = raid.event_id {
+        match gid.scheduled_event(http, event_id as u64, false).await {
+            Ok(ev) if matches!(ev.status, ScheduledEventStatus::Completed | ScheduledEventStatus::Canceled) => {
+                return Ok(Some(event_id));
+            }
+            Ok(ev) => {
+                let mut edit = EditScheduledEvent::new()
+                    .name(raid.raid_name.clone())
+                    .description(event_description(http, raid).await)
+                    .location(event_location(raid))
+                    .end_time(end);
+                // Discord refuses to move the start of an event that is already running
+                if ev.status == ScheduledEventStatus::Scheduled {
+                    edit = edit.start_time(start);
+                }
+                gid.edit_scheduled_event(http, event_id as u64, edit).await?;
+                return Ok(Some(event_id));
+            }
+            // Removed by hand on Discord: make a new one below
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    // Events can only be created with a start in the future
+    if start <= chrono::Utc::now() {
+        if raid.event_id.is_some() {
+            repo::set_raid_event_id(pool, raid.id, None).await?;
+        }
+        return Ok(None);
+    }
+
+    let ev = gid.create_scheduled_event(
+        http,
+        CreateScheduledEvent::new(ScheduledEventType::External, raid.raid_name.clone(), start)
+            .end_time(end)
+            .location(event_location(raid))
+            .description(event_description(http, raid).await),
+    ).await?;
+    let event_id = ev.id.get() as i64;
+    repo::set_raid_event_id(pool, raid.id, Some(event_id)).await?;
+    Ok(Some(event_id))
+}
+
+/// Cancelled raid: the event goes away.
+pub async fn delete_raid_event(http: &Http, pool: &PgPool, raid: &Raid) {
+    let Some(event_id) = raid.event_id else { return; };
+    let gid = GuildId::new(raid.guild_id as u64);
+    match gid.delete_scheduled_event(http, event_id as u64).await {
+        Ok(()) => {}
+        Err(e) if is_not_found(&e) => {}
+        Err(e) => {
+            eprintln!("deleting scheduled event for raid {} failed: {e:#}", raid.id);
+            return;
+        }
+    }
+    let _ = repo::set_raid_event_id(pool, raid.id, None).await;
+}
+
+/// Finished raid: mark the event completed. Discord only allows Scheduled → Active → Completed.
+pub async fn complete_raid_event(http: &Http, raid: &Raid) {
+    let Some(event_id) = raid.event_id else { return; };
+    let gid = GuildId::new(raid.guild_id as u64);
+    let Ok(ev) = gid.scheduled_event(http, event_id as u64, false).await else { return; };
+
+    if ev.status == ScheduledEventStatus::Scheduled {
+        let started = EditScheduledEvent::new().status(ScheduledEventStatus::Active);
+        if let Err(e) = gid.edit_scheduled_event(http, event_id as u64, started).await {
+            eprintln!("starting scheduled event for raid {} failed: {e:#}", raid.id);
+            return;
+        }
+    }
+    if matches!(ev.status, ScheduledEventStatus::Scheduled | ScheduledEventStatus::Active) {
+        let done = EditScheduledEvent::new().status(ScheduledEventStatus::Completed);
+        if let Err(e) = gid.edit_scheduled_event(http, event_id as u64, done).await {
+            eprintln!("completing scheduled event for raid {} failed: {e:#}", raid.id);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use chrono_tz::Tz;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::all::{GuildId, RoleId};
+use serenity::http::Http;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::db::models::GuildSettings;
+use crate::db::repo;
+
+/* Used for guilds that never ran /config (and for bad rows) */
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Europe::Warsaw;
+pub const DEFAULT_CHANNEL_NAME_TEMPLATE: &str = "{emoji}-{slug}-{date} at {time}";
+
+/* guild_id -> settings row. Loaded once on ready, kept in sync by the setters below. */
+static GUILD_SETTINGS: Lazy<DashMap<u64, GuildSettings>> = Lazy::new(DashMap::new);
+
+/* Roles the bot acts on; each guild maps them to its own role IDs with `/config role` */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GuildRole {
+    Organiser,       // create templates, hand over raids, /role_add
+    Admin,           // /config, /raid_type, moving the raid list
+    Reserve,         // always joins as reserve, never auto-promoted
+    AltAllow,        // may sign up alts
+    C90,             // may join
+    C1to89,          // may join, tagged [-c90]
+    DefaultPriority, // priority role when /raid gets no prioritylist
+}
+
+impl GuildRole {
+    pub const ALL: [GuildRole; 7] = [
+        GuildRole::Organiser,
+        GuildRole::Admin,
+        GuildRole::Reserve,
+        GuildRole::AltAllow,
+        GuildRole::C90,
+        GuildRole::C1to89,
+        GuildRole::DefaultPriority,
+    ];
+
+    /* Value used in command choices */
+    pub fn key(self) -> &'static str {
+        match self {
+            GuildRole::Organiser => "organiser",
+            GuildRole::Admin => "admin",
+            GuildRole::Reserve => "reserve",
+            GuildRole::AltAllow => "alt_allow",
+            GuildRole::C90 => "c90",
+            GuildRole::C1to89 => "c1_89",
+            GuildRole::DefaultPriority => "priority",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<GuildRole> {
+        GuildRole::ALL.into_iter().find(|r| r.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GuildRole::Organiser => "Raid organiser",
+            GuildRole::Admin => "Bot admin",
+            GuildRole::Reserve => "Reserve",
+            GuildRole::AltAllow => "Alt allowed",
+            GuildRole::C90 => "c90",
+            GuildRole::C1to89 => "c1-89",
+            GuildRole::DefaultPriority => "Default priority",
+        }
+    }
+
+    /* Role names the bot used before role settings existed */
+    fn legacy_name(self) -> String {
+        match self {
+            GuildRole::Organiser => "raid_organiser".to_string(),
+            GuildRole::Admin => "Zarządca_permisje".to_string(),
+            GuildRole::Reserve => std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string()),
+            GuildRole::AltAllow => std::env::var("ALT_ALLOW_ROLE_NAME").unwrap_or_else(|_| "Alt_allow".to_string()),
+            GuildRole::C90 => "c90".to_string(),
+            GuildRole::C1to89 => "c1-89".to_string(),
+            GuildRole::DefaultPriority => "Maraton".to_string(),
+        }
+    }
+
+    pub fn get(self, s: &GuildSettings) -> Option<i64> {
+        match self {
+            GuildRole::Organiser => s.organiser_role_id,
+            GuildRole::Admin => s.admin_role_id,
+            GuildRole::Reserve => s.reserve_role_id,
+            GuildRole::AltAllow => s.alt_allow_role_id,
+            GuildRole::C90 => s.c90_role_id,
+            GuildRole::C1to89 => s.c1_89_role_id,
+            GuildRole::DefaultPriority => s.default_priority_role_id,
+        }
+    }
+
+    pub fn set(self, s: &mut GuildSettings, role_id: Option<i64>) {
+        let slot = match self {
+            GuildRole::Organiser => &mut s.organiser_role_id,
+            GuildRole::Admin => &mut s.admin_role_id,
+            GuildRole::Reserve => &mut s.reserve_role_id,
+            GuildRole::AltAllow => &mut s.alt_allow_role_id,
+            GuildRole::C90 => &mut s.c90_role_id,
+            GuildRole::C1to89 => &mut s.c1_89_role_id,
+            GuildRole::DefaultPriority => &mut s.default_priority_role_id,
+        };
+        *slot = role_id;
+    }
+}
+
+/* How reserves are ordered when a main slot frees up; raids use the guild's unless `/raid_edit` sets one */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PromotionPolicy {
+    JoinOrder,   // first come, first served
+    Reliability, // recent no-shows rank lower, long bench streaks higher; join time breaks ties
+}
+
+impl PromotionPolicy {
+    pub const ALL: [PromotionPolicy; 2] = [PromotionPolicy::JoinOrder, PromotionPolicy::Reliability];
+
+    /* Value stored in the DB and used in command choices */
+    pub fn key(self) -> &'static str {
+        match self {
+            PromotionPolicy::JoinOrder => "join_order",
+            PromotionPolicy::Reliability => "reliability",
+        }
+    }
+
+    /* Unknown values fall back to join order */
+    pub fn from_key(key: &str) -> PromotionPolicy {
+        PromotionPolicy::ALL.into_iter().find(|p| p.key() == key).unwrap_or(PromotionPolicy::JoinOrder)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PromotionPolicy::JoinOrder => "Join order",
+            PromotionPolicy::Reliability => "Reliability",
+        }
+    }
+}
+
+pub async fn load_all(pool: &PgPool) -> anyhow::Result<()> {
+    for row in repo::list_guild_settings(pool).await? {
+        GUILD_SETTINGS.insert(row.guild_id as u64, row);
+    }
+    Ok(())
+}
+
+pub fn get(guild_id: u64) -> GuildSettings {
+    GUILD_SETTINGS
+        .get(&guild_id)
+        .map(|s| s.value().clone())
+        .unwrap_or_else(|| GuildSettings {
+            guild_id: guild_id as i64,
+            timezone: DEFAULT_TIMEZONE.name().to_string(),
+            weekday_categories: Json(HashMap::new()),
+            channel_name_template: None,
+            fallback_category_id: None,
+            max_channels_per_category: None,
+            raid_thread_channel_id: None,
+            organiser_role_id: None,
+            admin_role_id: None,
+            reserve_role_id: None,
+            alt_allow_role_id: None,
+            c90_role_id: None,
+            c1_89_role_id: None,
+            default_priority_role_id: None,
+            promotion_policy: PromotionPolicy::JoinOrder.key().to_string(),
+        })
+}
+
+/* Timezone for parsing and displaying raid times in this guild */
+pub fn guild_tz(guild_id: u64) -> Tz {
+    GUILD_SETTINGS
+        .get(&guild_id)
+        .and_then(|s| s.timezone.parse::<Tz>().ok())
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/* Configured role ID of this kind, if any */
+pub fn role_id(guild_id: u64, role: GuildRole) -> Option<RoleId> {
+    GUILD_SETTINGS
+        .get(&guild_id)
+        .and_then(|s| role.get(&s))
+        .map(|id| RoleId::new(id as u64))
+}
+
+/* "<@&id>" for messages, or a hint that the role still needs configuring */
+pub fn role_mention(guild_id: u64, role: GuildRole) -> String {
+    match role_id(guild_id, role) {
+        Some(id) => format!("<@&{}>", id),
+        None => format!("`{}` (not configured, see `/config role`)", role.label()),
+    }
+}
+
+pub async fn save(pool: &PgPool, settings: &GuildSettings) -> anyhow::Result<GuildSettings> {
+    let row = repo::upsert_guild_settings(pool, settings).await?;
+    GUILD_SETTINGS.insert(row.guild_id as u64, row.clone());
+    Ok(row)
+}
+
+pub async fn set_timezone(pool: &PgPool, guild_id: u64, tz: Tz) -> anyhow::Result<GuildSettings> {
+    let mut s = get(guild_id);
+    s.timezone = tz.name().to_string();
+    save(pool, &s).await
+}
+
+/// Guilds set up before role settings existed relied on fixed role names. If none of their roles
+/// are configured yet, pick up the roles carrying those names once, so nothing changes for them.
+pub async fn adopt_legacy_roles(http: &Http, pool: &PgPool, guild_id: GuildId) -> anyhow::Result<()> {
+    let mut s = get(guild_id.get());
+    if GuildRole::ALL.iter().any(|r| r.get(&s).is_some()) {
+        return Ok(());
+    }
+    let roles = guild_id.roles(http).await?;
+    let mut found = false;
+    for role in GuildRole::ALL {
+        let name = role.legacy_name();
+        if let Some(id) = roles.values().find(|r| r.name.eq_ignore_ascii_case(&name)).map(|r| r.id) {
+            role.set(&mut s, Some(id.get() as i64));
+            found = true;
+        }
+    }
+    if found {
+        save(pool, &s).await?;
+    }
+    Ok(())
+}
@@ -1,14 +1,78 @@
 use std::sync::Arc;
 use tokio::time::{sleep_until, Duration, Instant};
 use std::collections::HashMap;
-use chrono_tz::Europe::Warsaw;
 use serenity::http::Http;
-use serenity::all::ChannelId;
+use serenity::all::{ChannelId, Context, EditMessage, EditThread, GuildId, UserId};
 use sqlx::PgPool;
 use uuid::Uuid;
 use chrono::Duration as CDuration;
 
+use crate::db::models::{Raid, RaidTemplate};
 use crate::db::repo;
+use crate::join_rules;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/* Per-raid timers. Arming one again replaces (aborts) the previous, so edits can reschedule. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RaidTimer {
+    Priority,
+    Reminder,
+    Attendance,
+    AutoDelete,
+}
+static RAID_TIMERS: Lazy<DashMap<(Uuid, RaidTimer), AbortHandle>> = Lazy::new(DashMap::new);
+
+fn track_raid_timer(raid_id: Uuid, kind: RaidTimer, handle: JoinHandle<()>) {
+    if let Some(old) = RAID_TIMERS.insert((raid_id, kind), handle.abort_handle()) {
+        old.abort();
+    }
+}
+
+pub fn cancel_raid_timer(raid_id: Uuid, kind: RaidTimer) {
+    if let Some((_, h)) = RAID_TIMERS.remove(&(raid_id, kind)) {
+        h.abort();
+    }
+}
+
+/* One pending run per weekly template; re-arming aborts the previous timer (restore runs on every ready) */
+static TEMPLATE_TIMERS: Lazy<DashMap<Uuid, AbortHandle>> = Lazy::new(DashMap::new);
+
+fn track_template_timer(template_id: Uuid, handle: JoinHandle<()>) {
+    if let Some(old) = TEMPLATE_TIMERS.insert(template_id, handle.abort_handle()) {
+        old.abort();
+    }
+}
+
+pub fn cancel_template_timer(template_id: Uuid) {
+    if let Some((_, h)) = TEMPLATE_TIMERS.remove(&template_id) {
+        h.abort();
+    }
+}
+
+/// (Re)arms priority promotion, the 15-minute reminder and auto-delete from the stored raid row.
+pub fn arm_raid_timers(http: Arc<Http>, pool: PgPool, redis: redis::Client, raid: &Raid) {
+    match raid.priority_until {
+        Some(until) => schedule_priority_promotion(
+            http.clone(), pool.clone(), raid.id, raid.guild_id, raid.channel_id, raid.message_id, until
+        ),
+        None => cancel_raid_timer(raid.id, RaidTimer::Priority),
+    }
+
+    let now = chrono::Utc::now();
+    if now < raid.scheduled_for {
+        // Moved to start within 15 minutes → remind right away
+        let reminder_at = (raid.scheduled_for - CDuration::minutes(15)).max(now);
+        schedule_raid_15m_reminder(http.clone(), pool.clone(), redis, raid.id, reminder_at);
+    } else {
+        cancel_raid_timer(raid.id, RaidTimer::Reminder);
+    }
+
+    let ends_at = raid.scheduled_for + CDuration::minutes(raid.duration_minutes as i64);
+    schedule_attendance_panel(http.clone(), pool.clone(), raid.id, ends_at);
+    schedule_auto_delete(http, pool, raid.id, auto_delete_at(raid.scheduled_for, raid.duration_minutes));
+}
 
 pub fn schedule_priority_promotion(
     http: Arc<Http>,
@@ -21,28 +85,75 @@ pub fn schedule_priority_promotion(
 ) {
     let wait = (run_at - chrono::Utc::now()).to_std().unwrap_or(Duration::from_secs(0));
     let when = Instant::now() + wait;
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         sleep_until(when).await;
         let _ = promote_and_refresh(&http, &pool, raid_id, guild_id, channel_id, message_id).await;
     });
+    track_raid_timer(raid_id, RaidTimer::Priority, handle);
 }
 
 
+/* Raid channels are removed 20 minutes after the raid's stored end time */
+pub fn auto_delete_at(scheduled_for: chrono::DateTime<chrono::Utc>, duration_minutes: i32) -> chrono::DateTime<chrono::Utc> {
+    scheduled_for + CDuration::minutes(duration_minutes as i64) + CDuration::minutes(20)
+}
+
+/// At the raid's end the owner gets the attendance panel, before auto-delete closes the raid.
+pub fn schedule_attendance_panel(
+    http: Arc<Http>,
+    pool: PgPool,
+    raid_id: Uuid,
+    run_at: chrono::DateTime<chrono::Utc>,
+) {
+    let wait = (run_at - chrono::Utc::now()).to_std().unwrap_or(Duration::from_secs(0));
+    let when = Instant::now() + wait;
+    let handle = tokio::spawn(async move {
+        sleep_until(when).await;
+        if let Err(e) = crate::commands::attendance::send_panel(&http, &pool, raid_id).await {
+            eprintln!("attendance panel for raid {raid_id} failed: {e:#}");
+        }
+    });
+    track_raid_timer(raid_id, RaidTimer::Attendance, handle);
+}
+
 pub fn schedule_auto_delete(
     http: Arc<Http>,
     pool: PgPool,                 // <— NOWE
-    _raid_id: Uuid,                // (zostaw jeśli używasz gdzie indziej)
-    channel_id: i64,
+    raid_id: Uuid,
     run_at: chrono::DateTime<chrono::Utc>,
 ) {
     let wait = (run_at - chrono::Utc::now()).to_std().unwrap_or(Duration::from_secs(0));
     let when = Instant::now() + wait;
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         sleep_until(when).await;
-        if ChannelId::new(channel_id as u64).delete(&http).await.is_ok() {
-            let _ = crate::db::repo::inactive_raid_after_delete_channel(&pool, channel_id).await;
+        if let Err(e) = close_raid(&http, &pool, raid_id).await {
+            eprintln!("auto-delete of raid {raid_id} failed: {e:#}");
         }
     });
+    track_raid_timer(raid_id, RaidTimer::AutoDelete, handle);
+}
+
+/// Closes a finished raid. Channel mode deletes the raid's channel; thread mode archives and
+/// locks the thread and strips the buttons from the embed (the raids channel itself is shared).
+pub async fn close_raid(http: &Http, pool: &PgPool, raid_id: Uuid) -> anyhow::Result<()> {
+    let raid = repo::get_raid(pool, raid_id).await?;
+    crate::scheduled_events::complete_raid_event(http, &raid).await;
+    match raid.thread_id {
+        Some(thread_id) => {
+            ChannelId::new(thread_id as u64)
+                .edit_thread(http, EditThread::new().archived(true).locked(true))
+                .await?;
+            let _ = ChannelId::new(raid.channel_id as u64)
+                .edit_message(http, raid.message_id as u64, EditMessage::new().components(vec![]))
+                .await;
+            repo::deactivate_raid(pool, raid_id).await?;
+        }
+        None => {
+            ChannelId::new(raid.channel_id as u64).delete(http).await?;
+            repo::inactive_raid_after_delete_channel(pool, raid.channel_id).await?;
+        }
+    }
+    Ok(())
 }
 
 async fn promote_and_refresh(
@@ -53,7 +164,6 @@ async fn promote_and_refresh(
     channel_id: i64,
     message_id: i64,
 ) -> anyhow::Result<()> {
-    use serenity::all::{GuildId, UserId};
     let raid = repo::get_raid(pool, raid_id).await?;
 
     // Run only AFTER the priority window ended
@@ -63,20 +173,16 @@ async fn promote_and_refresh(
         }
     }
 
-    // Exclude users who currently have the RESERVE_ROLE_NAME role
+    // Exclude users a join rule keeps on reserve
     let mut exclude_ids: Vec<i64> = Vec::new();
     let gid = GuildId::new(guild_id as u64);
-    if let Ok(roles_map) = gid.roles(http).await {
-        let reserve_role_name = std::env::var("RESERVE_ROLE_NAME").unwrap_or_else(|_| "reserve".to_string());
-        let parts_for_check = repo::list_participants(pool, raid_id).await?;
-        for p in &parts_for_check {
-            if let Ok(member) = gid.member(http, UserId::new(p.user_id as u64)).await {
-                let has_reserve = member.roles.iter().any(|rid| {
-                    roles_map.get(rid).map_or(false, |r| r.name.eq_ignore_ascii_case(&reserve_role_name))
-                });
-                if has_reserve {
-                    exclude_ids.push(p.user_id);
-                }
+    let rules = join_rules::rules_for(pool, gid.get(), &raid.raid_name).await?;
+    let parts_for_check = repo::list_participants(pool, raid_id).await?;
+    for p in &parts_for_check {
+        if let Ok(member) = gid.member(http, UserId::new(p.user_id as u64)).await {
+            let has_reserve = rules.keeps_on_reserve(&member.roles);
+            if has_reserve {
+                exclude_ids.push(p.user_id);
             }
         }
     }
@@ -92,11 +198,93 @@ async fn promote_and_refresh(
     let embed = crate::ui::embeds::render_raid_embed_plain(&raid, &parts);
 
     ChannelId::new(channel_id as u64)
-        .edit_message(http, message_id as u64, serenity::builder::EditMessage::new().embed(embed))
+        .edit_message(http, message_id as u64, EditMessage::new().embed(embed))
         .await?;
     Ok(())
 }
 
+/// Fills free main slots the way a leave does: members a join rule keeps on reserve are never auto-promoted,
+/// and during an active priority window only holders of the raid's priority roles move up.
+/// `skip` users stay where they are this time (e.g. a main who just stepped down).
+pub async fn promote_reserves_for_raid(http: &Http, pool: &PgPool, raid: &Raid, skip: &[i64]) -> anyhow::Result<()> {
+    let now = chrono::Utc::now();
+    let mut exclude_ids: Vec<i64> = skip.to_vec();
+    let mut priority_user_ids: Vec<i64> = Vec::new();
+    let gid = GuildId::new(raid.guild_id as u64);
+    let rules = join_rules::rules_for(pool, gid.get(), &raid.raid_name).await?;
+    let parts_for_check = repo::list_participants(pool, raid.id).await?;
+    // Priority role IDs configured on the raid (array of BIGINT)
+    let pr_set: std::collections::HashSet<u64> = raid
+        .priority_role_id
+        .iter()
+        .flatten()
+        .map(|x| *x as u64)
+        .collect();
+
+    for p in &parts_for_check {
+        if let Ok(member) = gid.member(http, UserId::new(p.user_id as u64)).await {
+            let has_reserve = rules.keeps_on_reserve(&member.roles);
+            if has_reserve { exclude_ids.push(p.user_id); }
+
+            if !pr_set.is_empty() {
+                let has_priority = member.roles.iter().any(|rid| pr_set.contains(&rid.get()));
+                if has_priority { priority_user_ids.push(p.user_id); }
+            }
+        }
+    }
+
+    // During active priority window (or indefinite when is_priority=true and no until): promote ONLY users with priority roles.
+    let active_priority = raid.is_priority && raid.priority_until.map(|u| now < u).unwrap_or(true);
+    if active_priority {
+        repo::promote_reserves_with_priority_excluding(
+            pool, raid.id, raid.max_players, raid.max_alts, &priority_user_ids, &exclude_ids
+        ).await
+    } else {
+        repo::promote_reserves_with_alt_limits_excluding(
+            pool, raid.id, raid.max_players, raid.max_alts, &exclude_ids
+        ).await
+    }
+}
+
+/// Brings the mains/reserves split in line with the raid's current `max_players` / `max_alts`.
+/// Extra mains are demoted newest-first (alts over the alt cap go first); free slots are filled
+/// through `promote_reserves_for_raid`. Everyone whose status changed gets a DM.
+pub async fn rebalance_roster(http: &Http, pool: &PgPool, raid: &Raid) -> anyhow::Result<()> {
+    let before: HashMap<Uuid, bool> = repo::list_participants(pool, raid.id).await?
+        .into_iter()
+        .map(|p| (p.id, p.is_main))
+        .collect();
+
+    let alt_mains = repo::count_alt_mains(pool, raid.id).await?;
+    repo::demote_latest_mains(pool, raid.id, alt_mains - raid.max_alts as i64, true).await?;
+    let mains = repo::count_mains(pool, raid.id).await?;
+    if mains > raid.max_players as i64 {
+        repo::demote_latest_mains(pool, raid.id, mains - raid.max_players as i64, false).await?;
+    } else {
+        promote_reserves_for_raid(http, pool, raid, &[]).await?;
+    }
+
+    let when_local = raid.scheduled_for
+        .with_timezone(&crate::settings::guild_tz(raid.guild_id as u64))
+        .format("%Y-%m-%d %H:%M %Z");
+    for p in repo::list_participants(pool, raid.id).await? {
+        let Some(was_main) = before.get(&p.id) else { continue; };
+        let msg = match (*was_main, p.is_main) {
+            (false, true) => format!(
+                "✅ You were **promoted to MAIN** for **{}** on {} (main slots: {}).\nChannel: <#{}>",
+                raid.raid_name, when_local, raid.max_players, raid.chat_channel_id()
+            ),
+            (true, false) => format!(
+                "↩️ You were **moved to RESERVE** for **{}** on {} (main slots: {}).\nChannel: <#{}>",
+                raid.raid_name, when_local, raid.max_players, raid.chat_channel_id()
+            ),
+            _ => continue,
+        };
+        crate::utils::dm_user(http, p.user_id as u64, msg).await;
+    }
+    Ok(())
+}
+
 /// Spawn one timer: at (scheduled_for - 15m) DM **current** participants (mains + reserves)
 pub fn schedule_raid_15m_reminder(
     http: Arc<Http>,
@@ -108,7 +296,7 @@ pub fn schedule_raid_15m_reminder(
     let wait = (scheduled_for_utc - chrono::Utc::now()).to_std().unwrap_or(Duration::from_secs(0));
     let when_inst = Instant::now() + wait;
 
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         sleep_until(when_inst).await;
 
         // Resolve raid + participants at send time (so it's always up-to-date)
@@ -121,7 +309,7 @@ pub fn schedule_raid_15m_reminder(
             return;
         }
 
-        let claimed = match crate::redis_ext::claim_raid_reminder_15m(&redis, raid_id).await {
+        let claimed = match crate::redis_ext::claim_raid_reminder_15m(&redis, raid_id, raid.scheduled_for.timestamp()).await {
             Ok(v) => v,
             Err(e) => {
                 eprintln!("claim_raid_reminder_15m failed: {e:#}");
@@ -136,8 +324,8 @@ pub fn schedule_raid_15m_reminder(
             Ok(v) => v, Err(_) => return,
         };
 
-        let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
-        let chan_mention = format!("<#{}>", raid.channel_id as u64);
+        let when_local = raid.scheduled_for.with_timezone(&crate::settings::guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
+        let chan_mention = format!("<#{}>", raid.chat_channel_id());
 
         // unique per user: prefer MAIN if they have any main row
         let mut main_any_by_user: HashMap<i64, bool> = HashMap::new();
@@ -157,13 +345,124 @@ pub fn schedule_raid_15m_reminder(
             crate::utils::dm_user(&http, uid as u64, msg).await;
         }
     });
+    track_raid_timer(raid_id, RaidTimer::Reminder, handle);
+}
+
+const TEMPLATE_RETRY: Duration = Duration::from_secs(5 * 60);
+
+/// Arms the next run of a weekly template: at (occurrence - days_ahead) the raid is created through
+/// the same path as `/raid`, then the following week is armed. Returns the occurrence that was armed.
+pub fn schedule_template_run(
+    ctx: Context,
+    pool: PgPool,
+    template: &RaidTemplate,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let weekday = crate::utils::weekday_from_index(template.weekday)?;
+    let now = chrono::Utc::now();
+    // Weeks already posted are skipped; a week whose post time passed while offline runs right away.
+    let after = template.last_run_for.map(|t| t.max(now)).unwrap_or(now);
+    let occurrence = crate::utils::next_weekly_occurrence(
+        weekday,
+        template.start_time,
+        after,
+        crate::settings::guild_tz(template.guild_id as u64),
+    )?;
+    let run_at = occurrence - CDuration::days(template.days_ahead as i64);
+
+    let template_id = template.id;
+    let wait = (run_at - chrono::Utc::now()).to_std().unwrap_or(Duration::from_secs(0));
+    let when = Instant::now() + wait;
+    let handle = tokio::spawn(async move {
+        sleep_until(when).await;
+        // A failed post releases the week: retry this same occurrence until the raid would start
+        while let Err(e) = run_template(&ctx, &pool, template_id, occurrence).await {
+            eprintln!("raid template {template_id} run for {occurrence} failed: {e:#}");
+            let left = (occurrence - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO);
+            if left.is_zero() {
+                report_template_failure(&ctx.http, &pool, template_id, occurrence, &e).await;
+                break;
+            }
+            tokio::time::sleep(left.min(TEMPLATE_RETRY)).await;
+        }
+        // Re-read so a deleted template stops here. Re-arming replaces this task's own handle,
+        // which is harmless: nothing is awaited after it.
+        if let Ok(Some(t)) = repo::get_raid_template(&pool, template_id).await {
+            if t.is_active {
+                schedule_template_run(ctx, pool, &t);
+            }
+        }
+    });
+    track_template_timer(template_id, handle);
+    Some(occurrence)
+}
+
+/* The week is given up on: tell the template's creator instead of skipping it silently */
+async fn report_template_failure(
+    http: &Http,
+    pool: &PgPool,
+    template_id: Uuid,
+    occurrence: chrono::DateTime<chrono::Utc>,
+    error: &anyhow::Error,
+) {
+    let Ok(Some(t)) = repo::get_raid_template(pool, template_id).await else { return; };
+    let when_local = occurrence
+        .with_timezone(&crate::settings::guild_tz(t.guild_id as u64))
+        .format("%Y-%m-%d %H:%M %Z");
+    crate::utils::dm_user(http, t.created_by as u64, format!(
+        "⚠️ The weekly **{}** raid for {} couldn't be posted: {}\nThe template goes on with next week. Template ID: `{}`",
+        t.raid_name, when_local, error, template_id
+    )).await;
+}
+
+async fn run_template(
+    ctx: &Context,
+    pool: &PgPool,
+    template_id: Uuid,
+    occurrence: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<()> {
+    let Some(t) = repo::get_raid_template(pool, template_id).await? else { return Ok(()); };
+    if !t.is_active {
+        return Ok(());
+    }
+    // Claim the week first so a restart mid-run never posts it twice; a failed post releases it again
+    if !repo::claim_raid_template_run(pool, template_id, occurrence).await? {
+        return Ok(());
+    }
+
+    let priority_until = match t.priority_hours {
+        Some(h) if t.is_priority => Some(occurrence - CDuration::hours(h as i64)),
+        _ => None,
+    };
+    let duration_minutes = repo::get_raid_type(pool, t.guild_id, &t.raid_name).await?
+        .map(|rt| rt.default_duration_minutes)
+        .unwrap_or(60);
+    let created = crate::commands::raid::create_raid(ctx, crate::commands::raid::NewRaid {
+        guild_id: GuildId::new(t.guild_id as u64),
+        created_by: UserId::new(t.created_by as u64),
+        raid_name: t.raid_name,
+        scheduled_for: occurrence,
+        duration_minutes,
+        max_players: t.max_players as i64,
+        allow_alts: t.allow_alts,
+        max_alts: t.max_alts as i64,
+        priority: t.is_priority,
+        priority_role_id: if t.is_priority { Some(t.priority_role_ids) } else { None },
+        priority_until,
+        description: t.description,
+    }).await;
+    if let Err(e) = created {
+        repo::release_raid_template_run(pool, template_id, occurrence, t.last_run_for).await?;
+        return Err(e);
+    }
+    Ok(())
 }
 
 pub async fn restore_schedules(
-    http: Arc<Http>,
+    ctx: Context,
     pool: PgPool,
     redis: redis::Client,
 ) -> anyhow::Result<()> {
+    let http = ctx.http.clone();
     // Fetch all active raids we might need to handle
     let raids = repo::list_active_raids_for_restore(&pool).await?;
 
@@ -189,20 +488,32 @@ pub async fn restore_schedules(
             schedule_raid_15m_reminder(http.clone(), pool.clone(), redis.clone(), r.id, chrono::Utc::now());
         }
 
-        // 3c) Auto-delete (recompute from description like at creation)
-        let (_desc_clean, dur_h) = crate::utils::extract_duration_hours(&r.description);
-        let duration_for_schedule: i64 = dur_h.ceil() as i64;
-        let delete_at = r.scheduled_for
-            + CDuration::hours(duration_for_schedule)
-            + CDuration::minutes(20);
+        // 3c) Auto-delete (same rule as at creation)
+        let delete_at = auto_delete_at(r.scheduled_for, r.duration_minutes);
 
         if chrono::Utc::now() < delete_at {
-            schedule_auto_delete(http.clone(),pool.clone(), r.id, r.channel_id, delete_at);
+            // Attendance panel at the raid's end (right away if it ended while offline; sent once)
+            let ends_at = r.scheduled_for + CDuration::minutes(r.duration_minutes as i64);
+            schedule_attendance_panel(http.clone(), pool.clone(), r.id, ends_at);
+            schedule_auto_delete(http.clone(),pool.clone(), r.id, delete_at);
+
+            // 3c.1) Events tab: recreate a missing event, fix one edited while we were offline
+            if let Ok(raid) = repo::get_raid(&pool, r.id).await {
+                crate::scheduled_events::sync_raid_event(&http, &pool, &raid).await;
+            }
         } else {
-            let _ = ChannelId::new(r.channel_id as u64).delete(&http).await;
-            let _ = crate::db::repo::inactive_raid_after_delete_channel(&pool, r.channel_id).await;
+            // The panel needs the raid still active, so it goes out before closing
+            if let Err(e) = crate::commands::attendance::send_panel(&http, &pool, r.id).await {
+                eprintln!("attendance panel for raid {} failed: {e:#}", r.id);
+            }
+            let _ = close_raid(&http, &pool, r.id).await;
         }
     }
 
+    // 3d) Weekly templates: arm the next pending run of each one
+    for t in repo::list_active_raid_templates(&pool).await? {
+        schedule_template_run(ctx.clone(), pool.clone(), &t);
+    }
+
     Ok(())
 }
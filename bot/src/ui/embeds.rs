@@ -1,19 +1,19 @@
 use serenity::all::{Context, CreateEmbed};
 use crate::db::models::{Raid, RaidParticipant};
+use crate::catalog;
+use crate::composition;
 use crate::utils::emoji_tag;
 use crate::utils::mention_user;
-use crate::utils::extract_duration_hours;
-use crate::utils::fmt_hours;
-use chrono_tz::Europe::Warsaw;
-
-pub fn render_new_raid_embed(raid_name: &str, description: &str, scheduled_for: chrono::DateTime<chrono::Utc>, max_player:&i64) -> CreateEmbed {
-    let (desc_clean, dur_h) = extract_duration_hours(description);
-    let dur_str = fmt_hours(dur_h);
-    let when_local = scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
+use crate::utils::fmt_duration;
+use chrono_tz::Tz;
+use crate::settings::guild_tz;
+
+pub fn render_new_raid_embed(raid_name: &str, description: &str, scheduled_for: chrono::DateTime<chrono::Utc>, duration_minutes: i32, max_player:&i64, tz: Tz) -> CreateEmbed {
+    let when_local = scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z");
     CreateEmbed::new()
         .title(format!("Raid: {}", raid_name))
-        .field("Duration", dur_str, true)
-        .field("Description", desc_clean, false)
+        .field("Duration", fmt_duration(duration_minutes), true)
+        .field("Description", description, false)
         .description(format!("**Date:** {}\n{}", when_local, render_empty_slots(*max_player )))
 }
 
@@ -31,43 +31,31 @@ fn render_raid_embed_inner(ctx_guild: Option<(&Context, u64)>, raid: &Raid, part
     let mains: Vec<&RaidParticipant> = participants.iter().filter(|p| p.is_main).collect();
     // mains.sort_by_key(|p| (p.is_alt, p.joined_at)); // non-alt mains first
 
-    let reserves: Vec<&RaidParticipant> = participants.iter().filter(|p| !p.is_main).collect();
+    let reserves: Vec<&RaidParticipant> = participants.iter().filter(|p| !p.is_main && !p.is_tentative).collect();
+    let tentative: Vec<&RaidParticipant> = participants.iter().filter(|p| !p.is_main && p.is_tentative).collect();
     // reserves.sort_by_key(|p| (p.is_alt, p.joined_at)); // show non-alt reserves first
 
 
     let mut lines: Vec<String> = Vec::with_capacity(slots);
     for i in 0..slots {
         if let Some(p) = mains.get(i) {
-            let mut label = decorate_joined_as(ctx_guild, &p.joined_as);
-            if !p.extra_sps.is_empty() {
-                // show extra SPs except the active one present in joined_as
-                let active_sp = p.joined_as.split('/').nth(1).map(|s| s.trim().to_ascii_uppercase());
-                let extras: Vec<String> = p
-                    .extra_sps
-                    .iter()
-                    .map(|s| s.trim().to_ascii_uppercase())
-                    .filter(|s| Some(s.as_str()) != active_sp.as_deref())
-                    .collect();
-                if !extras.is_empty() {
-                    label.push_str(&format!(" ({})", extras.join(", ")));
-                }
-            }
+            let label = participant_label(ctx_guild, p);
             let suffix_role = p.tag_suffix.as_str();
             let suffix = if p.is_alt { " (ALT)" } else { "" };
-            lines.push(format!("{}. {} {} {}{}", i + 1, label, mention_user( p.user_id), suffix,suffix_role));
+            let tentative = if p.is_tentative { " ❔" } else { "" };
+            lines.push(format!("{}. {} {} {}{}{}{}", i + 1, label, mention_user( p.user_id), suffix,suffix_role, tentative, note_suffix(p)));
         } else {
             lines.push(format!("{}. [Empty]", i + 1));
         }
     }
-    let (desc_clean, dur_h) = extract_duration_hours(&raid.description);
-    let dur_str = fmt_hours(dur_h);
-    let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
+    let tz = guild_tz(raid.guild_id as u64);
+    let when_local = raid.scheduled_for.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z");
     let mut e = CreateEmbed::new()
         .title(format!("Raid: {}", raid.raid_name))
-        .field("Duration", dur_str, true)
+        .field("Duration", fmt_duration(raid.duration_minutes), true)
         .description(format!("**Date:** {}\n{}", when_local, lines.join("\n")))
         .field("Owner", format!("{}", mention_user(raid.owner_id) ), true)
-        .field("Description", desc_clean, false)
+        .field("Description", &raid.description, false)
         .field(
             "Capacity",
             format!("{}/{} (alts allowed: {}, max_alts: {})",
@@ -80,29 +68,26 @@ fn render_raid_embed_inner(ctx_guild: Option<(&Context, u64)>, raid: &Raid, part
         );
 
     if let Some(until) = raid.priority_until {
-        e = e.field("Priority until", format!("{}", until), true);
+        e = e.field("Priority until", until.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string(), true);
+    }
+
+    if raid.min_points > 0 {
+        e = e.field("Min. points", raid.min_points.to_string(), true);
+    }
+
+    // Composition limits (unmet ones in bold)
+    if !raid.composition.is_empty() {
+        e = e.field("Composition", composition::summary(&raid.composition, participants).join("\n"), false);
     }
 
     // Reserves compact field (first 10)
     if !reserves.is_empty() {
         let mut rlines = Vec::new();
         for p in reserves.iter().take(10) {
-            let mut label = decorate_joined_as(ctx_guild, &p.joined_as);
-            if !p.extra_sps.is_empty() {
-                let active_sp = p.joined_as.split('/').nth(1).map(|s| s.trim().to_ascii_uppercase());
-                let extras: Vec<String> = p
-                    .extra_sps
-                    .iter()
-                    .map(|s| s.trim().to_ascii_uppercase())
-                    .filter(|s| Some(s.as_str()) != active_sp.as_deref())
-                    .collect();
-                if !extras.is_empty() {
-                    label.push_str(&format!(" ({})", extras.join(", ")));
-                }
-            }
+            let label = participant_label(ctx_guild, p);
             let suffix = if p.is_alt { " (ALT)" } else { "" };
             let suffix_role = p.tag_suffix.as_str();
-            rlines.push(format!("• {} {} {}{}", label, mention_user( p.user_id), suffix,suffix_role));
+            rlines.push(format!("• {} {} {}{}{}", label, mention_user( p.user_id), suffix,suffix_role, note_suffix(p)));
         }
         if reserves.len() > 10 {
             rlines.push(format!("... and {} more", reserves.len() - 10));
@@ -110,33 +95,53 @@ fn render_raid_embed_inner(ctx_guild: Option<(&Context, u64)>, raid: &Raid, part
         e = e.field("Reserves", rlines.join("\n"), false);
     }
 
+    // Tentative players (behind the reserves)
+    if !tentative.is_empty() {
+        let mut tlines = Vec::new();
+        for p in tentative.iter().take(10) {
+            let label = participant_label(ctx_guild, p);
+            let suffix = if p.is_alt { " (ALT)" } else { "" };
+            tlines.push(format!("• {} {}{}{}", label, mention_user(p.user_id), suffix, note_suffix(p)));
+        }
+        if tentative.len() > 10 {
+            tlines.push(format!("... and {} more", tentative.len() - 10));
+        }
+        e = e.field("Tentative", tlines.join("\n"), false);
+    }
+
     e
 }
 
+/* Signup note, cut short so the roster stays one line per player */
+fn note_suffix(p: &RaidParticipant) -> String {
+    const MAX: usize = 30;
+    if p.note.is_empty() {
+        return String::new();
+    }
+    if p.note.chars().count() > MAX {
+        format!(" — _{}…_", p.note.chars().take(MAX - 1).collect::<String>())
+    } else {
+        format!(" — _{}_", p.note)
+    }
+}
+
 fn render_empty_slots(n: i64) -> String {
     (1..=n).map(|i| format!("{i}. [Empty]")).collect::<Vec<_>>().join("\n")
 }
 
-fn decorate_joined_as(ctx_guild: Option<(&Context, u64)>, text: &str) -> String {
+/* "<emoji> CLASS / SP (extra SPs)" */
+fn participant_label(ctx_guild: Option<(&Context, u64)>, p: &RaidParticipant) -> String {
     let mut out = String::new();
-    let parts: Vec<&str> = text.split('/').map(|s| s.trim()).collect();
-    if parts.len() == 2 {
-        let class = parts[0];
-        let sp = parts[1].to_ascii_uppercase().replace("SP", "SP");
-        let abbr: String = match class.to_ascii_lowercase().as_str() {
-            "warrior" | "msw" => "MSW".to_string(),
-            "archer" | "arch" => "ARCH".to_string(),
-            "swordsman" | "sword" => "SWORD".to_string(),
-            "mage" | "mag" => "MAG".to_string(),
-            other => other.to_ascii_uppercase(),
-        };
-        let emoji_name = format!("{}_{}", abbr, sp);
-        if let Some((ctx, gid)) = ctx_guild {
-            if let Some(tag) = emoji_tag(ctx, gid, &emoji_name) {
-                out.push_str(&format!("{} ", tag));
-            }
+    if let Some((ctx, gid)) = ctx_guild {
+        if let Some(tag) = emoji_tag(ctx, gid, &catalog::emoji_name(&p.class, &p.active_sp)) {
+            out.push_str(&format!("{} ", tag));
         }
     }
-    out.push_str(text);
+    out.push_str(&p.joined_as());
+    // show extra SPs except the active one
+    let extras: Vec<&str> = p.extra_sps.iter().map(|s| s.as_str()).filter(|s| !s.eq_ignore_ascii_case(&p.active_sp)).collect();
+    if !extras.is_empty() {
+        out.push_str(&format!(" ({})", extras.join(", ")));
+    }
     out
 }
@@ -4,6 +4,8 @@ use serenity::builder::{
 use serenity::all::ButtonStyle;
 use uuid::Uuid;
 
+use crate::catalog;
+
 /* Main buttons row */
 pub fn main_buttons_row(raid_id: Uuid) -> CreateActionRow {
     CreateActionRow::Buttons(vec![
@@ -25,9 +27,12 @@ pub fn main_buttons_row(raid_id: Uuid) -> CreateActionRow {
     ])
 }
 
-/* Additional SP controls row */
+/* Quick join (saved /profile characters) and additional SP controls row */
 pub fn sp_buttons_row(raid_id: Uuid) -> CreateActionRow {
     CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("r:qj:{raid_id}"))
+            .label("Quick join")
+            .style(ButtonStyle::Success),
         CreateButton::new(format!("r:asp:{raid_id}"))
             .label("Add another SP")
             .style(ButtonStyle::Secondary),
@@ -49,6 +54,30 @@ pub fn confirm_row(raid_id: Uuid, main: bool) -> CreateActionRow {
     ])
 }
 
+/* After signing up: note modal and tentative toggle */
+pub fn signup_extras_row(raid_id: Uuid, tentative: bool) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("r:nt:{raid_id}"))
+            .label("Add note")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("r:tn:{raid_id}"))
+            .label(if tentative { "I'm coming" } else { "Mark tentative" })
+            .style(if tentative { ButtonStyle::Success } else { ButtonStyle::Secondary }),
+    ])
+}
+
+/* /raid preview: post or drop the pending raid */
+pub fn new_raid_confirm_row(pending_id: Uuid) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("r:nc:{pending_id}"))
+            .label("Create")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("r:nx:{pending_id}"))
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ])
+}
+
 /* Owner manage rows built from string options (reserves only / all users) */
 pub fn user_select_row(custom_id: String, placeholder: &str, options: Vec<(String, String)>) -> CreateActionRow {
     let menu = CreateSelectMenu::new(
@@ -63,12 +92,12 @@ pub fn user_select_row(custom_id: String, placeholder: &str, options: Vec<(Strin
     CreateActionRow::SelectMenu(menu)
 }
 pub fn class_menu_row_selected(raid_id: Uuid, selected: Option<&str>) -> CreateActionRow {
-    let classes = ["MSW", "MAG", "ARCH", "SWORD"];
-    let options = classes
+    let options = catalog::classes()
         .into_iter()
-        .map(|label| {
-            let mut opt = CreateSelectMenuOption::new(label, label);
-            if selected.map(|s| s.eq_ignore_ascii_case(label)).unwrap_or(false) {
+        .take(25) // select menu limit
+        .map(|class| {
+            let mut opt = CreateSelectMenuOption::new(&class.name, &class.code);
+            if selected.map(|s| s.eq_ignore_ascii_case(&class.code)).unwrap_or(false) {
                 opt = opt.default_selection(true);
             }
             opt
@@ -86,27 +115,44 @@ pub fn class_menu_row_selected(raid_id: Uuid, selected: Option<&str>) -> CreateA
     CreateActionRow::SelectMenu(menu)
 }
 
-pub fn sp_menu_row_selected(raid_id: Uuid, class: Option<&str>, selected_sp: Option<&str>) -> CreateActionRow {
-    let sp_list = match class.map(|s| s.to_ascii_uppercase()) {
-        Some(ref c) if c == "MSW" => vec![1,2,3,4,9,10,11],
-        _ => (1..=11).collect::<Vec<_>>(),
-    };
-
-    let options = sp_list
+/* SPs of the class as select options; labels are the catalog names, values the SP codes */
+pub fn sp_options(class: &str, selected_sp: Option<&str>, skip: &[String]) -> Vec<CreateSelectMenuOption> {
+    catalog::sps_for(class)
         .into_iter()
-        .map(|i| {
-            let label = format!("SP{}", i);
-            let mut opt = CreateSelectMenuOption::new(&label, &label);
-            if selected_sp
-                .as_deref()
-                .map(|v| v.eq_ignore_ascii_case(&label))
-                .unwrap_or(false)
-            {
+        .filter(|sp| !skip.iter().any(|s| s.eq_ignore_ascii_case(&sp.code)))
+        .take(25)
+        .map(|sp| {
+            let mut opt = CreateSelectMenuOption::new(&sp.name, &sp.code);
+            if selected_sp.map(|v| v.eq_ignore_ascii_case(&sp.code)).unwrap_or(false) {
                 opt = opt.default_selection(true);
             }
             opt
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+pub fn sp_menu_row_selected(raid_id: Uuid, class: Option<&str>, selected_sp: Option<&str>) -> CreateActionRow {
+    let mut options = match class {
+        Some(class) => sp_options(class, selected_sp, &[]),
+        // No class yet: every SP any class has, so the two menus can be used in any order
+        None => {
+            let mut seen: Vec<String> = Vec::new();
+            let mut all = Vec::new();
+            for sp in catalog::classes().iter().flat_map(|c| catalog::sps_for(&c.code)) {
+                if seen.contains(&sp.code) || all.len() == 25 { continue; }
+                let mut opt = CreateSelectMenuOption::new(&sp.name, &sp.code);
+                if selected_sp.map(|v| v.eq_ignore_ascii_case(&sp.code)).unwrap_or(false) {
+                    opt = opt.default_selection(true);
+                }
+                seen.push(sp.code);
+                all.push(opt);
+            }
+            all
+        }
+    };
+    if options.is_empty() {
+        options.push(CreateSelectMenuOption::new("No SPs for this class", "none"));
+    }
 
     let menu = CreateSelectMenu::new(
         format!("r:ps:{raid_id}"),
@@ -1,8 +1,9 @@
 use uuid::Uuid;
 use chrono::{NaiveDate, NaiveTime, NaiveDateTime, Utc, TimeZone};
 use chrono::Datelike;
-use chrono_tz::Europe::Warsaw;
-use serenity::all::{Context, Emoji, UserId,Http,GuildId,RoleId};
+use chrono_tz::Tz;
+use serenity::all::{CommandInteraction, Context, Emoji, UserId,Http,GuildId,RoleId};
+use crate::settings::GuildRole;
 use serenity::prelude::Mentionable;
 use once_cell::sync::Lazy;
 use dashmap::DashMap;
@@ -11,8 +12,6 @@ use tokio::time::Instant;
 use regex::Regex;
 use std::collections::HashMap;
 
-pub const ORGANISER_ROLE_NAME: &str = "raid_organiser";
-pub const PERMISSIONS_ROLE_NAME: &str = "Zarządca_permisje";
 static NAME_CACHE: Lazy<DashMap<u64, (String, Instant)>> = Lazy::new(DashMap::new);
 const NAME_TTL: StdDuration = StdDuration::from_secs(60 * 30); // 30 min
 
@@ -202,24 +201,141 @@ pub fn parse_component_id(s: &str) -> Option<(String, String, Uuid)> {
         ["aspick",uuid] => uuid.parse().ok().map(|u| ("aspick".into(),"".into(),u)),
         ["csp",uuid]    => uuid.parse().ok().map(|u| ("csp".into(),"".into(),u)),
         ["cspick",uuid] => uuid.parse().ok().map(|u| ("cspick".into(),"".into(),u)),
+        ["nc",uuid]     => uuid.parse().ok().map(|u| ("nc".into(),"".into(),u)),
+        ["nx",uuid]     => uuid.parse().ok().map(|u| ("nx".into(),"".into(),u)),
+        ["ed",uuid]     => uuid.parse().ok().map(|u| ("ed".into(),"".into(),u)),
+        ["edm",uuid]    => uuid.parse().ok().map(|u| ("edm".into(),"".into(),u)),
+        ["wzt",uuid]    => uuid.parse().ok().map(|u| ("wzt".into(),"".into(),u)),
+        ["wzm",uuid]    => uuid.parse().ok().map(|u| ("wzm".into(),"".into(),u)),
+        ["wzr",uuid]    => uuid.parse().ok().map(|u| ("wzr".into(),"".into(),u)),
+        ["wzb",uuid]    => uuid.parse().ok().map(|u| ("wzb".into(),"".into(),u)),
+        ["wzc",uuid]    => uuid.parse().ok().map(|u| ("wzc".into(),"".into(),u)),
+        ["wzx",uuid]    => uuid.parse().ok().map(|u| ("wzx".into(),"".into(),u)),
+        ["cln",uuid]    => uuid.parse().ok().map(|u| ("cln".into(),"".into(),u)),
+        ["clnm",uuid]   => uuid.parse().ok().map(|u| ("clnm".into(),"".into(),u)),
+        ["qj",uuid]     => uuid.parse().ok().map(|u| ("qj".into(),"".into(),u)),
+        ["qjp",uuid]    => uuid.parse().ok().map(|u| ("qjp".into(),"".into(),u)),
+        ["nt",uuid]     => uuid.parse().ok().map(|u| ("nt".into(),"".into(),u)),
+        ["ntm",uuid]    => uuid.parse().ok().map(|u| ("ntm".into(),"".into(),u)),
+        ["tn",uuid]     => uuid.parse().ok().map(|u| ("tn".into(),"".into(),u)),
+        ["atu",uuid]    => uuid.parse().ok().map(|u| ("atu".into(),"".into(),u)),
+        ["atm",code,uuid] => uuid.parse().ok().map(|u| ("atm".into(),code.to_string(),u)),
+        ["atr",uuid]    => uuid.parse().ok().map(|u| ("atr".into(),"".into(),u)),
+        ["lt",uuid]     => uuid.parse().ok().map(|u| ("lt".into(),"".into(),u)),
+        ["ltu",uuid]    => uuid.parse().ok().map(|u| ("ltu".into(),"".into(),u)),
+        ["ltm",user,uuid] => uuid.parse().ok().map(|u| ("ltm".into(),user.to_string(),u)),
+        // Raid history views belong to no raid: "<page>:<user>" instead of an id
+        [k @ ("rhd" | "rhp"),page,user] if page.parse::<i64>().is_ok() && user.parse::<u64>().is_ok()
+                        => Some((k.to_string(), format!("{page}:{user}"), Uuid::nil())),
 
         _ => None,
     }
 }
 
-/* Parse "HH:MM YYYY-MM-DD" in Europe/Warsaw -> UTC */
-pub fn parse_raid_datetime(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
-    let parts: Vec<&str> = s.split_whitespace().collect();
-    if parts.len() != 2 { return None; }
-    let time = NaiveTime::parse_from_str(parts[0], "%H:%M").ok()?;
-    let date = NaiveDate::parse_from_str(parts[1], "%Y-%m-%d").ok()?;
-    let local = Warsaw.from_local_datetime(&NaiveDateTime::new(date, time)).single()?;
-    Some(local.with_timezone(&Utc))
+pub const RAID_DATE_FORMATS: &str =
+    "`HH:MM YYYY-MM-DD`, `DD.MM HH:MM`, `today 20:00`, `tomorrow 21:30`, `fri 19:00`, `+2d 20:00` or a Discord timestamp `<t:1700000000>`";
+
+static DISCORD_TS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<t:(-?\d+)(?::[tTdDfFR])?>$").unwrap());
+
+/* Parse raid date input in the guild's timezone -> UTC. Date and time may come in either order.
+   The error is a message for the user (unknown format, past time, DST gap/overlap). */
+pub fn parse_raid_datetime(s: &str, tz: Tz) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let now = Utc::now();
+    let s = s.trim();
+
+    // Pasted Discord timestamp is already absolute
+    if let Some(c) = DISCORD_TS_RE.captures(s) {
+        let dt = c[1].parse::<i64>().ok()
+            .and_then(|secs| chrono::DateTime::<Utc>::from_timestamp(secs, 0))
+            .ok_or_else(|| format!("`{}` is not a valid timestamp.", s))?;
+        return not_in_past(dt, tz, now);
+    }
+
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    let (time_s, date_s) = match tokens.as_slice() {
+        [a, b] if a.contains(':') => (*a, *b),
+        [a, b] if b.contains(':') => (*b, *a),
+        _ => return Err(format!("Couldn't read `{}`. Use {}.", s, RAID_DATE_FORMATS)),
+    };
+    let time = NaiveTime::parse_from_str(time_s, "%H:%M")
+        .map_err(|_| format!("`{}` is not a valid time. Use `HH:MM` (24h).", time_s))?;
+
+    let now_local = now.with_timezone(&tz).naive_local();
+    let today = now_local.date();
+    let date = resolve_raid_date(&date_s.to_lowercase(), today, time, now_local)
+        .ok_or_else(|| format!("Couldn't read the date `{}`. Use {}.", date_s, RAID_DATE_FORMATS))?;
+
+    let dt = match tz.from_local_datetime(&NaiveDateTime::new(date, time)) {
+        chrono::LocalResult::Single(dt) => dt,
+        chrono::LocalResult::Ambiguous(_, _) => return Err(format!(
+            "{} {} happens twice in {} (clocks go back). Pick another time or paste a `<t:…>` timestamp.",
+            date, time.format("%H:%M"), tz.name()
+        )),
+        chrono::LocalResult::None => return Err(format!(
+            "{} {} doesn't exist in {} (clocks go forward). Pick another time.",
+            date, time.format("%H:%M"), tz.name()
+        )),
+    };
+    not_in_past(dt.with_timezone(&Utc), tz, now)
+}
+
+/* Date part of raid input; weekday names and bare DD.MM roll forward so the result is not behind `now_local` */
+fn resolve_raid_date(s: &str, today: NaiveDate, time: NaiveTime, now_local: NaiveDateTime) -> Option<NaiveDate> {
+    let is_ahead = |d: NaiveDate| NaiveDateTime::new(d, time) > now_local;
+    match s {
+        "today" | "dzis" | "dziś" => return Some(today),
+        "tomorrow" | "jutro" => return today.succ_opt(),
+        _ => {}
+    }
+    if let Some(n) = s.strip_prefix('+').and_then(|r| r.strip_suffix('d')) {
+        let n: u64 = n.parse().ok().filter(|n| *n <= 365)?;
+        return today.checked_add_days(chrono::Days::new(n));
+    }
+    if let Ok(wd) = s.parse::<chrono::Weekday>() {
+        let mut ahead = (7 + wd.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        if ahead == 0 && !is_ahead(today) {
+            ahead = 7;
+        }
+        return today.checked_add_days(chrono::Days::new(ahead as u64));
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(d);
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%d.%m.%Y") {
+        return Some(d);
+    }
+    // DD.MM: this year, or next year once this year's date has passed
+    let (day, month) = s.split_once('.')?;
+    let (day, month): (u32, u32) = (day.parse().ok()?, month.trim_end_matches('.').parse().ok()?);
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+    match this_year {
+        Some(d) if is_ahead(d) => Some(d),
+        _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day),
+    }
+}
+
+fn not_in_past(
+    dt: chrono::DateTime<chrono::Utc>,
+    tz: Tz,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if dt <= now {
+        return Err(format!(
+            "{} is already in the past.",
+            dt.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z")
+        ));
+    }
+    Ok(dt)
 }
 
-/* Weekday key string used to find category (e.g., "tuesday") */
-pub fn weekday_key(dt_utc: chrono::DateTime<chrono::Utc>) -> &'static str {
-    match dt_utc.with_timezone(&Warsaw).weekday() {
+/* Channel-name slug for a raid type: lowercase, spaces/underscores -> hyphens ("Hc_Azgobas" -> "hc-azgobas") */
+pub fn raid_slug(name: &str) -> String {
+    name.to_lowercase().replace([' ', '_'], "-")
+}
+
+/* Weekday key string used to find category (e.g., "tuesday"), in the guild's timezone */
+pub fn weekday_key(dt_utc: chrono::DateTime<chrono::Utc>, tz: Tz) -> &'static str {
+    match dt_utc.with_timezone(&tz).weekday() {
         chrono::Weekday::Mon => "monday",
         chrono::Weekday::Tue => "tuesday",
         chrono::Weekday::Wed => "wednesday",
@@ -230,6 +346,85 @@ pub fn weekday_key(dt_utc: chrono::DateTime<chrono::Utc>) -> &'static str {
     }
 }
 
+/* Next `weekday` at `time` (guild timezone) strictly after `after`, as UTC. Used by raid templates. */
+pub fn next_weekly_occurrence(
+    weekday: chrono::Weekday,
+    time: NaiveTime,
+    after: chrono::DateTime<chrono::Utc>,
+    tz: Tz,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let start = after.with_timezone(&tz).date_naive();
+    (0..=14)
+        .filter_map(|d| start.checked_add_days(chrono::Days::new(d)))
+        .filter(|date| date.weekday() == weekday)
+        .filter_map(|date| tz.from_local_datetime(&NaiveDateTime::new(date, time)).earliest())
+        .map(|local| local.with_timezone(&Utc))
+        .find(|utc| *utc > after)
+}
+
+/* `at` moved by `days` calendar days at the same wall-clock time in `tz`, so a 20:00 raid stays at 20:00
+   across a DST change. The error is a message for the user when that local time is skipped or repeated. */
+pub fn shift_local_days(
+    at: chrono::DateTime<chrono::Utc>,
+    days: i64,
+    tz: Tz,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let local = at.with_timezone(&tz).naive_local();
+    let date = local.date()
+        .checked_add_signed(chrono::Duration::days(days))
+        .ok_or_else(|| format!("{} days is out of range.", days))?;
+    match tz.from_local_datetime(&NaiveDateTime::new(date, local.time())) {
+        chrono::LocalResult::Single(dt) => Ok(dt.with_timezone(&Utc)),
+        chrono::LocalResult::Ambiguous(_, _) => Err(format!(
+            "{} {} happens twice in {} (clocks go back). Pick another number of days.",
+            date, local.time().format("%H:%M"), tz.name()
+        )),
+        chrono::LocalResult::None => Err(format!(
+            "{} {} doesn't exist in {} (clocks go forward). Pick another number of days.",
+            date, local.time().format("%H:%M"), tz.name()
+        )),
+    }
+}
+
+/* Weekday from the 0 = Monday index stored in raid_templates */
+pub fn weekday_from_index(idx: i16) -> Option<chrono::Weekday> {
+    match idx {
+        0 => Some(chrono::Weekday::Mon),
+        1 => Some(chrono::Weekday::Tue),
+        2 => Some(chrono::Weekday::Wed),
+        3 => Some(chrono::Weekday::Thu),
+        4 => Some(chrono::Weekday::Fri),
+        5 => Some(chrono::Weekday::Sat),
+        6 => Some(chrono::Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// True if the member holds the guild's configured role of this kind (false while it isn't configured).
+pub async fn member_has_guild_role(http: &Http, guild_id: GuildId, user_id: UserId, role: GuildRole) -> anyhow::Result<bool> {
+    let Some(role_id) = crate::settings::role_id(guild_id.get(), role) else { return Ok(false); };
+    let member = guild_id.member(http, user_id).await?;
+    Ok(member.roles.contains(&role_id))
+}
+
+/// Bot admins: the configured admin role, or anyone who can manage the server (so `/config` works on a fresh guild).
+pub async fn is_bot_admin(http: &Http, cmd: &CommandInteraction) -> anyhow::Result<bool> {
+    if cmd.member.as_ref().and_then(|m| m.permissions).is_some_and(|p| p.manage_guild()) {
+        return Ok(true);
+    }
+    let Some(gid) = cmd.guild_id else { return Ok(false); };
+    member_has_guild_role(http, gid, cmd.user.id, GuildRole::Admin).await
+}
+
+/// Whoever owns the bot application (or any member of its team). Gates data shared by every server.
+pub async fn is_bot_owner(http: &Http, user_id: UserId) -> anyhow::Result<bool> {
+    let info = http.get_current_application_info().await?;
+    if let Some(team) = info.team {
+        return Ok(team.owner_user_id == user_id || team.members.iter().any(|m| m.user.id == user_id));
+    }
+    Ok(info.owner.is_some_and(|o| o.id == user_id))
+}
+
 // Returns <:name:id> for a guild emoji if found
 pub fn emoji_tag(ctx: &Context, guild_id: u64, name: &str) -> Option<String> {
     let cache = ctx.cache.clone();
@@ -238,73 +433,15 @@ pub fn emoji_tag(ctx: &Context, guild_id: u64, name: &str) -> Option<String> {
     emoji.map(|e: &Emoji| format!("<:{}:{}>", e.name, e.id.get()))
 }
 
-/// Finds a duration in `text` and returns (cleaned_text, hours).
-/// Recognizes: "2h", "2 h", "2hr", "2hours", "2 godz", "2godzina/2godziny/2godzin",
-/// loose "g" / "gorziny" (common typo), and also minutes: "90m", "90 min", etc.
-/// If nothing found -> (original text, 1.0).
-pub fn extract_duration_hours(text: &str) -> (String, f64) {
-    static RE: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(
-            r#"(?ix)
-            \b
-            (?P<num>\d+(?:[.,]\d+)?)
-            \s*
-            (?P<unit>
-                h|hr|hrs|hour|hours|
-                m|min|mins|minute|minutes|
-                godz(?:ina|iny|in)?|g(?:odz)?|gorziny
-            )
-            \b
-        "#
-        )
-            .unwrap()
-    });
-
-    if let Some(m) = RE.find(text) {
-        // numeric value
-        let caps = RE.captures(m.as_str()).unwrap();
-        let num = caps["num"].replace(',', ".").parse::<f64>().unwrap_or(1.0);
-        let unit = &caps["unit"].to_ascii_lowercase();
-
-        // convert to hours
-        let hours = match unit.as_str() {
-            "m" | "min" | "mins" | "minute" | "minutes" => (num / 60.0).max(0.0),
-            _ => num.max(0.0),
-        };
-
-        // remove the matched fragment from description
-        let mut cleaned = String::new();
-        cleaned.push_str(&text[..m.start()]);
-        cleaned.push_str(&text[m.end()..]);
-
-        // tidy double spaces and stray punctuation near removed token
-        let cleaned = cleaned
-            .replace("  ", " ")
-            .replace(" ,", ",")
-            .replace("( )", "")
-            .trim()
-            .to_string();
-
-        let hours = if hours > 0.0 { hours } else { 1.0 };
-        return (cleaned, hours);
-    }
-
-    (text.to_string(), 1.0)
-}
-
-/// Format hours nicely: "2h" or "1.5h"
-pub fn fmt_hours(h: f64) -> String {
-    if (h.fract()).abs() < 0.01 {
-        format!("{}h", h.round() as i64)
-    } else {
-        // 1 decimal place looks neat
-        format!("{:.1}h", h)
+/// Format a raid length: "2h", "1h 30m" or "45m"
+pub fn fmt_duration(minutes: i32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
     }
 }
 
-
-
-
 pub async fn dm_user(http: &Http, user_id: u64, content: String) {
     let uid = UserId::new(user_id);
     if let Ok(dm) = uid.create_dm_channel(http).await {
@@ -318,14 +455,13 @@ pub async fn dm_user(http: &Http, user_id: u64, content: String) {
 pub async fn notify_raid_now(ctx: &serenity::prelude::Context, raid_id: uuid::Uuid) -> anyhow::Result<()> {
     use crate::handlers::pool_from_ctx;
     use crate::db::repo;
-    use chrono_tz::Europe::Warsaw;
 
     let pool = pool_from_ctx(ctx).await?;
     let raid = repo::get_raid(&pool, raid_id).await?;
     let parts = repo::list_participants(&pool, raid_id).await?;
 
-    let when_local = raid.scheduled_for.with_timezone(&Warsaw).format("%Y-%m-%d %H:%M %Z");
-    let chan_mention = format!("<#{}>", raid.channel_id as u64);
+    let when_local = raid.scheduled_for.with_timezone(&crate::settings::guild_tz(raid.guild_id as u64)).format("%Y-%m-%d %H:%M %Z");
+    let chan_mention = format!("<#{}>", raid.chat_channel_id());
 
     // unique per user: prefer MAIN if any main row
     let mut main_any_by_user: HashMap<i64, bool> = HashMap::new();
@@ -367,4 +503,91 @@ pub fn csv_to_role_ids(csv: &str) -> Vec<RoleId> {
         .filter_map(|p| p.trim().parse::<u64>().ok())
         .map(RoleId::new)
         .collect()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Warsaw;
+
+    fn local(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn warsaw_date(dt: chrono::DateTime<Utc>) -> NaiveDate {
+        dt.with_timezone(&Warsaw).date_naive()
+    }
+
+    fn today_in_warsaw() -> NaiveDate {
+        Utc::now().with_timezone(&Warsaw).date_naive()
+    }
+
+    #[test]
+    fn discord_timestamp_is_taken_as_is() {
+        let at = Utc::now().timestamp() + 86_400;
+        let expected = chrono::DateTime::<Utc>::from_timestamp(at, 0).unwrap();
+        assert_eq!(parse_raid_datetime(&format!("<t:{at}>"), Warsaw), Ok(expected));
+        assert_eq!(parse_raid_datetime(&format!("<t:{at}:F>"), Warsaw), Ok(expected));
+    }
+
+    #[test]
+    fn full_dates_in_either_order() {
+        let expected = Warsaw.with_ymd_and_hms(2099, 12, 31, 20, 0, 0).unwrap().with_timezone(&Utc);
+        assert_eq!(parse_raid_datetime("20:00 2099-12-31", Warsaw), Ok(expected));
+        assert_eq!(parse_raid_datetime("31.12.2099 20:00", Warsaw), Ok(expected));
+    }
+
+    #[test]
+    fn relative_days() {
+        let tomorrow = parse_raid_datetime("tomorrow 12:00", Warsaw).unwrap();
+        assert_eq!(warsaw_date(tomorrow), today_in_warsaw().succ_opt().unwrap());
+        let in_two = parse_raid_datetime("+2d 12:00", Warsaw).unwrap();
+        assert_eq!(warsaw_date(in_two), today_in_warsaw().checked_add_days(chrono::Days::new(2)).unwrap());
+    }
+
+    #[test]
+    fn today_and_weekdays_roll_forward_past_now() {
+        // Wednesday 2030-01-02, 18:00
+        let now = local("2030-01-02 18:00");
+        let today = now.date();
+        let at = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        assert_eq!(resolve_raid_date("today", today, at(20), now), Some(today));
+        assert_eq!(resolve_raid_date("tomorrow", today, at(20), now), NaiveDate::from_ymd_opt(2030, 1, 3));
+        assert_eq!(resolve_raid_date("fri", today, at(20), now), NaiveDate::from_ymd_opt(2030, 1, 4));
+        assert_eq!(resolve_raid_date("monday", today, at(20), now), NaiveDate::from_ymd_opt(2030, 1, 7));
+        // Later today stays today, earlier today means next week
+        assert_eq!(resolve_raid_date("wed", today, at(20), now), Some(today));
+        assert_eq!(resolve_raid_date("wed", today, at(17), now), NaiveDate::from_ymd_opt(2030, 1, 9));
+    }
+
+    #[test]
+    fn day_month_picks_the_next_one() {
+        let now = local("2030-06-15 18:00");
+        let at = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+        assert_eq!(resolve_raid_date("20.06", now.date(), at, now), NaiveDate::from_ymd_opt(2030, 6, 20));
+        assert_eq!(resolve_raid_date("15.06", now.date(), at, now), NaiveDate::from_ymd_opt(2030, 6, 15));
+        assert_eq!(resolve_raid_date("10.06.", now.date(), at, now), NaiveDate::from_ymd_opt(2031, 6, 10));
+        assert_eq!(resolve_raid_date("31.02", now.date(), at, now), None);
+
+        let next_week = today_in_warsaw().checked_add_days(chrono::Days::new(7)).unwrap();
+        let parsed = parse_raid_datetime(&format!("{} 12:00", next_week.format("%d.%m")), Warsaw).unwrap();
+        assert_eq!(warsaw_date(parsed), next_week);
+    }
+
+    #[test]
+    fn dst_gap_and_fold_are_refused() {
+        // Clocks go forward 02:00 -> 03:00 on 2030-03-31 and back 03:00 -> 02:00 on 2030-10-27
+        let gap = parse_raid_datetime("02:30 2030-03-31", Warsaw).unwrap_err();
+        assert!(gap.contains("doesn't exist"), "{gap}");
+        let fold = parse_raid_datetime("27.10.2030 02:30", Warsaw).unwrap_err();
+        assert!(fold.contains("happens twice"), "{fold}");
+    }
+
+    #[test]
+    fn past_and_malformed_input_is_refused() {
+        assert!(parse_raid_datetime("20:00 2020-01-01", Warsaw).unwrap_err().contains("in the past"));
+        assert!(parse_raid_datetime("<t:1000>", Warsaw).unwrap_err().contains("in the past"));
+        assert!(parse_raid_datetime("25:00 tomorrow", Warsaw).unwrap_err().contains("not a valid time"));
+        assert!(parse_raid_datetime("someday 20:00", Warsaw).unwrap_err().contains("Couldn't read the date"));
+        assert!(parse_raid_datetime("tomorrow", Warsaw).is_err());
+    }
+}
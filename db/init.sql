@@ -42,16 +42,8 @@ ALTER TABLE raid_participants
   ADD COLUMN IF NOT EXISTS tag_suffix TEXT NOT NULL DEFAULT '';
 ALTER TABLE raid_participants
   ADD COLUMN IF NOT EXISTS extra_sps TEXT[] NOT NULL DEFAULT '{}';
-DO $$
-BEGIN
-  IF NOT EXISTS (
-    SELECT 1 FROM pg_constraint
-    WHERE conname = 'raids_raid_name_check'
-  ) THEN
-    ALTER TABLE raids
-      ADD CONSTRAINT raids_raid_name_check CHECK (raid_name IN ('ArmaV2','Pollutus','Arma','Azgobas','Valehir','Alzanor','Hc_Azgobas','Hc_Valehir','Hc_Alzanor','Hc_A8-A6','Hc_A1-A5','Hc_A1-A8','Sky-Tower','Sky_Tower', 'Nezarun','Nezarun_v2','Fernon'));
-  END IF;
-END $$;
+-- Raid names used to be pinned by a CHECK constraint; they are validated against raid_types now
+ALTER TABLE raids DROP CONSTRAINT IF EXISTS raids_raid_name_check;
 
 CREATE INDEX IF NOT EXISTS idx_raids_priority_until ON raids (priority_until);
 
@@ -68,3 +60,287 @@ CREATE TABLE IF NOT EXISTS guild_raid_list (
 
 CREATE INDEX IF NOT EXISTS idx_guild_raid_list_channel ON guild_raid_list(channel_id);
 
+
+-- Weekly raid templates: a scheduler posts the next occurrence `days_ahead` days before it starts
+CREATE TABLE IF NOT EXISTS raid_templates (
+  id                UUID PRIMARY KEY,
+  guild_id          BIGINT NOT NULL,
+  created_by        BIGINT NOT NULL,
+  raid_name         TEXT NOT NULL,
+  weekday           SMALLINT NOT NULL CHECK (weekday BETWEEN 0 AND 6), -- 0 = Monday
+  start_time        TIME NOT NULL,
+  max_players       INT NOT NULL DEFAULT 12,
+  allow_alts        BOOLEAN NOT NULL DEFAULT TRUE,
+  max_alts          INT NOT NULL DEFAULT 1,
+  is_priority       BOOLEAN NOT NULL DEFAULT FALSE,
+  priority_role_ids BIGINT[] NOT NULL DEFAULT '{}',
+  priority_hours    INT,
+  description       TEXT NOT NULL,
+  days_ahead        INT NOT NULL DEFAULT 7,
+  last_run_for      TIMESTAMPTZ,
+  is_active         BOOLEAN NOT NULL DEFAULT TRUE,
+  created_at        TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_raid_templates_guild ON raid_templates (guild_id) WHERE is_active;
+
+-- Raid type catalog: names offered by /raid autocomplete, plus channel naming and default limits.
+-- guild_id 0 holds the defaults every guild sees; a guild's own row (added, edited or removed with
+-- /raid_type) replaces the default of the same name for that guild only.
+CREATE TABLE IF NOT EXISTS raid_types (
+  guild_id                 BIGINT NOT NULL DEFAULT 0,
+  name                     TEXT NOT NULL,
+  slug                     TEXT NOT NULL,
+  emoji                    TEXT NOT NULL DEFAULT '🏷️',
+  default_max_players      INT NOT NULL DEFAULT 12,
+  default_duration_minutes INT NOT NULL DEFAULT 60,
+  default_allow_alts       BOOLEAN NOT NULL DEFAULT TRUE,
+  default_max_alts         INT NOT NULL DEFAULT 1,
+  is_active                BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+-- The catalog used to be global with one row per name
+ALTER TABLE raid_types ADD COLUMN IF NOT EXISTS guild_id BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE raid_types DROP CONSTRAINT IF EXISTS raid_types_pkey;
+DROP INDEX IF EXISTS uq_raid_types_name_ci;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_raid_types_guild_name_ci ON raid_types (guild_id, lower(name));
+
+INSERT INTO raid_types (name, slug, emoji) VALUES
+  ('ArmaV2',     'armav2',     '🦾'),
+  ('Pollutus',   'pollutus',   '🦠'),
+  ('Sky_Tower',  'sky-tower',  '🗼'),
+  ('Arma',       'arma',       '🤖'),
+  ('Azgobas',    'azgobas',    '🐉'),
+  ('Valehir',    'valehir',    '💀'),
+  ('Alzanor',    'alzanor',    '🥶'),
+  ('Hc_Azgobas', 'hc-azgobas', '🔥'),
+  ('Hc_Valehir', 'hc-valehir', '🔥'),
+  ('Hc_Alzanor', 'hc-alzanor', '🔥'),
+  ('Hc_A8-A6',   'hc-a8-a6',   '🔥'),
+  ('Hc_A1-A5',   'hc-a1-a5',   '🔥'),
+  ('Hc_A1-8',    'hc-a1-8',    '🔥'),
+  ('Nezarun',    'nezarun',    '🔨'),
+  ('Nezarun_v2', 'nezarun-v2', '🐙'),
+  ('Fernon',     'fernon',     '🏷️')
+ON CONFLICT DO NOTHING;
+
+-- Per-guild settings (timezone used for parsing, display and channel names)
+CREATE TABLE IF NOT EXISTS guild_settings (
+  guild_id   BIGINT PRIMARY KEY,
+  timezone   TEXT NOT NULL DEFAULT 'Europe/Warsaw',
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Raid length in minutes. Older raids only had it in the description ("2h", "90 min", "2 godz"),
+-- so backfill with the pattern the bot used to scrape; no match means the old 1h default.
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS duration_minutes INT;
+
+UPDATE raids r
+SET duration_minutes = COALESCE(
+    (SELECT GREATEST(1, ROUND(
+                replace(m[1], ',', '.')::numeric
+                * CASE WHEN lower(m[2]) LIKE 'm%' THEN 1 ELSE 60 END
+            ))::int
+     FROM regexp_match(
+            r.description,
+            '\m(\d+(?:[.,]\d+)?)\s*(hours|hour|hrs|hr|h|minutes|minute|mins|min|m|godz(?:ina|iny|in)?|g(?:odz)?|gorziny)\M',
+            'i'
+          ) AS m
+     WHERE m IS NOT NULL),
+    60)
+WHERE duration_minutes IS NULL;
+
+ALTER TABLE raids ALTER COLUMN duration_minutes SET DEFAULT 60;
+ALTER TABLE raids ALTER COLUMN duration_minutes SET NOT NULL;
+
+-- Raid channel placement: weekday ("monday", …) -> category id, name template,
+-- fallback category and a cap on raid channels per category (NULL = no cap).
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS weekday_categories JSONB NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS channel_name_template TEXT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS fallback_category_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS max_channels_per_category INT;
+
+-- Thread mode: raids are posted in this channel with one thread each (NULL = one channel per raid).
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS raid_thread_channel_id BIGINT;
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS thread_id BIGINT;
+
+-- Guild Scheduled Event mirroring the raid in the server's Events tab
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS event_id BIGINT;
+
+-- Roles the bot acts on, by ID (replaces the RESERVE_ROLE_NAME / ALT_ALLOW_ROLE_NAME env vars and
+-- the fixed raid_organiser / Zarządca_permisje / c90 / c1-89 / Maraton names). NULL = not configured.
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS organiser_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS admin_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS reserve_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS alt_allow_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS c90_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS c1_89_role_id BIGINT;
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS default_priority_role_id BIGINT;
+
+-- Who may join a raid. raid_type '' = every raid in the guild; a raid type with rules of its own
+-- uses only those. access: main = may join as main, reserve = may join only as reserve,
+-- alts = may sign up alts. The first matching main/reserve rule (by position) gives the tag suffix.
+-- A guild without rows uses the default set built from its /config roles.
+CREATE TABLE IF NOT EXISTS join_rules (
+  id          BIGSERIAL PRIMARY KEY,
+  guild_id    BIGINT NOT NULL,
+  raid_type   TEXT NOT NULL DEFAULT '',
+  role_id     BIGINT NOT NULL,
+  access      TEXT NOT NULL CHECK (access IN ('main','reserve','keep_reserve','alts')),
+  tag_suffix  TEXT,
+  position    INT NOT NULL DEFAULT 0,
+  UNIQUE (guild_id, raid_type, role_id, access)
+);
+CREATE INDEX IF NOT EXISTS idx_join_rules_guild ON join_rules (guild_id);
+-- keep_reserve came later: a role that keeps members on reserve without letting anyone in
+ALTER TABLE join_rules DROP CONSTRAINT IF EXISTS join_rules_access_check;
+ALTER TABLE join_rules ADD CONSTRAINT join_rules_access_check CHECK (access IN ('main','reserve','keep_reserve','alts'));
+
+-- Game class / SP catalog behind the join menus and the embed's SP emoji (codes are upper case).
+-- emoji_name NULL = guild emoji "<CLASS>_<SP>", e.g. MSW_SP1.
+CREATE TABLE IF NOT EXISTS game_classes (
+  code       TEXT PRIMARY KEY,
+  name       TEXT NOT NULL,
+  aliases    TEXT[] NOT NULL DEFAULT '{}',
+  position   INT NOT NULL DEFAULT 0,
+  is_active  BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE TABLE IF NOT EXISTS game_sps (
+  class_code TEXT NOT NULL REFERENCES game_classes(code) ON UPDATE CASCADE,
+  code       TEXT NOT NULL,
+  name       TEXT NOT NULL,
+  emoji_name TEXT,
+  position   INT NOT NULL DEFAULT 0,
+  is_active  BOOLEAN NOT NULL DEFAULT TRUE,
+  PRIMARY KEY (class_code, code)
+);
+
+INSERT INTO game_classes (code, name, aliases, position) VALUES
+  ('MSW',   'MSW',       '{warrior}',   0),
+  ('MAG',   'Mage',      '{mage}',      1),
+  ('ARCH',  'Archer',    '{archer}',    2),
+  ('SWORD', 'Swordsman', '{swordsman}', 3)
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO game_sps (class_code, code, name, position)
+SELECT c, 'SP' || n, 'SP' || n, n
+FROM unnest(ARRAY['MSW','MAG','ARCH','SWORD']) AS c, generate_series(1, 11) AS n
+WHERE c <> 'MSW' OR n IN (1, 2, 3, 4, 9, 10, 11)
+ON CONFLICT (class_code, code) DO NOTHING;
+
+-- Typed class / active SP instead of the "CLASS / SP" joined_as text. Existing rows are parsed once
+-- (class names and aliases resolve through game_classes), then joined_as goes away.
+ALTER TABLE raid_participants ADD COLUMN IF NOT EXISTS class TEXT NOT NULL DEFAULT '';
+ALTER TABLE raid_participants ADD COLUMN IF NOT EXISTS active_sp TEXT NOT NULL DEFAULT '';
+
+DO $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM information_schema.columns
+             WHERE table_name = 'raid_participants' AND column_name = 'joined_as') THEN
+    UPDATE raid_participants p
+    SET class = COALESCE(
+          (SELECT c.code FROM game_classes c
+           WHERE lower(c.code) = lower(trim(split_part(p.joined_as, '/', 1)))
+              OR lower(c.name) = lower(trim(split_part(p.joined_as, '/', 1)))
+              OR lower(trim(split_part(p.joined_as, '/', 1))) = ANY(c.aliases)
+           LIMIT 1),
+          upper(trim(split_part(p.joined_as, '/', 1)))),
+        active_sp = upper(replace(trim(split_part(p.joined_as, '/', 2)), ' ', ''));
+    ALTER TABLE raid_participants DROP COLUMN joined_as;
+  END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_participants_class ON raid_participants (raid_id, class);
+
+-- Party composition: [{"class": "MSW", "sp": "SP3" | null, "min": 2, "max": 4 | null}, ...].
+-- Mains (alts included) are counted; full maxima send joins to reserve, unmet minima are promoted first.
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS composition JSONB NOT NULL DEFAULT '[]'::jsonb;
+
+-- Saved characters for /profile and the "Quick join" button. The default one joins as main, the
+-- others can be signed up as alts.
+CREATE TABLE IF NOT EXISTS player_profiles (
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL,
+  name TEXT NOT NULL,
+  class TEXT NOT NULL,
+  active_sp TEXT NOT NULL,
+  extra_sps TEXT[] NOT NULL DEFAULT '{}',
+  is_default BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (user_id, name)
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_player_profiles_default ON player_profiles (user_id) WHERE is_default;
+
+-- Signup notes ("late 10 min") and the tentative status: a tentative player waits on the bench
+-- behind the other reserves and is only promoted when nobody else is left.
+ALTER TABLE raid_participants ADD COLUMN IF NOT EXISTS note TEXT NOT NULL DEFAULT '';
+ALTER TABLE raid_participants ADD COLUMN IF NOT EXISTS is_tentative BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Attendance marked by the raid owner from the panel sent when a raid ends (/attendance).
+-- attendance_requested makes sure the panel goes out once, also across restarts.
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS attendance_requested BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS raid_attendance (
+  raid_id UUID NOT NULL REFERENCES raids(id) ON DELETE CASCADE,
+  user_id BIGINT NOT NULL,
+  guild_id BIGINT NOT NULL,
+  status TEXT NOT NULL CHECK (status IN ('present', 'late', 'no_show')),
+  marked_by BIGINT NOT NULL,
+  marked_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (raid_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_attendance_user ON raid_attendance (guild_id, user_id, marked_at DESC);
+
+-- Reserve promotion order: 'join_order' (first come, first served) or 'reliability' (recent
+-- no-shows rank lower, long bench streaks rank higher). Raids use the guild's unless set.
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS promotion_policy TEXT NOT NULL DEFAULT 'join_order';
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS promotion_policy TEXT;
+
+-- Guild points (DKP). Every change is a row; a balance is the sum of the user's rows in the guild.
+-- source: manual = /points give|take, attendance = awarded when attendance is marked (one row per
+-- raid and player, rewritten when the mark changes).
+CREATE TABLE IF NOT EXISTS points_ledger (
+  id          BIGSERIAL PRIMARY KEY,
+  guild_id    BIGINT NOT NULL,
+  user_id     BIGINT NOT NULL,
+  amount      INT NOT NULL,
+  reason      TEXT NOT NULL DEFAULT '',
+  source      TEXT NOT NULL DEFAULT 'manual' CHECK (source IN ('manual','attendance')),
+  raid_id     UUID REFERENCES raids(id) ON DELETE SET NULL,
+  given_by    BIGINT NOT NULL,
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS idx_points_ledger_user ON points_ledger (guild_id, user_id, created_at DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS uq_points_ledger_attendance ON points_ledger (raid_id, user_id) WHERE source = 'attendance';
+
+-- Points awarded per attendance mark. raid_type '' = every raid type without a row of its own;
+-- a guild without rows awards nothing.
+CREATE TABLE IF NOT EXISTS attendance_points (
+  guild_id   BIGINT NOT NULL,
+  raid_type  TEXT NOT NULL DEFAULT '',
+  present    INT NOT NULL DEFAULT 0,
+  late       INT NOT NULL DEFAULT 0,
+  no_show    INT NOT NULL DEFAULT 0,
+  PRIMARY KEY (guild_id, raid_type)
+);
+
+-- Points a player needs for a main slot (0 = no requirement); below it they join as reserve.
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS min_points INT NOT NULL DEFAULT 0;
+
+-- Who received which drop (Manage panel "Log loot", /loot history). Kept after the raid's channel is gone.
+CREATE TABLE IF NOT EXISTS raid_loot (
+  id         BIGSERIAL PRIMARY KEY,
+  raid_id    UUID NOT NULL REFERENCES raids(id) ON DELETE CASCADE,
+  guild_id   BIGINT NOT NULL,
+  user_id    BIGINT NOT NULL,
+  item       TEXT NOT NULL,
+  logged_by  BIGINT NOT NULL,
+  logged_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS idx_raid_loot_guild_user ON raid_loot (guild_id, user_id, logged_at DESC);
+CREATE INDEX IF NOT EXISTS idx_raid_loot_raid ON raid_loot (raid_id);
+
+-- Set when the owner cancels (/raid_history tells cancelled raids from finished ones)
+ALTER TABLE raids ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS idx_raids_guild_scheduled ON raids (guild_id, scheduled_for DESC);